@@ -0,0 +1,177 @@
+// Goes back over a finished hand and points out where a seat's choice cost it.
+// Every option is played out from the same made up deals, so they're compared on equal luck.
+// The playouts are the hard bot on both sides until the deck runs out and perfect play after.
+use bevy::utils::HashMap;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::cards::*;
+use crate::rules::*;
+use crate::view::PlayerView;
+use super::belief::CardCounter;
+use super::bot::Bot;
+use super::endgame::{action_outcomes, solve, Outcome};
+use super::heuristic::{Difficulty, HeuristicBot};
+use super::ismcts::{determinize, Move};
+
+// Deals each option is played out over
+pub const ANALYSIS_SAMPLES: usize = 64;
+
+// Points a move has to give away before it is worth mentioning
+const MISTAKE_THRESHOLD: f64 = 100.;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum MistakeKind {
+    // Once the deck was empty, a win that couldn't be stopped
+    ThrewAwayWin,
+    MissedCoupFourre,
+    // Thrown away when it could have been kept or played
+    WastedSafety,
+    WeakerMove
+}
+
+#[derive(Debug, Clone)]
+pub struct Mistake {
+    // Counting every action in the hand from 1
+    pub move_number: usize,
+    pub seat: Seat,
+    pub kind: MistakeKind,
+    pub played: (SubType, Action),
+    pub better: (SubType, Action),
+    // Average points lost against the better move
+    pub cost: f64
+}
+
+impl Mistake {
+    pub fn describe(&self) -> String {
+        let verb = |(sub_type, action): (SubType, Action)| match action {
+            Action::Discard { .. } => format!("discarding {}", sub_type.name()),
+            Action::CoupFourre { .. } => format!("a coup fourré with {}", sub_type.name()),
+            Action::Play { .. } => format!("playing {}", sub_type.name()),
+        };
+        let what = match self.kind {
+            MistakeKind::ThrewAwayWin => "Threw away a forced win".to_string(),
+            MistakeKind::MissedCoupFourre => "Missed a coup fourré".to_string(),
+            MistakeKind::WastedSafety => format!("Wasted {}", self.played.0.name()),
+            MistakeKind::WeakerMove => format!("Chose {}", verb(self.played)),
+        };
+        format!("Move {}: {}, {} was about {:.0} points better",
+            self.move_number, what, verb(self.better), self.cost)
+    }
+}
+
+// How the hand stood once the deck ran out
+#[derive(Debug, Clone, Copy)]
+pub struct Endgame {
+    // The first move made with an empty deck
+    pub move_number: usize,
+    pub outcome: Outcome
+}
+
+// Solves the hand from the first move made after the deck ran out
+pub fn endgame(log: &[LoggedAction]) -> Option<Endgame> {
+    let (index, logged) = log.iter().enumerate().find(|(_, logged)| logged.before.deck.is_empty())?;
+    Some(Endgame { move_number: index + 1, outcome: solve(&logged.before)? })
+}
+
+// Average points margin for `view`'s seat after each distinct legal action
+pub fn evaluate(view: &PlayerView, samples: usize, rng: &mut StdRng) -> Vec<(Action, f64)> {
+    let mut options: HashMap<Move, Action> = HashMap::default();
+    for action in view.legal_actions() {
+        options.entry(Move::new(view.seat, &view.hand, action)).or_insert(action);
+    }
+    let mut options: Vec<Action> = options.into_values().collect();
+    options.sort_by_key(|action| view.legal_actions().iter().position(|legal| legal == action));
+
+    let mut totals = vec![0.; options.len()];
+    for _ in 0..samples {
+        let deal = determinize(view, &CardCounter::default(), rng);
+        let seed: u64 = rng.gen();
+
+        for (total, action) in totals.iter_mut().zip(&options) {
+            let mut game = deal.clone();
+            game.apply(view.seat, *action).unwrap();
+            game.next_turn();
+            *total += play_out(game, view.seat, seed) as f64;
+        }
+    }
+
+    options.into_iter()
+        .zip(totals)
+        .map(|(action, total)| (action, total / samples.max(1) as f64))
+        .collect()
+}
+
+// Both seats play the hand out as the hard bot until the deck is empty, when the rest is solved.
+// Returns the points margin for `seat`.
+fn play_out(mut game: Game, seat: Seat, seed: u64) -> i32 {
+    let mut bot = HeuristicBot::seeded(Difficulty::Hard, seed).without_endgame_solver();
+    while let Some(turn) = game.turn {
+        if let Some(outcome) = solve(&game) {
+            return outcome.margin_for(seat);
+        }
+        let Some(action) = bot.choose(&game.view(turn)) else { break };
+        game.apply(turn, action).unwrap();
+        game.next_turn();
+    }
+    game.hand_points(seat) - game.hand_points(seat.other())
+}
+
+// Looks at every action `seats` took in `log` and returns the ones worth a second look
+pub fn analyse_hand(log: &[LoggedAction], seats: &[Seat], samples: usize, seed: u64) -> Vec<Mistake> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut mistakes = Vec::new();
+
+    for (index, logged) in log.iter().enumerate() {
+        if !seats.contains(&logged.seat) {
+            continue;
+        }
+
+        let view = logged.before.view(logged.seat);
+        let sub_type_of = |action: &Action| view.hand.iter().find(|card| card.entity == action.card()).map(|card| card.sub_type);
+        let Some(played_type) = sub_type_of(&logged.action) else { continue };
+
+        // With the deck empty the whole hand is known, so the values are exact
+        let exact = action_outcomes(&logged.before);
+        let values = match &exact {
+            Some(outcomes) => outcomes.iter().map(|(action, outcome)| (*action, outcome.margin_for(logged.seat) as f64)).collect(),
+            None => evaluate(&view, samples, &mut rng),
+        };
+        let played = Move::new(view.seat, &view.hand, logged.action);
+        let Some(played_value) = values.iter()
+            .find(|(action, _)| Move::new(view.seat, &view.hand, *action) == played)
+            .map(|(_, value)| *value) else { continue };
+        let Some((best, best_value)) = values.iter().copied().max_by(|(_, a), (_, b)| a.total_cmp(b)) else { continue };
+
+        // A seat which could force its trip home with the deck empty but didn't
+        let winning = exact.as_ref().and_then(|outcomes| outcomes.iter()
+            .filter(|(_, outcome)| outcome.winner == Some(logged.seat))
+            .max_by_key(|(_, outcome)| outcome.margin_for(logged.seat)));
+        let played_wins = exact.as_ref().is_some_and(|outcomes| outcomes.iter()
+            .any(|(action, outcome)| Move::new(view.seat, &view.hand, *action) == played && outcome.winner == Some(logged.seat)));
+        let threw_away = winning.filter(|_| !played_wins);
+
+        let coup_fourre = values.iter().find(|(action, _)| matches!(action, Action::CoupFourre { .. }));
+        let (kind, better, better_value) = match (threw_away, coup_fourre) {
+            (Some((action, outcome)), _) => (MistakeKind::ThrewAwayWin, *action, outcome.margin_for(logged.seat) as f64),
+            (_, Some((action, value))) if !matches!(logged.action, Action::CoupFourre { .. }) => (MistakeKind::MissedCoupFourre, *action, *value),
+            _ if matches!(logged.action, Action::Discard { .. }) && played_type.card_type() == CardType::Safety
+                && Move::new(view.seat, &view.hand, best) != played => {
+                (MistakeKind::WastedSafety, best, best_value)
+            }
+            _ if best_value - played_value >= MISTAKE_THRESHOLD => (MistakeKind::WeakerMove, best, best_value),
+            _ => continue,
+        };
+
+        let Some(better_type) = sub_type_of(&better) else { continue };
+        mistakes.push(Mistake {
+            move_number: index + 1,
+            seat: logged.seat,
+            kind,
+            played: (played_type, logged.action),
+            better: (better_type, better),
+            cost: (better_value - played_value).max(0.)
+        });
+    }
+
+    mistakes
+}
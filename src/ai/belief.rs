@@ -0,0 +1,92 @@
+use bevy::utils::HashMap;
+use crate::cards::*;
+use crate::events::*;
+use crate::view::PlayerView;
+
+// Every card type's count is public, so whatever a seat hasn't seen is somewhere in the deck or
+// the other hand. On top of that a seat which passes up a coup fourré can't have been holding the safety.
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Declined {
+    // Cards drawn by anyone since, and by the seat which declined
+    draws: usize,
+    holder_draws: usize
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CardCounter {
+    // A hazard which has just been played on a seat, along with the safety that would answer it
+    pending: Option<(Seat, SubType)>,
+    declined: HashMap<(Seat, SubType), Declined>
+}
+
+impl CardCounter {
+    // Feed in every game event in the order they happened
+    pub fn observe(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::CardPlayed(played) => self.answered(played.seat, Some(played.sub_type)),
+            GameEvent::CardDiscarded(discarded) => self.answered(discarded.seat, None),
+            // Always follows the `CardPlayed` for the hazard itself
+            GameEvent::HazardApplied(applied) => {
+                self.pending = applied.hazard.safety().map(|safety| (applied.target, safety));
+            }
+            GameEvent::CardDrawn(drawn) => {
+                for ((seat, _), declined) in self.declined.iter_mut() {
+                    declined.draws += 1;
+                    if *seat == drawn.seat {
+                        declined.holder_draws += 1;
+                    }
+                }
+            }
+            GameEvent::HandEnded(_) => *self = Self::default(),
+            _ => {}
+        }
+    }
+
+    // Only the very next card can be a coup fourré
+    fn answered(&mut self, seat: Seat, played: Option<SubType>) {
+        let Some((target, safety)) = self.pending.take() else { return };
+
+        if seat == target && played != Some(safety) {
+            self.declined.insert((target, safety), Declined::default());
+        }
+    }
+
+    // How many of each card type `view`'s seat hasn't seen yet
+    pub fn remaining(&self, view: &PlayerView) -> Vec<(SubType, i32)> {
+        let unseen = view.unseen();
+        DECK_COMPOSITION.iter()
+            .map(|(sub_type, _)| (*sub_type, unseen.iter().filter(|unseen| *unseen == sub_type).count() as i32))
+            .collect()
+    }
+
+    // The chance the other seat is holding at least one `sub_type`, as far as `view`'s seat can tell
+    pub fn hand_chance(&self, view: &PlayerView, sub_type: SubType) -> f32 {
+        let holder = view.seat.other();
+        let unseen = view.unseen();
+        let copies = unseen.iter().filter(|unseen| **unseen == sub_type).count();
+        let hand = view.opponent_hand_size;
+        if copies == 0 || hand == 0 {
+            return 0.;
+        }
+
+        // When they passed it up the card was in the deck, so they can only have drawn it since
+        if let Some(declined) = self.declined.get(&(holder, sub_type)) {
+            let deck_then = view.deck_size + declined.draws;
+            return (declined.holder_draws as f32 / deck_then.max(1) as f32).min(1.);
+        }
+
+        // One minus the chance that none of the copies were dealt into a hand of this size
+        let total = unseen.len();
+        let mut none = 1.;
+        for drawn in 0..hand {
+            none *= (total - copies).saturating_sub(drawn) as f32 / (total - drawn) as f32;
+        }
+        1. - none
+    }
+
+    // Whether `seat` is known not to have held `safety` when it last could have answered with it
+    pub fn has_declined(&self, seat: Seat, safety: SubType) -> bool {
+        self.declined.contains_key(&(seat, safety))
+    }
+}
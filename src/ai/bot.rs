@@ -0,0 +1,232 @@
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task};
+use futures_lite::future;
+use crate::cards::Seat;
+use crate::constants::*;
+use crate::events::{GameEvent, GameEventReaders, PlayRequest};
+use crate::millebornes::TurnSet;
+use crate::rules::{Action, Game};
+use crate::view::PlayerView;
+use super::heuristic::Difficulty;
+use super::personality::{Personalities, PERSONALITIES_FILE};
+
+// Anything that can take a seat's turn. Bots only ever get the seat's `PlayerView`.
+pub trait Bot: Send {
+    // `None` if there is nothing the seat can do
+    fn choose(&mut self, view: &PlayerView) -> Option<Action>;
+
+    // Every game event as the bot's seat saw it, for bots which keep track of the hand as it goes
+    fn observe(&mut self, _event: &GameEvent) {}
+
+    // Set while a move is no longer wanted, bots which think for a while should give up when it is
+    fn cancel_with(&mut self, _cancelled: Arc<AtomicBool>) {}
+}
+
+pub enum Controller {
+    Human,
+    Computer(ComputerSeat),
+    // Somebody playing from another machine, whose moves arrive from the server
+    Remote
+}
+impl Controller {
+    pub fn computer(mut bot: Box<dyn Bot>) -> Self {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        bot.cancel_with(cancelled.clone());
+        Controller::Computer(ComputerSeat { bot: Arc::new(Mutex::new(bot)), missed: Vec::new(), cancelled })
+    }
+}
+
+// A bot which can be lent to a task while it thinks about its move
+pub struct ComputerSeat {
+    bot: Arc<Mutex<Box<dyn Bot>>>,
+    // Events which arrived while the bot was busy
+    missed: Vec<GameEvent>,
+    // Tells a bot which is still thinking that its move won't be played
+    cancelled: Arc<AtomicBool>
+}
+impl ComputerSeat {
+    fn observe(&mut self, event: &GameEvent) {
+        self.missed.push(*event);
+        self.catch_up();
+    }
+
+    fn catch_up(&mut self) {
+        if let Ok(mut bot) = self.bot.try_lock() {
+            for event in self.missed.drain(..) {
+                bot.observe(&event);
+            }
+        }
+    }
+}
+
+// Who is sitting in each seat
+#[derive(Resource)]
+pub struct Seats {
+    pub player: Controller,
+    pub opponent: Controller
+}
+impl Default for Seats {
+    fn default() -> Self {
+        Self {
+            player: Controller::Human,
+            opponent: Controller::computer(Difficulty::default().bot())
+        }
+    }
+}
+
+impl Seats {
+    pub fn controller(&self, seat: Seat) -> &Controller {
+        match seat {
+            Seat::Player => &self.player,
+            Seat::Opponent => &self.opponent,
+        }
+    }
+
+    pub fn controller_mut(&mut self, seat: Seat) -> &mut Controller {
+        match seat {
+            Seat::Player => &mut self.player,
+            Seat::Opponent => &mut self.opponent,
+        }
+    }
+
+    pub fn is_human(&self, seat: Seat) -> bool {
+        matches!(self.controller(seat), Controller::Human)
+    }
+
+    pub fn is_remote(&self, seat: Seat) -> bool {
+        matches!(self.controller(seat), Controller::Remote)
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct BotSettings {
+    // Seconds a bot waits before playing so people can follow what it did
+    pub delay: f32
+}
+impl Default for BotSettings {
+    fn default() -> Self {
+        Self { delay: 0.8 }
+    }
+}
+
+#[derive(Resource, Default)]
+struct BotTimer(Timer);
+
+// The move a bot is working on, off the main thread so searching doesn't stall rendering
+#[derive(Resource, Default)]
+pub struct BotThinking {
+    seat: Option<Seat>,
+    task: Option<Task<Option<Action>>>,
+    chosen: Option<Option<Action>>
+}
+impl BotThinking {
+    // The seat whose bot is still deciding, or waiting out its delay
+    pub fn seat(&self) -> Option<Seat> {
+        self.seat
+    }
+}
+
+/*******************
+ * COMPUTER PLAYERS
+ *******************/
+
+// Plays the turns of any seat which isn't `Controller::Human`
+pub struct ComputerPlayers;
+impl Plugin for ComputerPlayers {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Seats>()
+            .init_resource::<BotSettings>()
+            .init_resource::<BotTimer>()
+            .init_resource::<BotThinking>()
+            .insert_resource(Personalities::load_or_default(PERSONALITIES_FILE))
+            .add_systems(
+                OnEnter(GameState::DuringTurn),
+                start_bot_turn
+            )
+            .add_systems(
+                OnEnter(GameState::Menu),
+                cancel_bot_turn
+            )
+            .add_systems(
+                Update, (
+                    show_bots_events,
+                    take_bot_turn.run_if(in_state(GameState::DuringTurn))
+                ).chain().in_set(TurnSet::Input)
+            );
+    }
+}
+
+fn start_bot_turn(settings: Res<BotSettings>, mut timer: ResMut<BotTimer>, mut thinking: ResMut<BotThinking>) {
+    timer.0 = Timer::from_seconds(settings.delay.max(0.), TimerMode::Once);
+    *thinking = BotThinking::default();
+}
+
+// Dropping the task only stops us waiting for it, the bot has to be told to stop too
+fn cancel_bot_turn(mut thinking: ResMut<BotThinking>, seats: Res<Seats>) {
+    *thinking = BotThinking::default();
+    for seat in [Seat::Player, Seat::Opponent] {
+        if let Controller::Computer(computer) = seats.controller(seat) {
+            computer.cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+fn show_bots_events(mut events: GameEventReaders, mut seats: ResMut<Seats>) {
+    for event in events.read() {
+        for seat in [Seat::Player, Seat::Opponent] {
+            if let Controller::Computer(computer) = seats.controller_mut(seat) {
+                computer.observe(&event.seen_by(Some(seat)));
+            }
+        }
+    }
+}
+
+// Hand the bot to a task at the start of its turn, then wait for both its move and the delay
+fn take_bot_turn(time: Res<Time>,
+                 mut timer: ResMut<BotTimer>,
+                 mut thinking: ResMut<BotThinking>,
+                 mut seats: ResMut<Seats>,
+                 game: Res<Game>,
+                 mut requests: EventWriter<PlayRequest>)
+{
+    let Some(seat) = game.turn else { return };
+    let Controller::Computer(computer) = seats.controller_mut(seat) else { return };
+
+    if thinking.seat.is_none() {
+        computer.catch_up();
+        // A new flag for each move, so calling this one off can't be missed however long the bot
+        // takes to finish the last, and the last still sees that it was called off
+        computer.cancelled = Arc::new(AtomicBool::new(false));
+        let bot = computer.bot.clone();
+        let cancelled = computer.cancelled.clone();
+        let view = game.view(seat);
+
+        thinking.seat = Some(seat);
+        thinking.task = Some(AsyncComputeTaskPool::get().spawn(async move {
+            let mut bot = bot.lock().unwrap();
+            bot.cancel_with(cancelled);
+            bot.choose(&view)
+        }));
+    }
+
+    if let Some(task) = thinking.task.as_mut() {
+        if let Some(chosen) = future::block_on(future::poll_once(task)) {
+            thinking.chosen = Some(chosen);
+            thinking.task = None;
+        }
+    }
+
+    if !timer.0.tick(time.delta()).finished() {
+        return;
+    }
+
+    if let Some(chosen) = thinking.chosen.take() {
+        thinking.seat = None;
+        if let Some(action) = chosen {
+            requests.send(PlayRequest { seat, action });
+        }
+    }
+}
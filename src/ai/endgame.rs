@@ -0,0 +1,207 @@
+// Exact play once the draw pile is empty. Every card not in our hand is face up
+// by then, so both hands are known and the rest of the hand can be searched to the end.
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use crate::cards::*;
+use crate::rules::*;
+use crate::view::PlayerView;
+use super::ismcts::Move;
+
+// Positions searched before giving up, the deck only runs out with a handful of cards
+// left in each hand so this is rarely reached
+pub const SOLVER_NODE_LIMIT: usize = 500_000;
+
+// Hidden cards are given entities well clear of the real ones
+const KNOWN_CARD_BASE: u32 = 1_000_000;
+
+// How the hand ends with both seats playing perfectly
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Outcome {
+    // The player's hand points less the opponent's
+    pub margin: i32,
+    // Who completes their trip, `None` if neither can
+    pub winner: Option<Seat>
+}
+impl Outcome {
+    pub fn margin_for(&self, seat: Seat) -> i32 {
+        match seat {
+            Seat::Player => self.margin,
+            Seat::Opponent => -self.margin,
+        }
+    }
+}
+
+// Everything the rest of the hand depends on, with the cards themselves left out
+// so positions reached in different orders are only searched once
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+struct Position {
+    hands: [Vec<SubType>; 2],
+    tableaus: [TableauSummary; 2],
+    turn: Option<Seat>,
+    extra_turn: bool,
+    last_hazard: Option<(Seat, SubType)>
+}
+
+// The battle and speed piles are kept whole, a safety uncovers whatever is under the hazard it clears
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+struct TableauSummary {
+    battle: Vec<SubType>,
+    speed: Vec<SubType>,
+    safeties: Vec<SubType>,
+    miles: i32,
+    two_hundreds: usize,
+    coup_fourres: i32
+}
+
+fn sorted(cards: &[PileCard]) -> Vec<SubType> {
+    let mut sub_types: Vec<SubType> = cards.iter().map(|card| card.sub_type).collect();
+    sub_types.sort_by_key(|sub_type| *sub_type as u8);
+    sub_types
+}
+
+impl Position {
+    fn of(game: &Game) -> Self {
+        let summary = |tableau: &Tableau| TableauSummary {
+            battle: tableau.battle.iter().map(|card| card.sub_type).collect(),
+            speed: tableau.speed.iter().map(|card| card.sub_type).collect(),
+            safeties: sorted(&tableau.safeties),
+            miles: tableau.miles(),
+            two_hundreds: tableau.two_hundreds(),
+            coup_fourres: tableau.coup_fourres
+        };
+        Self {
+            hands: [sorted(&game.player_hand), sorted(&game.opponent_hand)],
+            tableaus: [summary(&game.player), summary(&game.opponent)],
+            turn: game.turn,
+            extra_turn: game.extra_turn,
+            last_hazard: game.last_hazard.map(|(seat, card)| (seat, card.sub_type))
+        }
+    }
+}
+
+struct Solver {
+    memo: HashMap<Position, Outcome>,
+    nodes: usize
+}
+
+impl Solver {
+    fn new() -> Self {
+        Self { memo: HashMap::default(), nodes: 0 }
+    }
+
+    // `None` if the search ran out of nodes
+    fn outcome(&mut self, game: &Game) -> Option<Outcome> {
+        let Some(seat) = game.turn else {
+            let margin = game.hand_points(Seat::Player) - game.hand_points(Seat::Opponent);
+            return Some(Outcome { margin, winner: game.winner() });
+        };
+
+        let position = Position::of(game);
+        if let Some(outcome) = self.memo.get(&position) {
+            return Some(*outcome);
+        }
+
+        self.nodes += 1;
+        if self.nodes > SOLVER_NODE_LIMIT {
+            return None;
+        }
+
+        let mut best: Option<Outcome> = None;
+        for (_, outcome) in self.action_outcomes(game)? {
+            let better = best.is_none_or(|best| outcome.margin_for(seat) > best.margin_for(seat));
+            if better {
+                best = Some(outcome);
+            }
+        }
+
+        // A seat with nothing it can do only happens once both hands are empty
+        let outcome = best.unwrap_or(Outcome {
+            margin: game.hand_points(Seat::Player) - game.hand_points(Seat::Opponent),
+            winner: game.winner()
+        });
+        self.memo.insert(position, outcome);
+        Some(outcome)
+    }
+
+    // Each distinct action for the seat to move. Cards of the same type lead to the
+    // same position, so only the first of them is tried.
+    fn action_outcomes(&mut self, game: &Game) -> Option<Vec<(Action, Outcome)>> {
+        let Some(seat) = game.turn else { return Some(Vec::new()) };
+        let hand = game.hand(seat);
+
+        let mut tried = Vec::new();
+        let mut outcomes = Vec::new();
+        for action in game.legal_actions(seat) {
+            let mv = Move::new(seat, hand, action);
+            if tried.contains(&mv) {
+                continue;
+            }
+            tried.push(mv);
+
+            let mut next = game.clone();
+            next.apply(seat, action).unwrap();
+            next.next_turn();
+            outcomes.push((action, self.outcome(&next)?));
+        }
+        Some(outcomes)
+    }
+}
+
+// The outcome of `game` with perfect play. `None` while there are still cards
+// to draw, or if the position is too big to search.
+pub fn solve(game: &Game) -> Option<Outcome> {
+    if !game.deck.is_empty() {
+        return None;
+    }
+    Solver::new().outcome(game)
+}
+
+// The outcome after each distinct action open to the seat to move, in the order
+// `Game::legal_actions` gives them
+pub fn action_outcomes(game: &Game) -> Option<Vec<(Action, Outcome)>> {
+    if !game.deck.is_empty() {
+        return None;
+    }
+    Solver::new().action_outcomes(game)
+}
+
+// The best action for the seat to move. Ties go to the first action offered.
+pub fn best_action(game: &Game) -> Option<(Action, Outcome)> {
+    let seat = game.turn?;
+    let mut best: Option<(Action, Outcome)> = None;
+    for (action, outcome) in action_outcomes(game)? {
+        if best.is_none_or(|(_, best)| outcome.margin_for(seat) > best.margin_for(seat)) {
+            best = Some((action, outcome));
+        }
+    }
+    best
+}
+
+// The whole game as `view`'s seat can work it out once the deck is empty:
+// the other hand is whatever hasn't been seen
+pub fn known_game(view: &PlayerView) -> Option<Game> {
+    if view.deck_size > 0 {
+        return None;
+    }
+
+    let other_hand: Vec<PileCard> = view.unseen().into_iter()
+        .enumerate()
+        .map(|(index, sub_type)| PileCard { entity: Entity::from_raw(KNOWN_CARD_BASE + index as u32), sub_type })
+        .collect();
+    if other_hand.len() != view.opponent_hand_size {
+        return None;
+    }
+
+    let mut game = Game {
+        discard: view.discard.clone(),
+        player: view.player.clone(),
+        opponent: view.opponent.clone(),
+        turn: view.turn,
+        extra_turn: view.extra_turn,
+        last_hazard: view.last_hazard,
+        ..default()
+    };
+    *game.hand_mut(view.seat) = view.hand.clone();
+    *game.hand_mut(view.seat.other()) = other_hand;
+    Some(game)
+}
@@ -0,0 +1,231 @@
+// Bots written outside this codebase, run as a separate process which talks JSON lines.
+//
+// Each line we write to its stdin is one of:
+//   {"type":"event","event":{...}}                              every game event, as it happens
+//   {"type":"turn","id":7,"view":{...},"legal_actions":[...]}   its seat is to move
+// and it answers each turn with one line on its stdout:
+//   {"id":7,"action":{...}}                                     one of the legal actions, as given
+//
+// Cards are identified by the numbers in the view. Anything it writes to stderr is left alone.
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::events::GameEvent;
+use crate::rules::Action;
+use crate::view::PlayerView;
+use super::bot::Bot;
+use super::random::RandomBot;
+
+// How long the process gets to answer before we move for it
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Bad answers in a row before we stop asking and play the rest of the game ourselves
+const MAX_STRIKES: u32 = 3;
+
+// How often a wait for an answer checks whether the move is still wanted
+const CANCEL_CHECK: Duration = Duration::from_millis(20);
+
+// The command to run for the external seat, set from the command line
+#[derive(Resource, Debug, Clone)]
+pub struct ExternalBotCommand {
+    pub command: String,
+    pub timeout: Duration
+}
+impl ExternalBotCommand {
+    pub fn bot(&self) -> Box<dyn Bot> {
+        Box::new(ExternalBot::new(&self.command, self.timeout))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ToBot<'a> {
+    Event { event: &'a GameEvent },
+    Turn { id: u32, view: &'a PlayerView, legal_actions: &'a [Action] }
+}
+
+#[derive(Deserialize)]
+struct FromBot {
+    id: u32,
+    action: Action
+}
+
+struct Process {
+    child: Child,
+    stdin: ChildStdin,
+    // Lines from its stdout, read on their own thread so we can stop waiting
+    replies: Receiver<String>
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+pub struct ExternalBot {
+    command: String,
+    timeout: Duration,
+    // Started on first use, and dropped for good once it has misbehaved too often
+    process: Option<Process>,
+    started: bool,
+    turn: u32,
+    strikes: u32,
+    // Moves for the process when it can't
+    fallback: RandomBot,
+    cancelled: Arc<AtomicBool>
+}
+
+impl ExternalBot {
+    // `command` is split on whitespace, the first word is the program
+    pub fn new(command: &str, timeout: Duration) -> Self {
+        Self {
+            command: command.to_string(),
+            timeout,
+            process: None,
+            started: false,
+            turn: 0,
+            strikes: 0,
+            fallback: RandomBot::new(),
+            cancelled: Arc::default()
+        }
+    }
+
+    // The same, with the moves made for it repeatable
+    pub fn seeded(command: &str, timeout: Duration, seed: u64) -> Self {
+        Self { fallback: RandomBot::seeded(seed), ..Self::new(command, timeout) }
+    }
+
+    fn process(&mut self) -> Option<&mut Process> {
+        if !self.started {
+            self.started = true;
+            self.process = self.spawn();
+        }
+        self.process.as_mut()
+    }
+
+    fn spawn(&self) -> Option<Process> {
+        let mut words = self.command.split_whitespace();
+        let program = words.next()?;
+        let spawned = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn();
+
+        let mut child = match spawned {
+            Ok(child) => child,
+            Err(error) => {
+                warn!("Couldn't start external bot {:?}: {}", self.command, error);
+                return None;
+            }
+        };
+
+        let stdin = child.stdin.take()?;
+        let stdout = child.stdout.take()?;
+        let (sender, replies) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Some(Process { child, stdin, replies })
+    }
+
+    fn send(&mut self, message: &ToBot) -> bool {
+        let Ok(line) = serde_json::to_string(message) else { return false };
+        let Some(process) = self.process() else { return false };
+
+        if writeln!(process.stdin, "{}", line).and_then(|_| process.stdin.flush()).is_err() {
+            warn!("External bot {:?} has gone away", self.command);
+            self.process = None;
+            return false;
+        }
+        true
+    }
+
+    // Waits for the answer to turn `id`, skipping any late answers to turns we've already played.
+    // `Ok(None)` if the move stopped being wanted first.
+    fn receive(&mut self, id: u32) -> Result<Option<Action>, String> {
+        let Some(process) = self.process.as_mut() else { return Err("not running".into()) };
+        let deadline = Instant::now() + self.timeout;
+
+        loop {
+            if self.cancelled.load(Ordering::Relaxed) {
+                return Ok(None);
+            }
+            let wait = deadline.saturating_duration_since(Instant::now()).min(CANCEL_CHECK);
+            let line = match process.replies.recv_timeout(wait) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) if Instant::now() < deadline => continue,
+                Err(RecvTimeoutError::Timeout) => return Err("timed out".into()),
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.process = None;
+                    return Err("exited".into());
+                }
+            };
+
+            match serde_json::from_str::<FromBot>(&line) {
+                Ok(reply) if reply.id == id => return Ok(Some(reply.action)),
+                Ok(_) => continue,
+                Err(error) => return Err(format!("sent {:?}: {}", line, error)),
+            }
+        }
+    }
+
+    fn strike(&mut self, reason: &str) {
+        self.strikes += 1;
+        warn!("External bot {:?} {}, moving for it", self.command, reason);
+
+        if self.strikes >= MAX_STRIKES && self.process.is_some() {
+            warn!("Giving up on external bot {:?}", self.command);
+            self.process = None;
+        }
+    }
+}
+
+impl Bot for ExternalBot {
+    fn choose(&mut self, view: &PlayerView) -> Option<Action> {
+        let legal_actions = view.legal_actions();
+        if legal_actions.is_empty() {
+            return None;
+        }
+
+        self.turn += 1;
+        let id = self.turn;
+        if self.send(&ToBot::Turn { id, view, legal_actions: &legal_actions }) {
+            match self.receive(id) {
+                Ok(None) => return None,
+                Ok(Some(action)) if legal_actions.contains(&action) => {
+                    self.strikes = 0;
+                    return Some(action);
+                }
+                Ok(Some(action)) => self.strike(&format!("chose an illegal action {:?}", action)),
+                Err(reason) => self.strike(&reason),
+            }
+        }
+
+        self.fallback.choose(view)
+    }
+
+    fn observe(&mut self, event: &GameEvent) {
+        if self.process.is_some() || !self.started {
+            self.send(&ToBot::Event { event });
+        }
+    }
+
+    fn cancel_with(&mut self, cancelled: Arc<AtomicBool>) {
+        self.cancelled = cancelled;
+    }
+}
@@ -0,0 +1,259 @@
+use std::time::Duration;
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::cards::*;
+use crate::rules::*;
+use crate::view::PlayerView;
+use crate::events::GameEvent;
+use super::belief::CardCounter;
+use super::bot::Bot;
+use super::endgame::{best_action, known_game};
+use super::ismcts::{Budget, IsmctsBot};
+use super::personality::Personality;
+
+// How hard the computer opponent tries
+#[derive(Resource, Debug, Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Medium,
+    Hard,
+    // Searches instead of following the heuristics
+    Expert
+}
+impl Difficulty {
+    pub fn next(&self) -> Difficulty {
+        match self {
+            Difficulty::Easy => Difficulty::Medium,
+            Difficulty::Medium => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Expert,
+            Difficulty::Expert => Difficulty::Easy,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Medium => "Medium",
+            Difficulty::Hard => "Hard",
+            Difficulty::Expert => "Expert",
+        }
+    }
+
+    pub fn bot(&self) -> Box<dyn Bot> {
+        self.bot_with(&Personality::default())
+    }
+
+    pub fn bot_with(&self, personality: &Personality) -> Box<dyn Bot> {
+        match self {
+            Difficulty::Expert => Box::new(IsmctsBot::new(Budget::Time(EXPERT_THINKING_TIME)).with_personality(personality.clone())),
+            _ => Box::new(HeuristicBot::new(*self).with_personality(personality.clone())),
+        }
+    }
+
+    // Chance of ignoring the heuristics and playing anything legal
+    fn blunder_chance(&self) -> f64 {
+        match self {
+            Difficulty::Easy => 0.4,
+            Difficulty::Medium => 0.1,
+            Difficulty::Hard | Difficulty::Expert => 0.,
+        }
+    }
+}
+
+const EXPERT_THINKING_TIME: Duration = Duration::from_millis(500);
+
+const HAZARDS: [SubType; 5] = [SubType::Accident, SubType::OutOfGas, SubType::FlatTyre, SubType::SpeedLimit, SubType::Stop];
+
+// Once the deck is this small a held safety might never get its coup fourré
+pub const HOARD_UNTIL_DECK: usize = 6;
+
+// Scores every legal action with a few rules of thumb and plays the best one
+pub struct HeuristicBot {
+    difficulty: Difficulty,
+    rng: StdRng,
+    counter: CardCounter,
+    personality: Personality,
+    // Search the hand out exactly once the deck is empty
+    solve_endgame: bool
+}
+
+impl HeuristicBot {
+    pub fn new(difficulty: Difficulty) -> Self {
+        Self::with_rng(difficulty, StdRng::from_entropy(), CardCounter::default())
+    }
+
+    pub fn seeded(difficulty: Difficulty, seed: u64) -> Self {
+        Self::with_rng(difficulty, StdRng::seed_from_u64(seed), CardCounter::default())
+    }
+
+    // Starts from what someone else has already worked out about the hand
+    pub fn with_counter(difficulty: Difficulty, counter: CardCounter) -> Self {
+        Self::with_rng(difficulty, StdRng::from_entropy(), counter)
+    }
+
+    fn with_rng(difficulty: Difficulty, rng: StdRng, counter: CardCounter) -> Self {
+        Self {
+            difficulty,
+            rng,
+            counter,
+            personality: Personality::default(),
+            solve_endgame: difficulty >= Difficulty::Hard
+        }
+    }
+
+    // Plays in a given style, the difficulty still decides how carefully
+    pub fn with_personality(mut self, personality: Personality) -> Self {
+        self.personality = personality;
+        self
+    }
+
+    // Playouts run the bot many times over, where the search would cost too much
+    pub fn without_endgame_solver(mut self) -> Self {
+        self.solve_endgame = false;
+        self
+    }
+
+    // Whether to keep safeties back for a coup fourré. Only the harder bots think of it
+    // as a rule, the easier ones only when their personality is keener on it than usual.
+    fn hoards(&self, view: &PlayerView) -> bool {
+        let hoarding = if self.difficulty >= Difficulty::Hard { self.personality.hoarding } else { self.personality.hoarding - 1. };
+        hoarding > 0. && view.deck_size as f32 > HOARD_UNTIL_DECK as f32 / hoarding
+    }
+
+    // The highest scoring action, without any blunders.
+    // Ties go to the first action offered, so plays beat discards.
+    pub fn best(&self, view: &PlayerView) -> Option<Action> {
+        let actions = view.legal_actions();
+        let mut best = *actions.first()?;
+
+        if self.solve_endgame {
+            if let Some((action, _)) = known_game(view).as_ref().and_then(best_action) {
+                return Some(action);
+            }
+        }
+
+        let mut best_score = self.score(view, &best);
+        for action in &actions[1..] {
+            let score = self.score(view, action);
+            if score > best_score {
+                best = *action;
+                best_score = score;
+            }
+        }
+
+        Some(best)
+    }
+
+    fn score(&self, view: &PlayerView, action: &Action) -> i32 {
+        let Some(card) = view.hand.iter().find(|card| card.entity == action.card()) else { return i32::MIN };
+        let sub_type = card.sub_type;
+        let mine = view.tableau(view.seat);
+        let theirs = view.tableau(view.seat.other());
+
+        match *action {
+            Action::CoupFourre { .. } => 1000,
+            Action::Discard { .. } => -usefulness(view, sub_type),
+            Action::Play { .. } => match sub_type.card_type() {
+                CardType::Safety => {
+                    let answers_hazard = mine.battle_top().safety() == Some(sub_type)
+                        || mine.speed_top().safety() == Some(sub_type);
+
+                    if answers_hazard {
+                        900
+                    }
+                    // Held back for a coup fourré, but still better than throwing away a useful card
+                    else if self.hoards(view) {
+                        -3
+                    }
+                    else {
+                        300
+                    }
+                }
+                CardType::Remedy if sub_type == SubType::EndOfLimit => 400,
+                // Right of Way already keeps us rolling
+                CardType::Remedy if sub_type == SubType::Roll && mine.is_rolling() => 50,
+                CardType::Remedy => 900,
+                CardType::Distance => {
+                    if mine.miles() + sub_type.miles() == mine.target_miles {
+                        950
+                    }
+                    // A 200 gives up the safe trip bonus
+                    else if sub_type == SubType::TwoHundred {
+                        200 + (200. * self.personality.risk) as i32
+                    }
+                    else {
+                        200 + sub_type.miles()
+                    }
+                }
+                CardType::Hazard => {
+                    let leading = theirs.miles() >= mine.miles();
+                    let stops = sub_type != SubType::SpeedLimit;
+
+                    let score = match (stops && theirs.is_rolling() && leading, self.difficulty) {
+                        (true, Difficulty::Easy) => 600,
+                        (true, _) => 850,
+                        (false, _) if stops => 600,
+                        // A limit only hurts someone with long distance cards still to play
+                        (false, _) => 250 + theirs.miles() / 10,
+                    };
+                    // Never so keen that it passes up a coup fourré
+                    let score = (score as f32 * self.personality.aggression).min(990.) as i32;
+
+                    // Walking into a coup fourré hands them the points and another turn
+                    if self.difficulty >= Difficulty::Hard {
+                        let chance = sub_type.safety().map_or(0., |safety| self.counter.hand_chance(view, safety));
+                        score - (400. * chance) as i32
+                    }
+                    else {
+                        score
+                    }
+                }
+            },
+        }
+    }
+}
+
+// How much a card in hand is worth keeping, dead cards are worth nothing
+pub fn usefulness(view: &PlayerView, sub_type: SubType) -> i32 {
+    let mine = view.tableau(view.seat);
+    let theirs = view.tableau(view.seat.other());
+
+    match sub_type.card_type() {
+        CardType::Safety => 100,
+        CardType::Distance => {
+            let overshoots = mine.miles() + sub_type.miles() > mine.target_miles;
+            let too_many_200s = sub_type == SubType::TwoHundred && mine.two_hundreds() >= 2;
+            if overshoots || too_many_200s { 0 } else { sub_type.miles() / 5 }
+        }
+        CardType::Hazard => {
+            if theirs.is_protected_from(sub_type) { 0 } else { 30 }
+        }
+        CardType::Remedy => {
+            // Once we hold the safety the hazard this remedy clears can't be played on us
+            let covered = HAZARDS.iter().any(|hazard| hazard.remedy() == Some(sub_type) && mine.is_protected_from(*hazard));
+            let spare = view.hand.iter().filter(|card| card.sub_type == sub_type).count() > 2;
+            if covered { 0 } else if spare { 10 } else { 40 }
+        }
+    }
+}
+
+impl Bot for HeuristicBot {
+    fn choose(&mut self, view: &PlayerView) -> Option<Action> {
+        let actions = view.legal_actions();
+        if actions.is_empty() {
+            return None;
+        }
+
+        if self.rng.gen_bool(self.difficulty.blunder_chance()) {
+            return Some(actions[self.rng.gen_range(0..actions.len())]);
+        }
+
+        self.best(view)
+    }
+
+    fn observe(&mut self, event: &GameEvent) {
+        self.counter.observe(event);
+    }
+}
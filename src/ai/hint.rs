@@ -0,0 +1,75 @@
+// Suggests a move for a human seat, with a line saying why
+use crate::cards::*;
+use crate::rules::Action;
+use crate::view::PlayerView;
+use super::belief::CardCounter;
+use super::endgame::{best_action, known_game, Outcome};
+use super::heuristic::{usefulness, Difficulty, HeuristicBot, HOARD_UNTIL_DECK};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hint {
+    pub action: Action,
+    pub explanation: String
+}
+
+// The move the hard bot would make, using what `counter` has worked out about the other hand.
+// Once the deck is empty that is the solver's move, which can take a while to find.
+pub fn hint(view: &PlayerView, counter: &CardCounter) -> Option<Hint> {
+    if let Some((action, outcome)) = known_game(view).as_ref().and_then(best_action) {
+        return Some(Hint { action, explanation: explain_solved(view, &action, &outcome)? });
+    }
+
+    let action = HeuristicBot::with_counter(Difficulty::Hard, counter.clone()).without_endgame_solver().best(view)?;
+    Some(Hint { action, explanation: explain(view, &action)? })
+}
+
+// The solver has looked at every way the hand can go, so the result is the reason
+fn explain_solved(view: &PlayerView, action: &Action, outcome: &Outcome) -> Option<String> {
+    let name = view.hand.iter().find(|card| card.entity == action.card())?.sub_type.name();
+    let verb = if matches!(action, Action::Discard { .. }) { "Discarding" } else { "Playing" };
+    let result = match outcome.margin_for(view.seat) {
+        margin if margin > 0 => format!("puts you {} points ahead", margin),
+        0 => "keeps the hand level".to_string(),
+        margin => format!("keeps you to {} points behind", -margin),
+    };
+    Some(format!("Solved endgame: {} {} {} with the best play from both sides", verb, name, result))
+}
+
+// A short reason for `action`, `None` if its card isn't in the hand
+pub fn explain(view: &PlayerView, action: &Action) -> Option<String> {
+    let sub_type = view.hand.iter().find(|card| card.entity == action.card())?.sub_type;
+    let name = sub_type.name();
+    let mine = view.tableau(view.seat);
+    let theirs = view.tableau(view.seat.other());
+
+    let explanation = match *action {
+        Action::CoupFourre { .. } => {
+            let hazard = view.last_hazard.map_or("hazard", |(_, hazard)| hazard.sub_type.name());
+            format!("Coup fourré! {} answers their {} for 300 points and another turn", name, hazard)
+        }
+        Action::Discard { .. } if usefulness(view, sub_type) == 0 => {
+            format!("Nothing is worth playing, and {} can't help you any more", name)
+        }
+        Action::Discard { .. } => format!("Nothing is worth playing, {} is the least useful card you hold", name),
+        Action::Play { .. } => match sub_type.card_type() {
+            CardType::Safety if mine.battle_top().safety() == Some(sub_type) || mine.speed_top().safety() == Some(sub_type) => {
+                format!("{} clears your hazard for good and gives you another turn", name)
+            }
+            CardType::Safety if view.deck_size <= HOARD_UNTIL_DECK => {
+                format!("The deck is nearly out, play {} before it misses its chance", name)
+            }
+            CardType::Safety => format!("{} protects you and gives you another turn", name),
+            CardType::Remedy if sub_type == SubType::Roll => "Roll gets you moving again".to_string(),
+            CardType::Remedy if sub_type == SubType::EndOfLimit => "End of Limit lets you play long distances again".to_string(),
+            CardType::Remedy => format!("{} fixes your {}", name, mine.battle_top().name()),
+            CardType::Distance if mine.miles() + sub_type.miles() == mine.target_miles => {
+                format!("{} finishes the trip", name)
+            }
+            CardType::Distance => format!("{} takes you to {} of {}km", name, mine.miles() + sub_type.miles(), mine.target_miles),
+            CardType::Hazard if sub_type == SubType::SpeedLimit => "Speed Limit keeps them to 50km a card".to_string(),
+            CardType::Hazard if theirs.miles() >= mine.miles() => format!("{} stops them while they are ahead", name),
+            CardType::Hazard => format!("{} stops them in their tracks", name),
+        },
+    };
+    Some(explanation)
+}
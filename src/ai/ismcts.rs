@@ -0,0 +1,289 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use crate::cards::*;
+use crate::rules::*;
+use crate::view::PlayerView;
+use crate::events::GameEvent;
+use super::belief::CardCounter;
+use super::bot::Bot;
+use super::heuristic::{Difficulty, HeuristicBot};
+use super::personality::Personality;
+
+// How long a search may run for
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Budget {
+    Iterations(u32),
+    Time(Duration)
+}
+
+// Made up cards standing in for the ones we can't see, well clear of any real entity
+const HIDDEN_CARD_BASE: u32 = u32::MAX - 256;
+
+// How strongly the search leans towards the heuristic's choice before it has seen enough playouts.
+// Hands are long and luck plays a big part, so early results are mostly noise.
+const PRIOR_WEIGHT: f64 = 5.;
+
+// Points difference which counts as a complete win or loss for a playout
+const REWARD_SCALE: f64 = 1000.;
+
+// Deal the cards `view` can't see into the deck and the other hand at random. Everything the seat
+// knows is kept as it is, so the result is one game which could really be going on.
+pub fn determinize(view: &PlayerView, counter: &CardCounter, rng: &mut StdRng) -> Game {
+    let mut hidden: Vec<PileCard> = view.unseen().into_iter()
+        .enumerate()
+        .map(|(index, sub_type)| PileCard { entity: Entity::from_raw(HIDDEN_CARD_BASE + index as u32), sub_type })
+        .collect();
+    hidden.shuffle(rng);
+
+    let mut other_hand = hidden.split_off(hidden.len().saturating_sub(view.opponent_hand_size));
+
+    // A safety they passed up a coup fourré with is only in their hand if they have drawn it since
+    for (safety, _) in DECK_COMPOSITION.iter().filter(|(sub_type, _)| sub_type.card_type() == CardType::Safety) {
+        if !counter.has_declined(view.seat.other(), *safety) {
+            continue;
+        }
+
+        let in_hand = other_hand.iter().position(|card| card.sub_type == *safety);
+        let in_deck = hidden.iter().position(|card| card.sub_type == *safety);
+        let wanted = rng.gen::<f32>() < counter.hand_chance(view, *safety);
+
+        match (in_hand, in_deck) {
+            (Some(index), _) if !wanted && !hidden.is_empty() => {
+                let swap = rng.gen_range(0..hidden.len());
+                std::mem::swap(&mut other_hand[index], &mut hidden[swap]);
+            }
+            (_, Some(index)) if wanted && !other_hand.is_empty() => {
+                let swap = rng.gen_range(0..other_hand.len());
+                std::mem::swap(&mut hidden[index], &mut other_hand[swap]);
+            }
+            _ => {}
+        }
+    }
+    let mut game = Game {
+        deck: hidden,
+        discard: view.discard.clone(),
+        player: view.player.clone(),
+        opponent: view.opponent.clone(),
+        turn: view.turn,
+        extra_turn: view.extra_turn,
+        last_hazard: view.last_hazard,
+        ..default()
+    };
+    *game.hand_mut(view.seat) = view.hand.clone();
+    *game.hand_mut(view.seat.other()) = other_hand;
+    game
+}
+
+// An action with the card left out, so the same move lines up across determinizations
+// where the hidden cards are different entities
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub(crate) struct Move {
+    seat: Seat,
+    sub_type: SubType,
+    action: Action
+}
+
+impl Move {
+    // `hand` is the hand of `seat`, which holds the action's card
+    pub(crate) fn new(seat: Seat, hand: &[PileCard], action: Action) -> Self {
+        let sub_type = hand.iter().find(|card| card.entity == action.card()).unwrap().sub_type;
+        let action = match action {
+            Action::Play { target, pile, .. } => Action::Play { card: Entity::PLACEHOLDER, target, pile },
+            Action::Discard { .. } => Action::Discard { card: Entity::PLACEHOLDER },
+            Action::CoupFourre { .. } => Action::CoupFourre { card: Entity::PLACEHOLDER },
+        };
+        Self { seat, sub_type, action }
+    }
+}
+
+struct Node {
+    // The move which led here, `None` for the root
+    mv: Option<Move>,
+    children: HashMap<Move, usize>,
+    visits: u32,
+    // How many times this node could have been picked, as not every move exists in every determinization
+    available: u32,
+    // Total reward for the seat which made `mv`
+    reward: f64
+}
+
+impl Node {
+    fn new(mv: Option<Move>) -> Self {
+        Self { mv, children: HashMap::default(), visits: 0, available: 0, reward: 0. }
+    }
+}
+
+/***********
+ * SEARCH
+ ***********/
+
+// Single observer information set MCTS. Each iteration plays through a different determinization,
+// only ever following moves which are legal in it.
+pub struct IsmctsBot {
+    budget: Budget,
+    exploration: f64,
+    rng: StdRng,
+    counter: CardCounter,
+    // Plays out each hand once it leaves the tree, random playouts are too weak to learn much from
+    playout: HeuristicBot,
+    cancelled: Arc<AtomicBool>
+}
+
+impl IsmctsBot {
+    pub fn new(budget: Budget) -> Self {
+        Self::with_rng(budget, StdRng::from_entropy())
+    }
+
+    pub fn seeded(budget: Budget, seed: u64) -> Self {
+        Self::with_rng(budget, StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(budget: Budget, mut rng: StdRng) -> Self {
+        let playout = HeuristicBot::seeded(Difficulty::Hard, rng.gen()).without_endgame_solver();
+        Self { budget, exploration: 0.7, rng, counter: CardCounter::default(), playout, cancelled: Arc::default() }
+    }
+
+    // Leans the search towards the moves the style would make, it still plays to win
+    pub fn with_personality(mut self, personality: Personality) -> Self {
+        self.playout = self.playout.with_personality(personality);
+        self
+    }
+
+    fn out_of_budget(&self, iterations: u32, started: Instant) -> bool {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return true;
+        }
+        match self.budget {
+            Budget::Iterations(limit) => iterations >= limit,
+            Budget::Time(limit) => started.elapsed() >= limit,
+        }
+    }
+
+    fn iterate(&mut self, view: &PlayerView, tree: &mut Vec<Node>) {
+        let mut game = determinize(view, &self.counter, &mut self.rng);
+        let mut path = vec![0];
+        let mut node = 0;
+
+        // Selection and expansion
+        while let Some(seat) = game.turn {
+            // Cards of the same type are the same move
+            let moves: HashMap<Move, Action> = game.legal_actions(seat).into_iter()
+                .map(|action| (Move::new(seat, game.hand(seat), action), action))
+                .collect();
+
+            let favoured = self.playout.choose(&game.view(seat))
+                .map(|action| Move::new(seat, game.hand(seat), action));
+
+            let mut untried: Vec<(&Move, &Action)> = moves.iter()
+                .filter(|(mv, _)| !tree[node].children.contains_key(*mv))
+                .collect();
+
+            // Try the heuristic's move first
+            if let Some(index) = untried.iter().position(|(mv, _)| Some(**mv) == favoured) {
+                untried = vec![untried[index]];
+            }
+
+            let tried: Vec<usize> = moves.keys().filter_map(|mv| tree[node].children.get(mv).copied()).collect();
+            for child in tried {
+                tree[child].available += 1;
+            }
+
+            if let Some((mv, action)) = untried.choose(&mut self.rng).copied() {
+                let child = tree.len();
+                tree.push(Node::new(Some(*mv)));
+                tree[child].available += 1;
+                tree[node].children.insert(*mv, child);
+
+                game.apply(seat, *action).unwrap();
+                game.next_turn();
+                path.push(child);
+                break;
+            }
+
+            let (child, action) = moves.iter()
+                .map(|(mv, action)| {
+                    let child = tree[node].children[mv];
+                    let bias = if Some(*mv) == favoured { PRIOR_WEIGHT / (tree[child].visits + 1) as f64 } else { 0. };
+                    (child, *action, self.ucb(&tree[child]) + bias)
+                })
+                .max_by(|(_, _, a), (_, _, b)| a.total_cmp(b))
+                .map(|(child, action, _)| (child, action))
+                .unwrap();
+
+            game.apply(seat, action).unwrap();
+            game.next_turn();
+            path.push(child);
+            node = child;
+        }
+
+        // Playout
+        while let Some(seat) = game.turn {
+            let action = self.playout.choose(&game.view(seat)).unwrap();
+            game.apply(seat, action).unwrap();
+            game.next_turn();
+        }
+
+        // Backpropagation
+        for index in path {
+            let node = &mut tree[index];
+            node.visits += 1;
+            if let Some(mv) = node.mv {
+                node.reward += reward(&game, mv.seat);
+            }
+        }
+    }
+
+    fn ucb(&self, node: &Node) -> f64 {
+        let visits = node.visits.max(1) as f64;
+        node.reward / visits + self.exploration * ((node.available.max(1) as f64).ln() / visits).sqrt()
+    }
+}
+
+// How well the finished hand went for `seat`, from 0 to 1
+fn reward(game: &Game, seat: Seat) -> f64 {
+    let difference = game.hand_points(seat) - game.hand_points(seat.other());
+    (0.5 + difference as f64 / (2. * REWARD_SCALE)).clamp(0., 1.)
+}
+
+impl Bot for IsmctsBot {
+    fn choose(&mut self, view: &PlayerView) -> Option<Action> {
+        let actions = view.legal_actions();
+        if actions.len() <= 1 {
+            return actions.first().copied();
+        }
+
+        let started = Instant::now();
+        let mut tree = vec![Node::new(None)];
+        let mut iterations = 0;
+        while !self.out_of_budget(iterations, started) {
+            self.iterate(view, &mut tree);
+            iterations += 1;
+        }
+
+        // The most visited move is the most trusted one. With nothing searched the move
+        // still has to be made, unless it has been called off.
+        let Some((best, _)) = tree[0].children.iter().max_by_key(|(_, child)| tree[**child].visits) else {
+            if self.cancelled.load(Ordering::Relaxed) {
+                return None;
+            }
+            return self.playout.choose(view).or(actions.first().copied());
+        };
+
+        actions.into_iter().find(|action| Move::new(view.seat, &view.hand, *action) == *best)
+    }
+
+    fn observe(&mut self, event: &GameEvent) {
+        self.counter.observe(event);
+        self.playout.observe(event);
+    }
+
+    fn cancel_with(&mut self, cancelled: Arc<AtomicBool>) {
+        self.cancelled = cancelled;
+    }
+}
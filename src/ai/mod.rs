@@ -0,0 +1,11 @@
+pub mod analysis;
+pub mod belief;
+pub mod bot;
+pub mod endgame;
+pub mod external;
+pub mod heuristic;
+pub mod hint;
+pub mod ismcts;
+pub mod personality;
+pub mod policy;
+pub mod random;
@@ -0,0 +1,80 @@
+// Playing styles for the heuristic bot, read from a data file at startup so new
+// opponents can be made without touching the code
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub const PERSONALITIES_FILE: &str = "assets/personalities.ron";
+
+// Weights on the heuristic bot's scores, 1 leaves a score as it is.
+// Anything left out of the file keeps the standard value. These rules have no
+// extensions to a longer trip, so there is no weight for taking one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Personality {
+    pub name: String,
+    // How keen it is to play hazards rather than drive
+    pub aggression: f32,
+    // How long safeties are held for a coup fourré, 0 plays them straight away
+    // and 2 holds them until half the usual number of cards are left
+    pub hoarding: f32,
+    // How much it likes playing 200s over keeping the safe trip open
+    pub risk: f32
+}
+impl Default for Personality {
+    fn default() -> Self {
+        Self {
+            name: "Standard".into(),
+            aggression: 1.,
+            hoarding: 1.,
+            risk: 1.
+        }
+    }
+}
+
+// The styles to choose from, the standard one first
+#[derive(Resource, Debug, Clone)]
+pub struct Personalities {
+    pub all: Vec<Personality>,
+    // Which one the menu has picked for the computer opponent
+    pub chosen: usize
+}
+impl Default for Personalities {
+    fn default() -> Self {
+        Self { all: vec![Personality::default()], chosen: 0 }
+    }
+}
+
+impl Personalities {
+    // The file holds a list of personalities, which go after the standard one
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let loaded: Vec<Personality> = ron::from_str(text).map_err(|error| error.to_string())?;
+        let mut personalities = Self::default();
+        personalities.all.extend(loaded);
+        Ok(personalities)
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|error| format!("{}: {}", path, error))?;
+        Self::parse(&text).map_err(|error| format!("{}: {}", path, error))
+    }
+
+    // A missing or broken file only costs the named opponents
+    pub fn load_or_default(path: &str) -> Self {
+        Self::load(path).unwrap_or_else(|error| {
+            warn!("Couldn't load personalities, {}", error);
+            Self::default()
+        })
+    }
+
+    pub fn named(&self, name: &str) -> Option<&Personality> {
+        self.all.iter().find(|personality| personality.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn chosen(&self) -> &Personality {
+        &self.all[self.chosen % self.all.len()]
+    }
+
+    pub fn choose_next(&mut self) {
+        self.chosen = (self.chosen + 1) % self.all.len();
+    }
+}
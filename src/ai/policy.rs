@@ -0,0 +1,107 @@
+// A policy learnt offline from exported training data. Each action index gets a linear
+// score from the observation and the best legal one is played.
+use serde::{Deserialize, Serialize};
+use crate::env::*;
+use crate::rules::Action;
+use crate::training::Sample;
+use crate::view::PlayerView;
+use super::bot::Bot;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinearPolicy {
+    // `ACTION_COUNT` rows of `OBSERVATION_SIZE` weights
+    pub weights: Vec<Vec<f32>>,
+    pub bias: Vec<f32>
+}
+impl Default for LinearPolicy {
+    fn default() -> Self {
+        Self { weights: vec![vec![0.; OBSERVATION_SIZE]; ACTION_COUNT], bias: vec![0.; ACTION_COUNT] }
+    }
+}
+
+impl LinearPolicy {
+    // Policies are saved as JSON, so they can be trained with other tools too
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|error| format!("{}: {}", path, error))?;
+        let policy: Self = serde_json::from_str(&text).map_err(|error| format!("{}: {}", path, error))?;
+
+        let shaped = policy.bias.len() == ACTION_COUNT
+            && policy.weights.len() == ACTION_COUNT
+            && policy.weights.iter().all(|row| row.len() == OBSERVATION_SIZE);
+        if !shaped {
+            return Err(format!("{}: expected {} actions of {} weights", path, ACTION_COUNT, OBSERVATION_SIZE));
+        }
+        Ok(policy)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, serde_json::to_string(self).unwrap()).map_err(|error| format!("{}: {}", path, error))
+    }
+
+    fn score(&self, observation: &[f32], action: usize) -> f32 {
+        self.bias[action] + self.weights[action].iter().zip(observation).map(|(weight, value)| weight * value).sum::<f32>()
+    }
+
+    // The legal action index with the highest score
+    pub fn best(&self, observation: &[f32], legal_mask: &[bool]) -> Option<usize> {
+        (0..ACTION_COUNT)
+            .filter(|action| legal_mask[*action])
+            .max_by(|a, b| self.score(observation, *a).total_cmp(&self.score(observation, *b)))
+    }
+
+    // Softmax regression over the legal actions, learning to make the samples' choices.
+    // Samples are taken in order, so the same data always gives the same policy.
+    pub fn fit(samples: &[Sample], epochs: usize, learning_rate: f32) -> Self {
+        let mut policy = Self::default();
+
+        for _ in 0..epochs {
+            for sample in samples {
+                let legal: Vec<usize> = (0..ACTION_COUNT).filter(|action| sample.legal_mask[*action]).collect();
+                let scores: Vec<f32> = legal.iter().map(|action| policy.score(&sample.observation, *action)).collect();
+                let top = scores.iter().copied().fold(f32::MIN, f32::max);
+                let exps: Vec<f32> = scores.iter().map(|score| (score - top).exp()).collect();
+                let total: f32 = exps.iter().sum();
+
+                for (action, exp) in legal.iter().zip(exps) {
+                    let target = if *action == sample.action { 1. } else { 0. };
+                    let step = learning_rate * (exp / total - target);
+                    policy.bias[*action] -= step;
+                    for (weight, value) in policy.weights[*action].iter_mut().zip(&sample.observation) {
+                        *weight -= step * value;
+                    }
+                }
+            }
+        }
+
+        policy
+    }
+
+    // The share of samples where the policy makes the same choice
+    pub fn accuracy(&self, samples: &[Sample]) -> f64 {
+        let agreed = samples.iter()
+            .filter(|sample| self.best(&sample.observation, &sample.legal_mask) == Some(sample.action))
+            .count();
+        agreed as f64 / samples.len().max(1) as f64
+    }
+}
+
+pub struct PolicyBot {
+    policy: LinearPolicy
+}
+
+impl PolicyBot {
+    pub fn new(policy: LinearPolicy) -> Self {
+        Self { policy }
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        LinearPolicy::load(path).map(Self::new)
+    }
+}
+
+impl Bot for PolicyBot {
+    fn choose(&mut self, view: &PlayerView) -> Option<Action> {
+        let index = self.policy.best(&encode_observation(view), &legal_mask(view))?;
+        index_action(view, index)
+    }
+}
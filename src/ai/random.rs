@@ -0,0 +1,33 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use crate::rules::Action;
+use crate::view::PlayerView;
+use super::bot::Bot;
+
+// Picks any legal action, mostly useful for testing the game loop
+pub struct RandomBot {
+    rng: StdRng
+}
+
+impl RandomBot {
+    pub fn new() -> Self {
+        Self { rng: StdRng::from_entropy() }
+    }
+
+    pub fn seeded(seed: u64) -> Self {
+        Self { rng: StdRng::seed_from_u64(seed) }
+    }
+}
+
+impl Default for RandomBot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bot for RandomBot {
+    fn choose(&mut self, view: &PlayerView) -> Option<Action> {
+        view.legal_actions().choose(&mut self.rng).copied()
+    }
+}
@@ -0,0 +1,78 @@
+// Hosts one networked match with no window. Clients join with `bevy_test --connect <address>`.
+//
+//   server --port 7878 --bot hard --miles 700 --winning-score 5000
+//
+// Without `--bot` the second seat waits for another person to join. `--grace` is how long
+// a seat is held for somebody whose connection drops, and `--coaching` lets people watching
+// ask to see one seat's hand.
+use std::time::Duration;
+use bevy_test::net::protocol::DEFAULT_PORT;
+use bevy_test::net::server::{Server, ServerConfig};
+use bevy_test::sim::bot_named;
+
+// A length of time given in seconds, `None` if it's negative or endless
+fn seconds(value: &str) -> Option<Duration> {
+    Duration::try_from_secs_f32(value.parse().ok()?).ok()
+}
+
+fn parse_options() -> Result<(u16, ServerConfig), String> {
+    let mut port = DEFAULT_PORT;
+    let mut config = ServerConfig::default();
+    let mut args = std::env::args().skip(1);
+
+    while let Some(flag) = args.next() {
+        if flag == "--coaching" {
+            config.coaching = true;
+            continue;
+        }
+        let value = args.next().ok_or(format!("{} needs a value", flag))?;
+        match flag.as_str() {
+            "--port" => port = value.parse().map_err(|_| format!("bad port {}", value))?,
+            "--bot" => config.bot = Some(value),
+            "--bot-delay" => config.bot_delay = seconds(&value).ok_or(format!("bad delay {}", value))?,
+            "--grace" => config.grace = seconds(&value).ok_or(format!("bad grace period {}", value))?,
+            "--miles" => config.game_rules.miles = value.parse().map_err(|_| format!("bad miles {}", value))?,
+            "--winning-score" => config.game_rules.winning_score = value.parse().map_err(|_| format!("bad score {}", value))?,
+            _ => return Err(format!("unknown option {}", flag)),
+        }
+    }
+
+    if !config.game_rules.is_playable() {
+        return Err(format!("can't play to {} miles and {} points", config.game_rules.miles, config.game_rules.winning_score));
+    }
+    if let Some(name) = &config.bot {
+        if bot_named(name, 0).is_none() {
+            return Err(format!("unknown bot {}", name));
+        }
+    }
+    Ok((port, config))
+}
+
+fn main() {
+    let (port, config) = match parse_options() {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}", error);
+            eprintln!("usage: server [--port <n>] [--bot <bot>] [--bot-delay <seconds>] [--grace <seconds>] [--miles <n>] [--winning-score <n>] [--coaching]");
+            eprintln!("bots are named as for simulate");
+            std::process::exit(2);
+        }
+    };
+
+    let server = match Server::bind(("0.0.0.0", port), config) {
+        Ok(server) => server,
+        Err(error) => {
+            eprintln!("Couldn't listen on port {}: {}", port, error);
+            std::process::exit(1);
+        }
+    };
+    println!("Listening on {}", server.local_addr().map_or(port.to_string(), |address| address.to_string()));
+
+    match server.run() {
+        Ok(ended) => println!("{:?} won the match {} to {}", ended.winner, ended.player_score, ended.opponent_score),
+        Err(reason) => {
+            eprintln!("The match was cut short: {}", reason);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,103 @@
+// Plays bots against each other with no window, for balancing and checking AI strength.
+//
+//   simulate --a hard --b random --matches 100 --seed 1 --csv games.csv --json results.json
+//
+// `--export <file>` adds every move of every match to a training data file.
+//
+// The bots swap seats every match, as the player always leads the first hand.
+use bevy_test::ai::personality::PERSONALITIES_FILE;
+use bevy_test::cards::Seat;
+use bevy_test::rules::GameRules;
+use bevy_test::sim::*;
+use bevy_test::training::{append_samples, samples_from_log};
+
+struct Options {
+    a: String,
+    b: String,
+    matches: usize,
+    seed: u64,
+    csv: Option<String>,
+    json: Option<String>,
+    export: Option<String>
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options { a: "hard".into(), b: "random".into(), matches: 100, seed: 0, csv: None, json: None, export: None };
+    let mut args = std::env::args().skip(1);
+
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or(format!("{} needs a value", flag))?;
+        match flag.as_str() {
+            "--a" => options.a = value,
+            "--b" => options.b = value,
+            "--matches" => options.matches = value.parse().map_err(|_| format!("bad match count {}", value))?,
+            "--seed" => options.seed = value.parse().map_err(|_| format!("bad seed {}", value))?,
+            "--csv" => options.csv = Some(value),
+            "--json" => options.json = Some(value),
+            "--export" => options.export = Some(value),
+            _ => return Err(format!("unknown option {}", flag)),
+        }
+    }
+
+    for name in [&options.a, &options.b] {
+        if bot_named(name, 0).is_none() {
+            return Err(format!("unknown bot {}", name));
+        }
+    }
+    Ok(options)
+}
+
+fn main() {
+    let options = match parse_options() {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}", error);
+            eprintln!("usage: simulate [--a <bot>] [--b <bot>] [--matches <n>] [--seed <n>] [--csv <file>] [--json <file>] [--export <file>]");
+            eprintln!("bots: random, easy, medium, hard, expert, ismcts:<iterations>, external:<command>, policy:<file>");
+            eprintln!("easy, medium and hard take a personality from {} too, as in hard:bully", PERSONALITIES_FILE);
+            std::process::exit(2);
+        }
+    };
+
+    let game_rules = GameRules::default();
+    let mut records = Vec::new();
+    for index in 0..options.matches {
+        let seed = options.seed.wrapping_add(index as u64);
+        let (player, opponent) = if index % 2 == 0 { (&options.a, &options.b) } else { (&options.b, &options.a) };
+
+        let Some(path) = &options.export else {
+            records.extend(play_match(&game_rules, player, opponent, seed));
+            continue;
+        };
+
+        let Some((record, logs)) = play_logged_match(&game_rules, player, opponent, seed) else { continue };
+        for log in &logs {
+            if let Err(error) = append_samples(path, &samples_from_log(log, &[Seat::Player, Seat::Opponent])) {
+                eprintln!("Couldn't write {}: {}", path, error);
+                std::process::exit(1);
+            }
+        }
+        records.push(record);
+    }
+
+    let summary = summarise(&records);
+    println!("{} matches, {} hands, {:.1} turns a hand, {:.2} coups fourrés a hand",
+        summary.matches, summary.hands, summary.average_hand_turns, summary.coup_fourres_per_hand);
+    for bot in &summary.bots {
+        println!("{:>12}: {:.1}% of {} matches, {:.0} points on average",
+            bot.name, 100. * bot.win_rate, bot.matches, bot.average_score);
+    }
+
+    if let Some(path) = &options.csv {
+        if let Err(error) = std::fs::write(path, matches_csv(&records)) {
+            eprintln!("Couldn't write {}: {}", path, error);
+        }
+    }
+
+    if let Some(path) = &options.json {
+        let json = serde_json::json!({ "summary": summary, "matches": records });
+        if let Err(error) = std::fs::write(path, serde_json::to_string_pretty(&json).unwrap()) {
+            eprintln!("Couldn't write {}: {}", path, error);
+        }
+    }
+}
@@ -0,0 +1,93 @@
+// Runs a tournament between bots and prints an Elo leaderboard.
+//
+//   tournament --bot easy --bot hard --bot "external:python3 my_bot.py" --format swiss --rounds 3 --games 5
+//
+// Bot names are the same as for `simulate`. Every deal is played twice, with the bots swapping seats,
+// so `--games 5` is ten matches each time two bots meet.
+use bevy_test::rules::GameRules;
+use bevy_test::sim::bot_named;
+use bevy_test::tournament::*;
+
+struct Options {
+    tournament: Tournament,
+    csv: Option<String>,
+    json: Option<String>
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut entrants = Vec::new();
+    let mut swiss = false;
+    let mut rounds = None;
+    let mut games = 5;
+    let mut seed = 0;
+    let (mut csv, mut json) = (None, None);
+    let mut args = std::env::args().skip(1);
+
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or(format!("{} needs a value", flag))?;
+        match flag.as_str() {
+            "--bot" => entrants.push(value),
+            "--format" => swiss = match value.as_str() {
+                "round-robin" => false,
+                "swiss" => true,
+                _ => return Err(format!("unknown format {}", value)),
+            },
+            "--rounds" => rounds = Some(value.parse().map_err(|_| format!("bad round count {}", value))?),
+            "--games" => games = value.parse().map_err(|_| format!("bad game count {}", value))?,
+            "--seed" => seed = value.parse().map_err(|_| format!("bad seed {}", value))?,
+            "--csv" => csv = Some(value),
+            "--json" => json = Some(value),
+            _ => return Err(format!("unknown option {}", flag)),
+        }
+    }
+
+    if entrants.is_empty() {
+        entrants = ["random", "easy", "medium", "hard"].map(String::from).to_vec();
+    }
+    if entrants.len() < 2 {
+        return Err("a tournament needs at least two bots".into());
+    }
+    if let Some(name) = entrants.iter().find(|name| bot_named(name, 0).is_none()) {
+        return Err(format!("unknown bot {}", name));
+    }
+
+    // Enough Swiss rounds to separate the field by default
+    let format = if swiss {
+        Format::Swiss { rounds: rounds.unwrap_or((entrants.len() as f64).log2().ceil() as usize) }
+    } else {
+        Format::RoundRobin
+    };
+
+    Ok(Options { tournament: Tournament { entrants, format, games, seed }, csv, json })
+}
+
+fn main() {
+    let options = match parse_options() {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}", error);
+            eprintln!("usage: tournament --bot <bot> --bot <bot> ... [--format round-robin|swiss] [--rounds <n>] [--games <n>] [--seed <n>] [--csv <file>] [--json <file>]");
+            std::process::exit(2);
+        }
+    };
+
+    let result = options.tournament.run(&GameRules::default());
+
+    println!("{:>4}  {:<24} {:>6} {:>6} {:>6} {:>6}", "rank", "bot", "elo", "played", "won", "lost");
+    for (index, standing) in result.leaderboard.iter().enumerate() {
+        println!("{:>4}  {:<24} {:>6.0} {:>6} {:>6} {:>6}",
+            index + 1, standing.name, standing.rating, standing.played, standing.wins, standing.losses);
+    }
+
+    if let Some(path) = &options.csv {
+        if let Err(error) = std::fs::write(path, leaderboard_csv(&result.leaderboard)) {
+            eprintln!("Couldn't write {}: {}", path, error);
+        }
+    }
+
+    if let Some(path) = &options.json {
+        if let Err(error) = std::fs::write(path, serde_json::to_string_pretty(&result).unwrap()) {
+            eprintln!("Couldn't write {}: {}", path, error);
+        }
+    }
+}
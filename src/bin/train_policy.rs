@@ -0,0 +1,62 @@
+// Fits a linear policy to exported training data, to be played as `policy:<file>`.
+//
+//   simulate --a hard --b hard --matches 200 --export games.mbtd
+//   train_policy --data games.mbtd --out policy.json --epochs 10 --rate 0.01
+use bevy_test::ai::policy::LinearPolicy;
+use bevy_test::training::load_samples;
+
+struct Options {
+    data: String,
+    out: String,
+    epochs: usize,
+    rate: f32
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut data = None;
+    let mut out = None;
+    let mut options = Options { data: String::new(), out: String::new(), epochs: 10, rate: 0.01 };
+    let mut args = std::env::args().skip(1);
+
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or(format!("{} needs a value", flag))?;
+        match flag.as_str() {
+            "--data" => data = Some(value),
+            "--out" => out = Some(value),
+            "--epochs" => options.epochs = value.parse().map_err(|_| format!("bad epoch count {}", value))?,
+            "--rate" => options.rate = value.parse().map_err(|_| format!("bad learning rate {}", value))?,
+            _ => return Err(format!("unknown option {}", flag)),
+        }
+    }
+
+    options.data = data.ok_or("--data is needed")?;
+    options.out = out.ok_or("--out is needed")?;
+    Ok(options)
+}
+
+fn main() {
+    let options = match parse_options() {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}", error);
+            eprintln!("usage: train_policy --data <file> --out <file> [--epochs <n>] [--rate <learning rate>]");
+            std::process::exit(2);
+        }
+    };
+
+    let samples = match load_samples(&options.data) {
+        Ok(samples) => samples,
+        Err(error) => {
+            eprintln!("Couldn't read {}: {}", options.data, error);
+            std::process::exit(1);
+        }
+    };
+
+    let policy = LinearPolicy::fit(&samples, options.epochs, options.rate);
+    println!("{} samples, {:.1}% of moves matched", samples.len(), 100. * policy.accuracy(&samples));
+
+    if let Err(error) = policy.save(&options.out) {
+        eprintln!("Couldn't write {}", error);
+        std::process::exit(1);
+    }
+}
@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::ui::card_ui::CardToUILink;
 
@@ -26,6 +27,29 @@ const SEVENTY_FIVE: i32 = 10;
 const ONE_HUNDRED: i32 = 12;
 const TWO_HUNDRED: i32 = 4;
 
+// How many of each card make up a full deck
+pub const DECK_COMPOSITION: [(SubType, i32); 19] = [
+    (SubType::Accident, ACCIDENT),
+    (SubType::OutOfGas, OUT_OF_GAS),
+    (SubType::FlatTyre, FLAT_TYRE),
+    (SubType::SpeedLimit, SPEED_LIMIT),
+    (SubType::Stop, STOP),
+    (SubType::Repairs, REPAIRS),
+    (SubType::Gasoline, GASOLINE),
+    (SubType::SpareTyre, SPARE_TYRE),
+    (SubType::EndOfLimit, END_OF_LIMIT),
+    (SubType::Roll, ROLL),
+    (SubType::DrivingAce, DRIVING_ACE),
+    (SubType::ExtraTank, EXTRA_TANK),
+    (SubType::PunctureProof, PUNCTURE_PROOF),
+    (SubType::RightOfWay, RIGHT_OF_WAY),
+    (SubType::TwentyFive, TWENTY_FIVE),
+    (SubType::Fifty, FIFTY),
+    (SubType::SeventyFive, SEVENTY_FIVE),
+    (SubType::OneHundred, ONE_HUNDRED),
+    (SubType::TwoHundred, TWO_HUNDRED),
+];
+
 pub struct Cards;
 
 #[derive(SystemSet, Hash, Debug, Eq, PartialEq, Clone)]
@@ -134,11 +158,14 @@ fn spawn_distances(mut commands: Commands)
     }
 }
 
-// Ideally this would have an int indicating order of the deck 
-// but ordered queries aren't supported yet
 #[derive(Component)]
 pub struct Deck;
 
+// Where a card sits in the draw pile, kept in step with `Game::deck`.
+// 0 is the bottom card and the highest position is drawn next.
+#[derive(Component, Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub struct DeckPosition(pub usize);
+
 #[derive(Component)]
 pub struct PlayerHand;
 
@@ -148,9 +175,29 @@ pub struct OpponentHand;
 #[derive(Component)]
 pub struct DiscardPile;
 
+// Where a card sits in the discard pile, 0 is the first card thrown away
+#[derive(Component, Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub struct DiscardPosition(pub usize);
+
+// The two sides of the table
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Seat {
+    Player,
+    Opponent
+}
+impl Seat {
+    pub fn other(&self) -> Seat {
+        match self {
+            Seat::Player => Seat::Opponent,
+            Seat::Opponent => Seat::Player,
+        }
+    }
+}
+
 // Play area components
 pub trait Playable {
-    fn is_valid(board_card_type: &SubType, card_type: &CardType, sub_type: &SubType) -> bool;
+    // `board` is the tableau the card would land on - the opponent's for hazards, our own otherwise
+    fn is_valid(board: &Tableau, card_type: &CardType, sub_type: &SubType) -> bool;
 }
 
 #[derive(Component)]
@@ -159,7 +206,72 @@ pub struct PlayerBoard;
 #[derive(Component)]
 pub struct OpponentBoard;
 
-#[derive(Component, Eq, PartialEq)]
+// A board card which has had another card played on top of it
+#[derive(Component)]
+pub struct Covered;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PileCard {
+    pub entity: Entity,
+    pub sub_type: SubType
+}
+
+// Everything a seat has played in front of them this hand
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Tableau {
+    pub battle: Vec<PileCard>,
+    pub speed: Vec<PileCard>,
+    pub distance: Vec<PileCard>,
+    pub safeties: Vec<PileCard>,
+    pub coup_fourres: i32,
+    pub target_miles: i32
+}
+impl Tableau {
+    pub fn battle_top(&self) -> SubType {
+        self.battle.last().map_or(SubType::NoCard, |card| card.sub_type)
+    }
+
+    pub fn speed_top(&self) -> SubType {
+        self.speed.last().map_or(SubType::NoCard, |card| card.sub_type)
+    }
+
+    pub fn miles(&self) -> i32 {
+        self.distance.iter().map(|card| card.sub_type.miles()).sum()
+    }
+
+    pub fn two_hundreds(&self) -> usize {
+        self.distance.iter().filter(|card| card.sub_type == SubType::TwoHundred).count()
+    }
+
+    pub fn has_safety(&self, safety: SubType) -> bool {
+        self.safeties.iter().any(|card| card.sub_type == safety)
+    }
+
+    pub fn is_protected_from(&self, hazard: SubType) -> bool {
+        hazard.safety().is_some_and(|safety| self.has_safety(safety))
+    }
+
+    // Right of Way lets a seat move without a Roll as long as no hazard is stopping them
+    pub fn is_rolling(&self) -> bool {
+        let top = self.battle_top();
+        top == SubType::Roll || (self.has_safety(SubType::RightOfWay) && top.safety().is_none())
+    }
+
+    pub fn is_speed_limited(&self) -> bool {
+        self.speed_top() == SubType::SpeedLimit && !self.has_safety(SubType::RightOfWay)
+    }
+
+    pub fn has_finished(&self) -> bool {
+        self.miles() == self.target_miles
+    }
+
+    // Every card on the table, covered or not
+    pub fn cards(&self) -> impl Iterator<Item = &PileCard> {
+        self.battle.iter().chain(&self.speed).chain(&self.distance).chain(&self.safeties)
+    }
+}
+
+#[derive(Component, Debug, Eq, PartialEq)]
 pub enum CardType {
     Hazard,
     Remedy,
@@ -167,7 +279,7 @@ pub enum CardType {
     Distance
 }
 
-#[derive(Component, Eq, PartialEq, Copy, Clone)]
+#[derive(Component, Debug, Eq, PartialEq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub enum SubType {
     Accident,
     OutOfGas,
@@ -190,11 +302,83 @@ pub enum SubType {
     TwoHundred,
     NoCard
 }
+impl SubType {
+    pub fn card_type(&self) -> CardType {
+        match self {
+            SubType::Accident | SubType::OutOfGas | SubType::SpeedLimit | SubType::FlatTyre | SubType::Stop => CardType::Hazard,
+            SubType::Repairs | SubType::Gasoline | SubType::EndOfLimit | SubType::SpareTyre | SubType::Roll => CardType::Remedy,
+            SubType::PunctureProof | SubType::ExtraTank | SubType::DrivingAce | SubType::RightOfWay => CardType::Safety,
+            SubType::TwentyFive | SubType::Fifty | SubType::SeventyFive | SubType::OneHundred | SubType::TwoHundred => CardType::Distance,
+            SubType::NoCard => panic!("NoCard has no card type"),
+        }
+    }
+
+    pub fn miles(&self) -> i32 {
+        match self {
+            SubType::TwentyFive => 25,
+            SubType::Fifty => 50,
+            SubType::SeventyFive => 75,
+            SubType::OneHundred => 100,
+            SubType::TwoHundred => 200,
+            _ => 0
+        }
+    }
+
+    // The safety which protects against (and cures) this hazard
+    pub fn safety(&self) -> Option<SubType> {
+        match self {
+            SubType::Accident => Some(SubType::DrivingAce),
+            SubType::OutOfGas => Some(SubType::ExtraTank),
+            SubType::FlatTyre => Some(SubType::PunctureProof),
+            SubType::SpeedLimit => Some(SubType::RightOfWay),
+            SubType::Stop => Some(SubType::RightOfWay),
+            _ => None
+        }
+    }
+
+    // The name printed on the card
+    pub fn name(&self) -> &'static str {
+        match self {
+            SubType::Accident => "Accident",
+            SubType::OutOfGas => "Out of Gas",
+            SubType::SpeedLimit => "Speed Limit",
+            SubType::FlatTyre => "Flat Tyre",
+            SubType::Stop => "Stop",
+            SubType::Repairs => "Repairs",
+            SubType::Gasoline => "Gasoline",
+            SubType::EndOfLimit => "End of Limit",
+            SubType::SpareTyre => "Spare Tyre",
+            SubType::Roll => "Roll",
+            SubType::PunctureProof => "Puncture Proof",
+            SubType::ExtraTank => "Extra Tank",
+            SubType::DrivingAce => "Driving Ace",
+            SubType::RightOfWay => "Right of Way",
+            SubType::TwentyFive => "25km",
+            SubType::Fifty => "50km",
+            SubType::SeventyFive => "75km",
+            SubType::OneHundred => "100km",
+            SubType::TwoHundred => "200km",
+            SubType::NoCard => "No Card",
+        }
+    }
+
+    // The remedy that clears a hazard
+    pub fn remedy(&self) -> Option<SubType> {
+        match self {
+            SubType::Accident => Some(SubType::Repairs),
+            SubType::OutOfGas => Some(SubType::Gasoline),
+            SubType::FlatTyre => Some(SubType::SpareTyre),
+            SubType::SpeedLimit => Some(SubType::EndOfLimit),
+            SubType::Stop => Some(SubType::Roll),
+            _ => None
+        }
+    }
+}
 
 #[derive(Component, Debug)]
 pub struct CardName(pub String);
 
-#[derive(Component, Eq, PartialEq)]
+#[derive(Component, Debug, Eq, PartialEq)]
 pub enum ActionType {
     Offensive,
     Defensive
@@ -227,12 +411,12 @@ impl Default for CardBundle {
 pub struct Card;
 
 impl Playable for Card {
-    fn is_valid(board_card_type: &SubType, card_type: &CardType, sub_type: &SubType) -> bool {
+    fn is_valid(board: &Tableau, card_type: &CardType, sub_type: &SubType) -> bool {
         match card_type {
-            CardType::Hazard => Hazard::is_valid(board_card_type, card_type, sub_type),
-            CardType::Remedy => Remedy::is_valid(board_card_type, card_type, sub_type),
-            CardType::Safety => Safety::is_valid(board_card_type, card_type, sub_type),
-            CardType::Distance => Distance::is_valid(board_card_type, card_type, sub_type),
+            CardType::Hazard => Hazard::is_valid(board, card_type, sub_type),
+            CardType::Remedy => Remedy::is_valid(board, card_type, sub_type),
+            CardType::Safety => Safety::is_valid(board, card_type, sub_type),
+            CardType::Distance => Distance::is_valid(board, card_type, sub_type),
         }
     }
 }
@@ -245,8 +429,15 @@ impl Playable for Card {
 pub struct Hazard;
 
 impl Playable for Hazard {
-    fn is_valid(board_card_type: &SubType, _card_type: &CardType, _sub_type: &SubType) -> bool {
-        return *board_card_type == SubType::Roll;
+    fn is_valid(board: &Tableau, _card_type: &CardType, sub_type: &SubType) -> bool {
+        if board.is_protected_from(*sub_type) {
+            return false;
+        }
+
+        match sub_type {
+            SubType::SpeedLimit => board.speed_top() != SubType::SpeedLimit,
+            _ => board.is_rolling()
+        }
     }
 }
 
@@ -361,13 +552,13 @@ impl Default for Stop {
 #[derive(Component)]
 pub struct Remedy;
 impl Playable for Remedy {
-    fn is_valid(board_card_type: &SubType, card_type: &CardType, sub_type: &SubType) -> bool {
+    fn is_valid(board: &Tableau, card_type: &CardType, sub_type: &SubType) -> bool {
         match sub_type {
-            SubType::Repairs => Repairs::is_valid(board_card_type, card_type, sub_type),
-            SubType::Gasoline => Gasoline::is_valid(board_card_type, card_type, sub_type),
-            SubType::EndOfLimit => EndOfLimit::is_valid(board_card_type, card_type, sub_type),
-            SubType::SpareTyre => SpareTyre::is_valid(board_card_type, card_type, sub_type),
-            SubType::Roll => Roll::is_valid(board_card_type, card_type, sub_type),
+            SubType::Repairs => Repairs::is_valid(board, card_type, sub_type),
+            SubType::Gasoline => Gasoline::is_valid(board, card_type, sub_type),
+            SubType::EndOfLimit => EndOfLimit::is_valid(board, card_type, sub_type),
+            SubType::SpareTyre => SpareTyre::is_valid(board, card_type, sub_type),
+            SubType::Roll => Roll::is_valid(board, card_type, sub_type),
             _ => panic!()
         }
     }
@@ -394,8 +585,8 @@ impl Default for Repairs {
     }
 }
 impl Playable for Repairs {
-    fn is_valid(board_card_type: &SubType, _card_type: &CardType, _sub_type: &SubType) -> bool {
-        return *board_card_type == SubType::Accident;
+    fn is_valid(board: &Tableau, _card_type: &CardType, _sub_type: &SubType) -> bool {
+        board.battle_top() == SubType::Accident
     }
 }
 
@@ -420,8 +611,8 @@ impl Default for Gasoline {
     }
 }
 impl Playable for Gasoline {
-    fn is_valid(board_card_type: &SubType, _card_type: &CardType, _sub_type: &SubType) -> bool {
-        return *board_card_type == SubType::OutOfGas;
+    fn is_valid(board: &Tableau, _card_type: &CardType, _sub_type: &SubType) -> bool {
+        board.battle_top() == SubType::OutOfGas
     }
 }
 
@@ -446,8 +637,8 @@ impl Default for SpareTyre {
     }
 }
 impl Playable for SpareTyre {
-    fn is_valid(board_card_type: &SubType, _card_type: &CardType, _sub_type: &SubType) -> bool {
-        return *board_card_type == SubType::FlatTyre;
+    fn is_valid(board: &Tableau, _card_type: &CardType, _sub_type: &SubType) -> bool {
+        board.battle_top() == SubType::FlatTyre
     }
 }
 
@@ -472,8 +663,8 @@ impl Default for EndOfLimit {
     }
 }
 impl Playable for EndOfLimit {
-    fn is_valid(board_card_type: &SubType, _card_type: &CardType, _sub_type: &SubType) -> bool {
-        return *board_card_type == SubType::SpeedLimit;
+    fn is_valid(board: &Tableau, _card_type: &CardType, _sub_type: &SubType) -> bool {
+        board.speed_top() == SubType::SpeedLimit
     }
 }
 
@@ -498,15 +689,12 @@ impl Default for Roll {
     }
 }
 impl Playable for Roll {
-    fn is_valid(board_card_type: &SubType, _card_type: &CardType, _sub_type: &SubType) -> bool {
-        match board_card_type {
-            SubType::Accident => return false,
-            SubType::OutOfGas => return false,
-            SubType::SpeedLimit => return false,
-            SubType::FlatTyre => return false,
-            SubType::Roll => return false,
-            _ => return true,
-        }
+    fn is_valid(board: &Tableau, _card_type: &CardType, _sub_type: &SubType) -> bool {
+        !matches!(board.battle_top(),
+            SubType::Accident |
+            SubType::OutOfGas |
+            SubType::FlatTyre |
+            SubType::Roll)
     }
 }
 
@@ -516,11 +704,9 @@ impl Playable for Roll {
 #[derive(Component)]
 pub struct Safety;
 impl Playable for Safety {
-    fn is_valid(_board_card_type: &SubType, _card_type: &CardType, _sub_type: &SubType) -> bool {
+    fn is_valid(_board: &Tableau, _card_type: &CardType, _sub_type: &SubType) -> bool {
         // it is always valid to play a safety
-        // todo : effects of playing cards
-        
-        return true;
+        true
     }
 }
 
@@ -614,8 +800,21 @@ impl Default for RightOfWay {
 #[derive(Component)]
 pub struct Distance;
 impl Playable for Distance {
-    fn is_valid(board_card_type: &SubType, _card_type: &CardType, _sub_type: &SubType) -> bool {
-        return *board_card_type == SubType::Roll;
+    fn is_valid(board: &Tableau, _card_type: &CardType, sub_type: &SubType) -> bool {
+        if !board.is_rolling() {
+            return false;
+        }
+
+        if board.is_speed_limited() && sub_type.miles() > 50 {
+            return false;
+        }
+
+        if *sub_type == SubType::TwoHundred && board.two_hundreds() >= 2 {
+            return false;
+        }
+
+        // The trip has to be completed exactly
+        board.miles() + sub_type.miles() <= board.target_miles
     }
 }
 
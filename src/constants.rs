@@ -4,10 +4,13 @@ use bevy::prelude::{States, Color};
 pub enum GameState {
     #[default]
     Menu,
+    // Waiting for a networked game to start
+    Lobby,
     SetupGame,
     BeginGame,
     DuringTurn,
-    NextTurn
+    NextTurn,
+    EndOfHand
 }
 
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
@@ -29,3 +32,4 @@ pub const HAZARD_CARD: Color = Color::RED;
 pub const REMEDY_CARD: Color = Color::DARK_GREEN;
 pub const SAFETY_CARD: Color = Color::PURPLE;
 pub const DISTANCE_CARD: Color = Color::MIDNIGHT_BLUE;
+pub const CARD_BACK: Color = Color::rgb(0.45, 0.3, 0.2);
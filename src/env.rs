@@ -0,0 +1,305 @@
+// A reinforcement learning environment on the pure rules core. The agent plays one
+// seat through numbered actions and sees fixed-size observations, a bot plays the other.
+// One episode is one hand.
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::ai::bot::Bot;
+use crate::cards::*;
+use crate::events::GameEvent;
+use crate::rules::*;
+use crate::sim::{bot_named, shuffled_deck};
+use crate::view::PlayerView;
+
+// Card types, in `DECK_COMPOSITION` order
+pub const CARD_KINDS: usize = DECK_COMPOSITION.len();
+
+// Playing (or coup fourré with) and discarding each card type
+pub const ACTION_COUNT: usize = 2 * CARD_KINDS;
+
+// A card type or no card at all, one-hot
+const TOP_SIZE: usize = CARD_KINDS + 1;
+
+// Battle and speed tops, safeties, miles, 200s and coups fourrés
+const TABLEAU_SIZE: usize = 2 * TOP_SIZE + SAFETIES.len() + 3;
+
+// Hand, both tableaus, discards, unseen cards, the hazard we could answer and four numbers
+pub const OBSERVATION_SIZE: usize = CARD_KINDS + 2 * TABLEAU_SIZE + 2 * CARD_KINDS + TOP_SIZE + 4;
+
+// Points are divided by this to keep rewards near 1
+pub const REWARD_SCALE: f32 = 1000.;
+
+// A full hand and the card just drawn
+const MAX_HAND: f32 = 7.;
+
+const SAFETIES: [SubType; 4] = [SubType::DrivingAce, SubType::ExtraTank, SubType::PunctureProof, SubType::RightOfWay];
+
+/***************
+ * OBSERVATIONS
+ ***************/
+
+fn kind(sub_type: SubType) -> Option<usize> {
+    DECK_COMPOSITION.iter().position(|(kind, _)| *kind == sub_type)
+}
+
+fn push_one_hot(observation: &mut Vec<f32>, sub_type: SubType) {
+    let index = kind(sub_type).unwrap_or(CARD_KINDS);
+    observation.extend((0..TOP_SIZE).map(|slot| if slot == index { 1. } else { 0. }));
+}
+
+// How many of each card type `cards` holds, as a share of the deck
+fn push_shares<'a>(observation: &mut Vec<f32>, cards: impl Iterator<Item = &'a SubType> + Clone) {
+    for (sub_type, count) in DECK_COMPOSITION {
+        let held = cards.clone().filter(|card| **card == sub_type).count();
+        observation.push(held as f32 / count as f32);
+    }
+}
+
+fn push_tableau(observation: &mut Vec<f32>, tableau: &Tableau) {
+    push_one_hot(observation, tableau.battle_top());
+    push_one_hot(observation, tableau.speed_top());
+    observation.extend(SAFETIES.map(|safety| if tableau.has_safety(safety) { 1. } else { 0. }));
+    observation.push(tableau.miles() as f32 / tableau.target_miles.max(1) as f32);
+    observation.push(tableau.two_hundreds() as f32 / 2.);
+    observation.push(tableau.coup_fourres as f32 / SAFETIES.len() as f32);
+}
+
+// `OBSERVATION_SIZE` numbers, mostly between 0 and 1, always from `view`'s own seat
+// so an agent trained in one seat can play the other
+pub fn encode_observation(view: &PlayerView) -> Vec<f32> {
+    let mut observation = Vec::with_capacity(OBSERVATION_SIZE);
+    let deck_size: i32 = DECK_COMPOSITION.iter().map(|(_, count)| count).sum();
+
+    for (sub_type, _) in DECK_COMPOSITION {
+        observation.push(view.hand.iter().filter(|card| card.sub_type == sub_type).count() as f32);
+    }
+    push_tableau(&mut observation, view.tableau(view.seat));
+    push_tableau(&mut observation, view.tableau(view.seat.other()));
+
+    let discard: Vec<SubType> = view.discard.iter().map(|card| card.sub_type).collect();
+    push_shares(&mut observation, discard.iter());
+    push_shares(&mut observation, view.unseen().iter());
+
+    let answerable = view.last_hazard.filter(|(target, _)| *target == view.seat);
+    push_one_hot(&mut observation, answerable.map_or(SubType::NoCard, |(_, hazard)| hazard.sub_type));
+
+    observation.push(view.deck_size as f32 / deck_size as f32);
+    observation.push(view.opponent_hand_size as f32 / MAX_HAND);
+    observation.push(if view.is_my_turn() { 1. } else { 0. });
+    observation.push(if view.extra_turn { 1. } else { 0. });
+
+    debug_assert_eq!(observation.len(), OBSERVATION_SIZE);
+    observation
+}
+
+/**********
+ * ACTIONS
+ **********/
+
+// Cards of the same type are the same action, a coup fourré takes the place of playing the card
+pub fn action_index(view: &PlayerView, action: Action) -> Option<usize> {
+    let card = view.hand.iter().find(|card| card.entity == action.card())?;
+    let discard = matches!(action, Action::Discard { .. });
+    Some(2 * kind(card.sub_type)? + discard as usize)
+}
+
+// The legal action behind `index`, if there is one
+pub fn index_action(view: &PlayerView, index: usize) -> Option<Action> {
+    view.legal_actions().into_iter().find(|action| action_index(view, *action) == Some(index))
+}
+
+pub fn legal_mask(view: &PlayerView) -> Vec<bool> {
+    let mut mask = vec![false; ACTION_COUNT];
+    for action in view.legal_actions() {
+        if let Some(index) = action_index(view, action) {
+            mask[index] = true;
+        }
+    }
+    mask
+}
+
+/**************
+ * ENVIRONMENT
+ **************/
+
+#[derive(Debug, Clone)]
+pub struct Step {
+    pub observation: Vec<f32>,
+    // The change in the agent's points margin since its last step, over `REWARD_SCALE`
+    pub reward: f32,
+    // The hand is over, nothing is legal until the next reset
+    pub done: bool,
+    pub legal_mask: Vec<bool>
+}
+
+pub struct Env {
+    game_rules: GameRules,
+    // The seat the agent plays
+    pub seat: Seat,
+    // Rebuilt from its name each reset, as in `sim::bot_named`
+    opponent_name: String,
+    opponent: Box<dyn Bot>,
+    game: Game,
+    margin: i32
+}
+
+impl Env {
+    // `None` if `opponent` isn't a bot `sim::bot_named` knows
+    pub fn new(game_rules: GameRules, seat: Seat, opponent: &str) -> Option<Self> {
+        let bot = bot_named(opponent, 0)?;
+        Some(Self {
+            game_rules,
+            seat,
+            opponent_name: opponent.to_string(),
+            opponent: bot,
+            game: Game::default(),
+            margin: 0
+        })
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn view(&self) -> PlayerView {
+        self.game.view(self.seat)
+    }
+
+    // Deals a new hand and plays the bot up to the agent's first turn
+    pub fn reset(&mut self, seed: u64) -> Step {
+        let mut rng = StdRng::seed_from_u64(seed);
+        self.opponent = bot_named(&self.opponent_name, rng.gen()).unwrap();
+        self.game = Game::new(shuffled_deck(&mut rng), &self.game_rules);
+
+        let mut events = self.game.deal(self.game_rules.hand_size);
+        events.extend(self.game.next_turn());
+        self.show(&events);
+        self.play_opponent();
+
+        // Anything the bot did first counts against the agent's first step,
+        // so the rewards add up to the hand's final margin
+        self.margin = 0;
+        self.step_result(0.)
+    }
+
+    // Takes the agent's action, then lets the bot play until it is the agent's turn again
+    pub fn step(&mut self, index: usize) -> Result<Step, RuleError> {
+        if self.game.turn != Some(self.seat) {
+            return Err(RuleError::NotYourTurn);
+        }
+        let action = index_action(&self.view(), index).ok_or(RuleError::Illegal)?;
+
+        let mut events = self.game.apply(self.seat, action)?;
+        events.extend(self.game.next_turn());
+        self.show(&events);
+        self.play_opponent();
+
+        let margin = self.current_margin();
+        let reward = (margin - self.margin) as f32 / REWARD_SCALE;
+        self.margin = margin;
+        Ok(self.step_result(reward))
+    }
+
+    fn current_margin(&self) -> i32 {
+        self.game.hand_points(self.seat) - self.game.hand_points(self.seat.other())
+    }
+
+    fn step_result(&self, reward: f32) -> Step {
+        let view = self.view();
+        Step {
+            observation: encode_observation(&view),
+            reward,
+            done: self.game.turn.is_none(),
+            legal_mask: legal_mask(&view)
+        }
+    }
+
+    fn show(&mut self, events: &[GameEvent]) {
+        let seat = self.seat.other();
+        for event in events {
+            self.opponent.observe(&event.seen_by(Some(seat)));
+        }
+    }
+
+    fn play_opponent(&mut self) {
+        let other = self.seat.other();
+        while self.game.turn == Some(other) {
+            let chosen = self.opponent.choose(&self.game.view(other)).filter(|action| self.game.check(other, action).is_ok());
+            let Some(action) = chosen.or_else(|| self.game.legal_actions(other).first().copied()) else { break };
+
+            let mut events = self.game.apply(other, action).unwrap();
+            events.extend(self.game.next_turn());
+            self.show(&events);
+        }
+    }
+}
+
+/*************
+ * VECTORISED
+ *************/
+
+// Many environments stepped together across threads. A finished hand is dealt again
+// straight away: its step still says `done` with the last reward, but the observation
+// and mask are for the new hand.
+pub struct VecEnv {
+    pub envs: Vec<Env>,
+    // Handed out in order to hands as they are dealt, so runs repeat exactly
+    next_seed: u64
+}
+
+impl VecEnv {
+    pub fn new(count: usize, game_rules: GameRules, seat: Seat, opponent: &str) -> Option<Self> {
+        let envs = (0..count)
+            .map(|_| Env::new(game_rules.clone(), seat, opponent))
+            .collect::<Option<Vec<Env>>>()?;
+        Some(Self { envs, next_seed: 0 })
+    }
+
+    pub fn reset(&mut self, seed: u64) -> Vec<Step> {
+        let seeds: Vec<u64> = (0..self.envs.len() as u64).map(|index| seed.wrapping_add(index)).collect();
+        self.next_seed = seed.wrapping_add(self.envs.len() as u64);
+        in_parallel(&mut self.envs, |index, env| env.reset(seeds[index]))
+    }
+
+    // One action for each environment, in order
+    pub fn step(&mut self, actions: &[usize]) -> Vec<Result<Step, RuleError>> {
+        assert_eq!(actions.len(), self.envs.len(), "one action is needed for each environment");
+        let mut steps = in_parallel(&mut self.envs, |index, env| env.step(actions[index]));
+
+        let mut seeds = vec![None; self.envs.len()];
+        for (seed, step) in seeds.iter_mut().zip(&steps) {
+            if step.as_ref().is_ok_and(|step| step.done) {
+                *seed = Some(self.next_seed);
+                self.next_seed = self.next_seed.wrapping_add(1);
+            }
+        }
+
+        let fresh = in_parallel(&mut self.envs, |index, env| seeds[index].map(|seed| env.reset(seed)));
+        for (step, fresh) in steps.iter_mut().zip(fresh) {
+            if let (Ok(step), Some(fresh)) = (step, fresh) {
+                step.observation = fresh.observation;
+                step.legal_mask = fresh.legal_mask;
+            }
+        }
+        steps
+    }
+}
+
+// Runs `work` on every environment, splitting them between the available cores
+fn in_parallel<T: Send>(envs: &mut [Env], work: impl Fn(usize, &mut Env) -> T + Sync) -> Vec<T> {
+    let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+    let chunk_size = envs.len().div_ceil(threads).max(1);
+    let work = &work;
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = envs.chunks_mut(chunk_size)
+            .enumerate()
+            .map(|(chunk, envs)| scope.spawn(move || {
+                envs.iter_mut()
+                    .enumerate()
+                    .map(|(index, env)| work(chunk * chunk_size + index, env))
+                    .collect::<Vec<T>>()
+            }))
+            .collect();
+        handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect()
+    })
+}
@@ -0,0 +1,236 @@
+use bevy::prelude::*;
+use bevy::ecs::system::SystemParam;
+use serde::{Deserialize, Serialize};
+use crate::cards::{Seat, SubType};
+use crate::rules::Action;
+
+/****************
+ * INPUT EVENTS
+ ****************/
+
+// Sent by whatever is driving a seat (currently the card buttons) to ask the rules to take an action
+#[derive(Event, Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PlayRequest {
+    pub seat: Seat,
+    pub action: Action
+}
+
+/*****************
+ * GAME EVENTS
+ *****************/
+
+#[derive(Event, Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CardDrawn {
+    pub seat: Seat,
+    pub card: Entity
+}
+
+// Sent for every card which leaves a hand onto a tableau, alongside any more specific event
+#[derive(Event, Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CardPlayed {
+    pub seat: Seat,
+    pub card: Entity,
+    pub sub_type: SubType
+}
+
+#[derive(Event, Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct HazardApplied {
+    pub seat: Seat,
+    pub target: Seat,
+    pub card: Entity,
+    pub hazard: SubType
+}
+
+#[derive(Event, Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SafetyPlayed {
+    pub seat: Seat,
+    pub card: Entity,
+    pub safety: SubType
+}
+
+// Sent instead of `SafetyPlayed` when the safety answers the hazard that was just played
+#[derive(Event, Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CoupFourre {
+    pub seat: Seat,
+    pub card: Entity,
+    pub safety: SubType,
+    pub hazard: SubType
+}
+
+#[derive(Event, Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CardDiscarded {
+    pub seat: Seat,
+    pub card: Entity,
+    pub sub_type: SubType
+}
+
+// `winner` is the seat which completed the trip, if anybody did
+#[derive(Event, Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct HandEnded {
+    pub winner: Option<Seat>,
+    pub player_points: i32,
+    pub opponent_points: i32
+}
+
+#[derive(Event, Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct MatchEnded {
+    pub winner: Seat,
+    pub player_score: i32,
+    pub opponent_score: i32
+}
+
+// Any of the game events, as produced by the rules in `Game`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum GameEvent {
+    CardDrawn(CardDrawn),
+    CardPlayed(CardPlayed),
+    HazardApplied(HazardApplied),
+    SafetyPlayed(SafetyPlayed),
+    CoupFourre(CoupFourre),
+    CardDiscarded(CardDiscarded),
+    HandEnded(HandEnded),
+    MatchEnded(MatchEnded)
+}
+impl GameEvent {
+    // The card the event is about, if it is about one
+    pub fn card(&self) -> Option<Entity> {
+        match self {
+            GameEvent::CardDrawn(event) => Some(event.card),
+            GameEvent::CardPlayed(event) => Some(event.card),
+            GameEvent::HazardApplied(event) => Some(event.card),
+            GameEvent::SafetyPlayed(event) => Some(event.card),
+            GameEvent::CoupFourre(event) => Some(event.card),
+            GameEvent::CardDiscarded(event) => Some(event.card),
+            GameEvent::HandEnded(_) | GameEvent::MatchEnded(_) => None,
+        }
+    }
+
+    // The same event about another card, for when cards go by different ids elsewhere
+    pub fn with_card(mut self, card: Entity) -> Self {
+        match &mut self {
+            GameEvent::CardDrawn(event) => event.card = card,
+            GameEvent::CardPlayed(event) => event.card = card,
+            GameEvent::HazardApplied(event) => event.card = card,
+            GameEvent::SafetyPlayed(event) => event.card = card,
+            GameEvent::CoupFourre(event) => event.card = card,
+            GameEvent::CardDiscarded(event) => event.card = card,
+            GameEvent::HandEnded(_) | GameEvent::MatchEnded(_) => {}
+        }
+        self
+    }
+
+    // The event as `seat` is allowed to see it, or somebody at neither seat with `None`:
+    // cards drawn by anybody else stay face down
+    pub fn seen_by(self, seat: Option<Seat>) -> Self {
+        match self {
+            GameEvent::CardDrawn(drawn) if Some(drawn.seat) != seat => self.with_card(Entity::PLACEHOLDER),
+            _ => self,
+        }
+    }
+}
+
+// Bundles the writers so the rules systems don't need a parameter per event
+#[derive(SystemParam)]
+pub struct GameEventWriters<'w> {
+    pub card_drawn: EventWriter<'w, CardDrawn>,
+    pub card_played: EventWriter<'w, CardPlayed>,
+    pub hazard_applied: EventWriter<'w, HazardApplied>,
+    pub safety_played: EventWriter<'w, SafetyPlayed>,
+    pub coup_fourre: EventWriter<'w, CoupFourre>,
+    pub card_discarded: EventWriter<'w, CardDiscarded>,
+    pub hand_ended: EventWriter<'w, HandEnded>,
+    pub match_ended: EventWriter<'w, MatchEnded>
+}
+impl GameEventWriters<'_> {
+    pub fn send(&mut self, event: GameEvent) {
+        match event {
+            GameEvent::CardDrawn(event) => self.card_drawn.send(event),
+            GameEvent::CardPlayed(event) => self.card_played.send(event),
+            GameEvent::HazardApplied(event) => self.hazard_applied.send(event),
+            GameEvent::SafetyPlayed(event) => self.safety_played.send(event),
+            GameEvent::CoupFourre(event) => self.coup_fourre.send(event),
+            GameEvent::CardDiscarded(event) => self.card_discarded.send(event),
+            GameEvent::HandEnded(event) => self.hand_ended.send(event),
+            GameEvent::MatchEnded(event) => self.match_ended.send(event),
+        }
+    }
+
+    pub fn send_all(&mut self, events: impl IntoIterator<Item = GameEvent>) {
+        for event in events {
+            self.send(event);
+        }
+    }
+}
+
+// Reads every game event back as a `GameEvent`. There is one action per frame, so reading
+// the action events before the draws and the end of the hand keeps them in the order they happened.
+#[derive(SystemParam)]
+pub struct GameEventReaders<'w, 's> {
+    pub card_played: EventReader<'w, 's, CardPlayed>,
+    pub hazard_applied: EventReader<'w, 's, HazardApplied>,
+    pub safety_played: EventReader<'w, 's, SafetyPlayed>,
+    pub coup_fourre: EventReader<'w, 's, CoupFourre>,
+    pub card_discarded: EventReader<'w, 's, CardDiscarded>,
+    pub card_drawn: EventReader<'w, 's, CardDrawn>,
+    pub hand_ended: EventReader<'w, 's, HandEnded>,
+    pub match_ended: EventReader<'w, 's, MatchEnded>
+}
+impl GameEventReaders<'_, '_> {
+    pub fn read(&mut self) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        events.extend(self.card_played.iter().map(|event| GameEvent::CardPlayed(*event)));
+        events.extend(self.hazard_applied.iter().map(|event| GameEvent::HazardApplied(*event)));
+        events.extend(self.safety_played.iter().map(|event| GameEvent::SafetyPlayed(*event)));
+        events.extend(self.coup_fourre.iter().map(|event| GameEvent::CoupFourre(*event)));
+        events.extend(self.card_discarded.iter().map(|event| GameEvent::CardDiscarded(*event)));
+        events.extend(self.card_drawn.iter().map(|event| GameEvent::CardDrawn(*event)));
+        events.extend(self.hand_ended.iter().map(|event| GameEvent::HandEnded(*event)));
+        events.extend(self.match_ended.iter().map(|event| GameEvent::MatchEnded(*event)));
+        events
+    }
+}
+
+pub struct GameEvents;
+impl Plugin for GameEvents {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<PlayRequest>()
+            .add_event::<CardDrawn>()
+            .add_event::<CardPlayed>()
+            .add_event::<HazardApplied>()
+            .add_event::<SafetyPlayed>()
+            .add_event::<CoupFourre>()
+            .add_event::<CardDiscarded>()
+            .add_event::<HandEnded>()
+            .add_event::<MatchEnded>()
+            .add_systems(PostUpdate, log_game_events);
+    }
+}
+
+// Stands in for the old println! announcements, anything else can read the same events
+fn log_game_events(mut played: EventReader<CardPlayed>,
+                   mut discarded: EventReader<CardDiscarded>,
+                   mut coup_fourres: EventReader<CoupFourre>,
+                   mut hands: EventReader<HandEnded>,
+                   mut matches: EventReader<MatchEnded>)
+{
+    for event in played.iter() {
+        info!("{:?} played {:?}", event.seat, event.sub_type);
+    }
+
+    for event in discarded.iter() {
+        info!("{:?} discarded {:?}", event.seat, event.sub_type);
+    }
+
+    for event in coup_fourres.iter() {
+        info!("{:?} coup fourré! {:?} against {:?}", event.seat, event.safety, event.hazard);
+    }
+
+    for event in hands.iter() {
+        info!("Hand over, player +{} opponent +{}", event.player_points, event.opponent_points);
+    }
+
+    for event in matches.iter() {
+        info!("{:?} wins the match {} to {}", event.winner, event.player_score, event.opponent_score);
+    }
+}
@@ -0,0 +1,84 @@
+use std::fmt;
+use bevy::prelude::*;
+use bevy::ecs::query::Has;
+use crate::cards::*;
+
+/*******************
+ * CARD CONSERVATION
+ *******************/
+
+// Something wrong with where the cards are, found by `card_violations`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CardViolation {
+    NoZone { card: Entity, name: String },
+    ManyZones { card: Entity, name: String, zones: Vec<&'static str> },
+    WrongCount { sub_type: SubType, expected: i32, found: i32 }
+}
+
+impl fmt::Display for CardViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CardViolation::NoZone { card, name } =>
+                write!(f, "{} ({:?}) is not in any zone", name, card),
+            CardViolation::ManyZones { card, name, zones } =>
+                write!(f, "{} ({:?}) is in several zones: {}", name, card, zones.join(", ")),
+            CardViolation::WrongCount { sub_type, expected, found } =>
+                write!(f, "Expected {} {:?} cards but found {}", expected, sub_type, found),
+        }
+    }
+}
+
+// Every card should be in exactly one zone, and the cards should add up to a full deck
+pub fn card_violations(world: &mut World) -> Vec<CardViolation> {
+    let mut query = world.query_filtered::<(Entity, &CardName, &SubType,
+                                            Has<Deck>, Has<PlayerHand>, Has<OpponentHand>,
+                                            Has<PlayerBoard>, Has<OpponentBoard>, Has<DiscardPile>), With<Card>>();
+
+    let mut violations = Vec::new();
+    let mut counts: Vec<(SubType, i32)> = DECK_COMPOSITION.iter().map(|(sub_type, _)| (*sub_type, 0)).collect();
+
+    for (card, name, sub_type, deck, player_hand, opponent_hand, player_board, opponent_board, discard) in query.iter(world) {
+        let zones: Vec<&'static str> = [
+                (deck, "Deck"),
+                (player_hand, "PlayerHand"),
+                (opponent_hand, "OpponentHand"),
+                (player_board, "PlayerBoard"),
+                (opponent_board, "OpponentBoard"),
+                (discard, "DiscardPile"),
+            ]
+            .into_iter()
+            .filter(|(in_zone, _)| *in_zone)
+            .map(|(_, zone)| zone)
+            .collect();
+
+        match zones.len() {
+            0 => violations.push(CardViolation::NoZone { card, name: name.0.clone() }),
+            1 => {}
+            _ => violations.push(CardViolation::ManyZones { card, name: name.0.clone(), zones }),
+        }
+
+        match counts.iter_mut().find(|(counted, _)| counted == sub_type) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((*sub_type, 1)),
+        }
+    }
+
+    for (sub_type, found) in counts {
+        let expected = DECK_COMPOSITION.iter()
+            .find(|(counted, _)| *counted == sub_type)
+            .map_or(0, |(_, expected)| *expected);
+
+        if found != expected {
+            violations.push(CardViolation::WrongCount { sub_type, expected, found });
+        }
+    }
+
+    violations
+}
+
+// Run in debug builds once a game has started
+pub fn check_card_conservation(world: &mut World) {
+    for violation in card_violations(world) {
+        error!("Card conservation: {}", violation);
+    }
+}
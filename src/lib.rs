@@ -0,0 +1,18 @@
+// Bevy systems routinely take long parameter lists and nested query filters
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
+
+pub mod millebornes;
+pub mod cards;
+pub mod menu;
+pub mod constants;
+pub mod events;
+pub mod invariants;
+pub mod rules;
+pub mod ui;
+pub mod view;
+pub mod ai;
+pub mod sim;
+pub mod tournament;
+pub mod env;
+pub mod training;
+pub mod net;
@@ -1,14 +1,61 @@
-pub mod millebornes;
-pub mod cards;
-pub mod menu;
-pub mod constants;
-pub mod ui;
-
+use std::time::Duration;
 use bevy::prelude::*;
-use millebornes::MilleBornes;
+use bevy_test::ai::external::{ExternalBotCommand, DEFAULT_TIMEOUT};
+use bevy_test::cards::Seat;
+use bevy_test::millebornes::MilleBornes;
+use bevy_test::net::client::{JoinServer, NetworkSettings};
+use bevy_test::training::TrainingExport;
+
+fn flag_value(flag: &str) -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    args.iter().position(|arg| arg == flag).and_then(|index| args.get(index + 1)).cloned()
+}
+
+// `--external-bot "<command>"` plays against a bot running as its own process,
+// `--bot-timeout <seconds>` is how long it gets to answer each turn
+fn external_bot() -> Option<ExternalBotCommand> {
+    let command = flag_value("--external-bot")?;
+    let timeout = match flag_value("--bot-timeout") {
+        Some(seconds) => seconds.parse().ok().and_then(|seconds| Duration::try_from_secs_f32(seconds).ok()).unwrap_or_else(|| {
+            warn!("Bad --bot-timeout {}, giving the bot {}s", seconds, DEFAULT_TIMEOUT.as_secs_f32());
+            DEFAULT_TIMEOUT
+        }),
+        None => DEFAULT_TIMEOUT,
+    };
+    Some(ExternalBotCommand { command, timeout })
+}
 
 fn main() {
-    App::new()
-        .add_plugins((DefaultPlugins, MilleBornes))
-        .run();
+    let mut app = App::new();
+    app.add_plugins((DefaultPlugins, MilleBornes));
+
+    if let Some(external) = external_bot() {
+        app.insert_resource(external);
+    }
+
+    // `--export-training <file>` adds the human seats' moves to a training data file after each hand
+    if let Some(path) = flag_value("--export-training") {
+        app.insert_resource(TrainingExport { path });
+    }
+
+    // `--name <name>` is who you are in network games, `--connect <address>` joins one straight away.
+    // `--watch <address>` looks on instead, and `--coach player|opponent` asks to see that seat's hand.
+    let mut network = NetworkSettings::default();
+    if let Some(name) = flag_value("--name") {
+        network.name = name;
+    }
+    let coach = flag_value("--coach").and_then(|seat| match seat.as_str() {
+        "player" => Some(Seat::Player),
+        "opponent" => Some(Seat::Opponent),
+        _ => None,
+    });
+    let joining = flag_value("--connect").map(|address| (address, None))
+        .or_else(|| flag_value("--watch").map(|address| (address, Some(coach))));
+    if let Some((address, watch)) = joining {
+        app.insert_resource(JoinServer { address: address.clone(), name: network.name.clone(), watch });
+        network.address = address;
+    }
+    app.insert_resource(network);
+
+    app.run();
 }
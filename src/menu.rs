@@ -1,5 +1,15 @@
+use std::thread;
 use bevy::prelude::*;
+use crate::ai::bot::{Controller, Seats};
+use crate::ai::external::ExternalBotCommand;
+use crate::ai::heuristic::Difficulty;
+use crate::ai::personality::Personalities;
+use crate::cards::Seat;
 use crate::constants::*;
+use crate::net::client::{Connection, JoinServer, LobbyInfo, NetworkSettings};
+use crate::net::protocol::{ClientMessage, DEFAULT_PORT};
+use crate::net::server::{Server, ServerConfig};
+use crate::rules::GameRules;
 
 /**************
  * MENU SETUP
@@ -9,6 +19,7 @@ pub struct Menu;
 impl Plugin for Menu {
     fn build(&self, app: &mut App) {
         app
+            .init_resource::<Difficulty>()
             // Menu
             .add_systems(
                 OnEnter(GameState::Menu), 
@@ -18,9 +29,28 @@ impl Plugin for Menu {
                 Update, 
                 update_menu.run_if(in_state(GameState::Menu))
             )
+            .add_systems(
+                Update,
+                type_address.run_if(in_state(GameState::Menu))
+            )
             .add_systems(
                 OnExit(GameState::Menu), 
                 cleanup_menu
+            )
+            // Lobby
+            .add_systems(
+                OnEnter(GameState::Lobby),
+                setup_lobby
+            )
+            .add_systems(
+                Update, (
+                    show_lobby,
+                    update_lobby
+                ).chain().run_if(in_state(GameState::Lobby))
+            )
+            .add_systems(
+                OnExit(GameState::Lobby),
+                cleanup_lobby
             );
     }
 }
@@ -30,53 +60,149 @@ pub struct MenuData {
     button_entity: Entity,
 }
  
-pub fn setup_menu(mut commands: Commands) {
+#[derive(Component)]
+pub enum MenuButton {
+    NewGame,
+    Difficulty,
+    Style,
+    Host,
+    Join,
+    Watch
+}
+
+// The label on the difficulty button, so it can follow the setting
+#[derive(Component)]
+pub struct DifficultyText;
+
+// A bot given on the command line takes the place of the built in ones
+fn difficulty_label(difficulty: &Difficulty, external: Option<&ExternalBotCommand>) -> String {
+    match external {
+        Some(_) => "Opponent: External".to_string(),
+        None => format!("Opponent: {}", difficulty.name()),
+    }
+}
+
+// The label on the style button, one of the personalities loaded at startup
+#[derive(Component)]
+pub struct StyleText;
+
+fn style_label(personalities: &Personalities) -> String {
+    format!("Style: {}", personalities.chosen().name)
+}
+
+// The label on the join button, which shows the address as it is typed
+#[derive(Component)]
+pub struct AddressText;
+
+fn join_label(network: &NetworkSettings) -> String {
+    format!("Join {}", network.address)
+}
+
+fn menu_button(parent: &mut ChildBuilder, button: impl Component, text: String, text_marker: impl Bundle) {
+    parent.spawn((
+        button,
+        ButtonBundle {
+            style: Style {
+                width: Val::Px(300.),
+                height: Val::Px(65.),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: NORMAL_BUTTON.into(),
+            ..default()
+        })).with_children(|parent| {
+            parent.spawn((
+                text_marker,
+                TextBundle::from_section(
+                    text,
+                    TextStyle {
+                        font_size: 40.,
+                        color: TEXT_COLOUR,
+                        ..default()
+                    }
+                )));
+            });
+}
+
+pub fn setup_menu(mut commands: Commands,
+                  difficulty: Res<Difficulty>,
+                  personalities: Res<Personalities>,
+                  network: Res<NetworkSettings>,
+                  external: Option<Res<ExternalBotCommand>>)
+{
     let button_entity = commands.spawn(
         NodeBundle {
             style: Style {
                 width: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
                 justify_content: JustifyContent::Center,
                 align_items: AlignItems::Center,
+                row_gap: Val::Px(20.),
                 ..default()
             },
             ..default()
         }).with_children(|parent| {
-            parent.spawn(
-                ButtonBundle {
-                    style: Style {
-                        width: Val::Px(200.),
-                        height: Val::Px(65.),
-                        justify_content: JustifyContent::Center,
-                        align_items: AlignItems::Center,
-                        ..default()
-                    },
-                    background_color: NORMAL_BUTTON.into(),
-                    ..default()
-                }).with_children(|parent| {
-                    parent.spawn(
-                        TextBundle::from_section(
-                            "New Game",
-                            TextStyle {
-                                font_size: 40.,
-                                color: TEXT_COLOUR.into(),
-                                ..default()
-                            }
-                        ));
-                    });
+            menu_button(parent, MenuButton::NewGame, "New Game".into(), ());
+            menu_button(parent, MenuButton::Difficulty, difficulty_label(&difficulty, external.as_deref()), DifficultyText);
+            menu_button(parent, MenuButton::Style, style_label(&personalities), StyleText);
+            menu_button(parent, MenuButton::Host, "Host Game".into(), ());
+            menu_button(parent, MenuButton::Join, join_label(&network), AddressText);
+            menu_button(parent, MenuButton::Watch, "Watch".into(), ());
         }).id();
 
         commands.insert_resource(MenuData { button_entity });
 }
 
 pub fn update_menu(mut next_state: ResMut<NextState<GameState>>,
-                   mut interaction_query: Query<(&Interaction, &mut BackgroundColor),
-                                                (Changed<Interaction>, With<Button>)>) 
+                   mut difficulty: ResMut<Difficulty>,
+                   mut personalities: ResMut<Personalities>,
+                   mut seats: ResMut<Seats>,
+                   network: Res<NetworkSettings>,
+                   mut commands: Commands,
+                   external: Option<Res<ExternalBotCommand>>,
+                   mut interaction_query: Query<(&Interaction, &MenuButton, &mut BackgroundColor),
+                                                (Changed<Interaction>, With<Button>)>,
+                   mut difficulty_text: Query<&mut Text, (With<DifficultyText>, Without<StyleText>)>,
+                   mut style_text: Query<&mut Text, With<StyleText>>)
 {
-    for (interaction, mut colour) in &mut interaction_query {
+    for (interaction, button, mut colour) in &mut interaction_query {
         match *interaction {
             Interaction::Pressed => {
                 *colour = PRESSED_BUTTON.into();
-                next_state.set(GameState::SetupGame);
+                match button {
+                    MenuButton::NewGame => {
+                        let bot = match &external {
+                            Some(external) => external.bot(),
+                            None => difficulty.bot_with(personalities.chosen()),
+                        };
+                        seats.opponent = Controller::computer(bot);
+                        next_state.set(GameState::SetupGame);
+                    }
+                    MenuButton::Difficulty => {
+                        *difficulty = difficulty.next();
+                        for mut text in &mut difficulty_text {
+                            text.sections[0].value = difficulty_label(&difficulty, external.as_deref());
+                        }
+                    }
+                    MenuButton::Style => {
+                        personalities.choose_next();
+                        for mut text in &mut style_text {
+                            text.sections[0].value = style_label(&personalities);
+                        }
+                    }
+                    MenuButton::Host => {
+                        if let Some(address) = host_game() {
+                            commands.insert_resource(JoinServer { address, name: network.name.clone(), watch: None });
+                        }
+                    }
+                    MenuButton::Join => {
+                        commands.insert_resource(JoinServer { address: network.address.clone(), name: network.name.clone(), watch: None });
+                    }
+                    MenuButton::Watch => {
+                        commands.insert_resource(JoinServer { address: network.address.clone(), name: network.name.clone(), watch: Some(None) });
+                    }
+                }
             }
             Interaction::Hovered => {
                 *colour = HOVERED_BUTTON.into();
@@ -91,4 +217,261 @@ pub fn update_menu(mut next_state: ResMut<NextState<GameState>>,
 pub fn cleanup_menu(mut commands: Commands, menu_data: Res<MenuData>) {
     commands.entity(menu_data.button_entity).despawn_recursive();
 }
- 
\ No newline at end of file
+
+// Starts a server for one match on this machine, returning the address to join it at
+fn host_game() -> Option<String> {
+    let server = match Server::bind(("0.0.0.0", DEFAULT_PORT), ServerConfig::default()) {
+        Ok(server) => server,
+        Err(error) => {
+            error!("Couldn't host on port {}: {}", DEFAULT_PORT, error);
+            return None;
+        }
+    };
+
+    thread::spawn(move || match server.run() {
+        Ok(ended) => info!("Hosted match won by {:?}", ended.winner),
+        Err(reason) => info!("Hosted match ended: {}", reason),
+    });
+    Some(format!("127.0.0.1:{}", DEFAULT_PORT))
+}
+
+// Typing edits the address the join button connects to, backspace takes a character off
+fn type_address(mut characters: EventReader<ReceivedCharacter>,
+                keys: Res<Input<KeyCode>>,
+                mut network: ResMut<NetworkSettings>,
+                mut address_text: Query<&mut Text, With<AddressText>>)
+{
+    let before = network.address.clone();
+    for typed in characters.iter() {
+        if typed.char.is_ascii_alphanumeric() || matches!(typed.char, '.' | ':' | '-') {
+            network.address.push(typed.char);
+        }
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        network.address.pop();
+    }
+
+    if network.address != before {
+        for mut text in &mut address_text {
+            text.sections[0].value = join_label(&network);
+        }
+    }
+}
+
+/********
+ * LOBBY
+ ********/
+
+// Bots the host can put in a seat, in the order the button goes through them
+const LOBBY_BOTS: [&str; 5] = ["random", "easy", "medium", "hard", "expert"];
+const LOBBY_MILES: [i32; 2] = [700, 1000];
+const LOBBY_WINNING_SCORES: [i32; 4] = [1000, 2500, 5000, 10000];
+const LOBBY_HAND_SIZES: [i32; 3] = [5, 6, 7];
+
+#[derive(Resource)]
+pub struct LobbyUI {
+    root: Entity
+}
+
+#[derive(Component, Clone, Copy)]
+pub enum LobbyButton {
+    Sit(Seat),
+    StandUp,
+    Bot(Seat),
+    Miles,
+    WinningScore,
+    HandSize,
+    Ready,
+    Leave
+}
+
+// The value after `current` in `options`, going back to the start after the last
+fn next_option<T: PartialEq + Copy>(options: &[T], current: T) -> T {
+    let index = options.iter().position(|option| *option == current).map_or(0, |index| index + 1);
+    options[index % options.len()]
+}
+
+// The bot after `current` for the host's button, with an empty seat after the last one
+fn next_bot(current: Option<&str>) -> Option<String> {
+    match current {
+        None => Some(LOBBY_BOTS[0].to_string()),
+        Some(bot) => LOBBY_BOTS.iter()
+            .position(|option| *option == bot)
+            .and_then(|index| LOBBY_BOTS.get(index + 1))
+            .map(|bot| bot.to_string()),
+    }
+}
+
+fn lobby_text(text: String, font_size: f32) -> TextBundle {
+    TextBundle::from_section(text, TextStyle { font_size, color: TEXT_COLOUR, ..default() })
+}
+
+fn lobby_button(parent: &mut ChildBuilder, button: LobbyButton, text: String) {
+    parent.spawn((
+        button,
+        ButtonBundle {
+            style: Style {
+                padding: UiRect::axes(Val::Px(15.), Val::Px(8.)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: NORMAL_BUTTON.into(),
+            ..default()
+        })).with_children(|parent| {
+            parent.spawn(lobby_text(text, 25.));
+        });
+}
+
+fn setup_lobby(mut commands: Commands) {
+    let root = commands.spawn(
+        NodeBundle {
+            style: Style {
+                width: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(15.),
+                ..default()
+            },
+            background_color: NORMAL_BUTTON.into(),
+            ..default()
+        }).with_children(|parent| {
+            parent.spawn(lobby_text("Connecting...".into(), 40.));
+        }).id();
+
+    commands.insert_resource(LobbyUI { root });
+}
+
+fn cleanup_lobby(mut commands: Commands, lobby_ui: Res<LobbyUI>) {
+    commands.entity(lobby_ui.root).despawn_recursive();
+    commands.remove_resource::<LobbyUI>();
+}
+
+// Redrawn from scratch whenever the server says something changed
+fn show_lobby(mut commands: Commands, lobby_ui: Res<LobbyUI>, info: Option<Res<LobbyInfo>>) {
+    let Some(info) = info.filter(|info| info.is_changed()) else { return };
+    let lobby = &info.lobby;
+    let host = info.is_host();
+
+    commands.entity(lobby_ui.root).despawn_descendants().with_children(|parent| {
+        parent.spawn(lobby_text("Lobby".into(), 50.));
+
+        for seat in [Seat::Player, Seat::Opponent] {
+            parent.spawn(NodeBundle {
+                style: Style { column_gap: Val::Px(15.), align_items: AlignItems::Center, ..default() },
+                ..default()
+            }).with_children(|row| {
+                let sitter = lobby.sitter(seat).unwrap_or_else(|| "empty".into());
+                row.spawn(lobby_text(format!("{:?}: {}", seat, sitter), 30.));
+
+                if info.me.is_some() && lobby.sitter(seat).is_none() {
+                    lobby_button(row, LobbyButton::Sit(seat), "Sit here".into());
+                }
+                if host && lobby.players.iter().all(|player| player.seat != Some(seat)) {
+                    lobby_button(row, LobbyButton::Bot(seat), "Change bot".into());
+                }
+            });
+        }
+
+        for player in &lobby.players {
+            let mut line = player.name.clone();
+            if lobby.host().is_some_and(|host| host.id == player.id) {
+                line.push_str(" (host)");
+            }
+            match player.seat {
+                Some(seat) => line.push_str(&format!(" - {:?}", seat)),
+                None => line.push_str(" - standing"),
+            }
+            if player.ready {
+                line.push_str(" - ready");
+            }
+            parent.spawn(lobby_text(line, 25.));
+        }
+        for watcher in &lobby.watchers {
+            let line = match watcher.coach {
+                Some(seat) => format!("{} - coaching {:?}", watcher.name, seat),
+                None => format!("{} - looking on", watcher.name),
+            };
+            parent.spawn(lobby_text(line, 25.));
+        }
+
+        let rules = &lobby.game_rules;
+        parent.spawn(NodeBundle {
+            style: Style { column_gap: Val::Px(15.), ..default() },
+            ..default()
+        }).with_children(|row| {
+            let labels = [
+                (LobbyButton::Miles, format!("Trip: {} miles", rules.miles)),
+                (LobbyButton::WinningScore, format!("Play to: {}", rules.winning_score)),
+                (LobbyButton::HandSize, format!("Hand: {} cards", rules.hand_size)),
+            ];
+            for (button, label) in labels {
+                if host {
+                    lobby_button(row, button, label);
+                }
+                else {
+                    row.spawn(lobby_text(label, 25.));
+                }
+            }
+        });
+
+        parent.spawn(NodeBundle {
+            style: Style { column_gap: Val::Px(15.), ..default() },
+            ..default()
+        }).with_children(|row| {
+            if info.my_seat().is_some() {
+                lobby_button(row, LobbyButton::StandUp, "Stand up".into());
+                lobby_button(row, LobbyButton::Ready, "Ready".into());
+            }
+            lobby_button(row, LobbyButton::Leave, "Leave".into());
+        });
+    });
+}
+
+fn update_lobby(mut next_state: ResMut<NextState<GameState>>,
+                mut connection: Option<ResMut<Connection>>,
+                info: Option<Res<LobbyInfo>>,
+                mut interaction_query: Query<(&Interaction, &LobbyButton, &mut BackgroundColor),
+                                             (Changed<Interaction>, With<Button>)>)
+{
+    for (interaction, button, mut colour) in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => {
+                *colour = PRESSED_BUTTON.into();
+                if let LobbyButton::Leave = button {
+                    next_state.set(GameState::Menu);
+                    continue;
+                }
+
+                let (Some(connection), Some(info)) = (connection.as_mut(), info.as_ref()) else { continue };
+                let rules = &info.lobby.game_rules;
+                let message = match *button {
+                    LobbyButton::Sit(seat) => ClientMessage::TakeSeat { seat: Some(seat) },
+                    LobbyButton::StandUp => ClientMessage::TakeSeat { seat: None },
+                    LobbyButton::Bot(seat) => ClientMessage::SetBot { seat, bot: next_bot(info.lobby.bots[seat as usize].as_deref()) },
+                    LobbyButton::Miles => ClientMessage::SetRules {
+                        game_rules: GameRules { miles: next_option(&LOBBY_MILES, rules.miles), ..rules.clone() }
+                    },
+                    LobbyButton::WinningScore => ClientMessage::SetRules {
+                        game_rules: GameRules { winning_score: next_option(&LOBBY_WINNING_SCORES, rules.winning_score), ..rules.clone() }
+                    },
+                    LobbyButton::HandSize => ClientMessage::SetRules {
+                        game_rules: GameRules { hand_size: next_option(&LOBBY_HAND_SIZES, rules.hand_size), ..rules.clone() }
+                    },
+                    LobbyButton::Ready => ClientMessage::Ready,
+                    LobbyButton::Leave => continue,
+                };
+                if let Err(error) = connection.send(&message) {
+                    warn!("Couldn't reach the server: {}", error);
+                }
+            }
+            Interaction::Hovered => {
+                *colour = HOVERED_BUTTON.into();
+            }
+            Interaction::None => {
+                *colour = NORMAL_BUTTON.into();
+            }
+        }
+    }
+}
@@ -1,103 +1,200 @@
 // Create the Mille Bornes plugin
 use bevy::prelude::*;
+use bevy::ecs::query::Has;
+use bevy::ecs::system::EntityCommands;
+use bevy::utils::HashMap;
 use rand::thread_rng;
 use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use crate::ai::bot::{ComputerPlayers, Seats};
 use crate::constants::*;
 use crate::cards::*;
+use crate::events::*;
+#[cfg(debug_assertions)]
+use crate::invariants::check_card_conservation;
 use crate::menu::*;
+use crate::net::client::NetworkClient;
+use crate::rules::*;
+use crate::training::{export_training_data, TrainingExport};
 use crate::ui::board_ui::create_board_ui;
 use crate::ui::board_ui::update_board_ui;
+use crate::ui::board_ui::cleanup_board_ui;
 use crate::ui::card_ui::CardToUILink;
 use crate::ui::card_ui::UIToCardLink;
 use crate::ui::card_ui::get_card_colour;
+use crate::ui::analysis_ui::AnalysisUI;
+use crate::ui::count_ui::CardCountUI;
+use crate::ui::hint_ui::HintUI;
+use crate::ui::score_ui::ScoreUI;
+use crate::ui::thinking_ui::ThinkingUI;
+use crate::view::Viewpoint;
 
+// The full game: rules plus the menus and board UI a human plays through
 pub struct MilleBornes;
 
 impl Plugin for MilleBornes {
     fn build(&self, app: &mut App) {
         app
-            .add_plugins(Cards)
+            .add_plugins(MilleBornesRules)
+            .add_plugins(ComputerPlayers)
             .add_plugins(Menu)
+            .add_plugins(ScoreUI)
+            .add_plugins(CardCountUI)
+            .add_plugins(ThinkingUI)
+            .add_plugins(HintUI)
+            .add_plugins(AnalysisUI)
+            .add_plugins(NetworkClient)
             .insert_resource(ClearColor(BACKGROUND_COLOUR))
+            .init_resource::<Viewpoint>()
+            .add_systems(
+                Startup,
+                setup_camera
+            )
+            .add_systems(
+                OnEnter(GameState::SetupGame),
+                create_board_ui
+            )
+            .add_systems(
+                PreUpdate,
+                (follow_turn, update_board_ui).chain().run_if(in_state(GameState::DuringTurn))
+            )
+            .add_systems(
+                Update,
+                process_turn.in_set(TurnSet::Input)
+            )
+            .add_systems(
+                Update,
+                abandon_game.run_if(not(in_state(GameState::Menu)))
+            )
+            .add_systems(
+                PostUpdate,
+                despawn_old_ui.run_if(in_state(GameState::DuringTurn))
+            )
+            // Hand Over
+            .add_systems(
+                OnEnter(GameState::EndOfHand),
+                cleanup_board_ui
+            )
+            .add_systems(
+                OnEnter(GameState::EndOfHand),
+                export_training_data.run_if(resource_exists::<TrainingExport>())
+            )
+            .add_systems(
+                OnEnter(GameState::Menu),
+                cleanup_board_ui
+            );
+    }
+}
+
+// Seats send `PlayRequest`s during `Input` which the rules pick up in `Resolve` the same frame
+#[derive(SystemSet, Hash, Debug, Eq, PartialEq, Clone)]
+pub enum TurnSet {
+    Input,
+    Resolve
+}
+
+// Dealing and judging the game here rather than leaving it to a server, which `NetworkClient`
+// switches off while connected to one
+#[derive(SystemSet, Hash, Debug, Eq, PartialEq, Clone)]
+pub struct LocalRules;
+
+// Just the game logic, with no window or UI, so it can run under `MinimalPlugins`
+pub struct MilleBornesRules;
+
+impl Plugin for MilleBornesRules {
+    fn build(&self, app: &mut App) {
+        app
+            .add_plugins(Cards)
+            .add_plugins(GameEvents)
             .add_state::<GameState>()
             .add_state::<TurnState>()
-            // Resources 
+            // Resources
             .init_resource::<Game>()
             .init_resource::<GameRules>()
             .init_resource::<Score>()
+            .init_resource::<ActionLog>()
+            .configure_sets(
+                Update,
+                (TurnSet::Input, TurnSet::Resolve).chain()
+            )
             .add_systems(
-                Startup,
-                setup_camera
+                OnEnter(GameState::Menu),
+                reset_score
             )
-            // Game Setup
+            // Game Setup, which the server does instead when playing over the network
             .add_systems(
                 OnEnter(GameState::SetupGame), (
                     setup_game,
-                    create_board_ui,
                     begin_game
-                ).chain().after(CardSet::CardInit)
+                ).chain().after(CardSet::CardInit).in_set(LocalRules)
             )
             // Game Start
             .add_systems(
                 OnEnter(GameState::BeginGame), (
                     deal,
-                    apply_deferred.after(deal),
                     next_turn
                 ).chain()
             )
+            // Player and Opponent Turns
             .add_systems(
-                OnEnter(TurnState::PlayerTurn),
-                draw_player_card
-            )
-            .add_systems(
-                OnEnter(TurnState::OpponentTurn),
-                draw_opponent_card
-            )
-            .add_systems(
-                PreUpdate,
-                update_board_ui.run_if(in_state(GameState::DuringTurn))
+                Update,
+                resolve_play
+                    .in_set(TurnSet::Resolve)
+                    .run_if(in_state(GameState::DuringTurn))
+                    .in_set(LocalRules)
             )
-            // Player Turn
             .add_systems(
-                Update, 
-                process_player_turn.run_if(in_state(TurnState::PlayerTurn))
-            )
-            // Opponent Turn
-            .add_systems(Update,
-                process_opponent_turn.run_if(in_state(TurnState::OpponentTurn))
+                PostUpdate,
+                next_turn.run_if(in_state(GameState::NextTurn))
             )
             .add_systems(
-                PostUpdate, (
-                    despawn_old_ui.run_if(in_state(GameState::DuringTurn)),
-                    next_turn.run_if(in_state(GameState::NextTurn))
-                )
+                Last,
+                sync_card_zones.run_if(resource_changed::<Game>())
             );
+
+        // Cards only have zones once the first game has been set up
+        #[cfg(debug_assertions)]
+        app.add_systems(
+            Last,
+            check_card_conservation
+                .after(sync_card_zones)
+                .run_if(not(in_state(GameState::Menu)))
+                .run_if(not(in_state(GameState::Lobby)))
+        );
     }
 }
 
-#[derive(Resource, Default)]
-struct Game {
-    deck: Vec<Entity>
+#[derive(Resource, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Score {
+    pub player_score: i32,
+    pub opponent_score: i32,
+    // Points from the most recent hand, already included in the totals
+    pub player_hand_score: i32,
+    pub opponent_hand_score: i32,
+    pub match_winner: Option<Seat>
 }
+impl Score {
+    // Add a finished hand to the totals, returning the end of the match if it has been won
+    pub fn record_hand(&mut self, hand: &HandEnded, game_rules: &GameRules) -> Option<MatchEnded> {
+        self.player_score += hand.player_points;
+        self.opponent_score += hand.opponent_points;
+        self.player_hand_score = hand.player_points;
+        self.opponent_hand_score = hand.opponent_points;
 
-#[derive(Resource)]
-struct GameRules {
-    miles: i32,
-    hand_size: i32
-}
-impl Default for GameRules {
-    fn default() -> Self {
-        Self {
-            miles: 700,
-            hand_size: 6
+        // A tie above the winning score means another hand
+        let best = self.player_score.max(self.opponent_score);
+        if best < game_rules.winning_score || self.player_score == self.opponent_score {
+            return None;
         }
-    }
-}
 
-#[derive(Resource, Default)]
-struct Score {
-    player_score: i32,
-    opponent_score: i32
+        let winner = if self.player_score > self.opponent_score { Seat::Player } else { Seat::Opponent };
+        self.match_winner = Some(winner);
+        Some(MatchEnded {
+            winner,
+            player_score: self.player_score,
+            opponent_score: self.opponent_score
+        })
+    }
 }
 
 /*************
@@ -108,18 +205,20 @@ fn setup_camera(mut commands: Commands) {
     commands.spawn(Camera2dBundle::default());
 }
 
-fn setup_game(mut game: ResMut<Game>, mut commands: Commands, 
-              card_query: Query<(Entity, &Card)>)
+fn reset_score(mut score: ResMut<Score>) {
+    *score = Score::default();
+}
+
+fn setup_game(mut game: ResMut<Game>, game_rules: Res<GameRules>,
+              card_query: Query<(Entity, &SubType), With<Card>>)
 {
-    for (entity, _card) in card_query.iter()
-    {
-        game.deck.push(entity);
+    let mut deck: Vec<PileCard> = card_query.iter()
+        .map(|(entity, sub_type)| PileCard { entity, sub_type: *sub_type })
+        .collect();
 
-        // Tag the card as being in the deck
-        commands.entity(entity).insert(Deck);
-    }
+    deck.shuffle(&mut thread_rng());
 
-    game.deck.shuffle(&mut thread_rng());
+    *game = Game::new(deck, &game_rules);
 }
 
 fn begin_game(mut next_state: ResMut<NextState<GameState>>)
@@ -127,129 +226,174 @@ fn begin_game(mut next_state: ResMut<NextState<GameState>>)
     next_state.set(GameState::BeginGame);
 }
 
-fn deal(game_rules: Res<GameRules>, mut game: ResMut<Game>, mut commands: Commands)
+fn deal(game_rules: Res<GameRules>, mut game: ResMut<Game>, mut log: ResMut<ActionLog>, mut events: GameEventWriters)
 {
-    for _i in 0..game_rules.hand_size {
-        // This is safe because if this panics something went wrong in setup
-        let player_card = game.deck.pop().unwrap();
-        let opponent_card = game.deck.pop().unwrap();
+    log.0.clear();
+    let dealt = game.deal(game_rules.hand_size);
+    events.send_all(dealt);
+}
 
-        commands.entity(player_card).remove::<Deck>().insert(PlayerHand);
-        commands.entity(opponent_card).remove::<Deck>().insert(OpponentHand);
+/************
+ * GAME LOOP
+ ************/
+fn seat_for_turn(turn: &TurnState) -> Option<Seat> {
+    match turn {
+        TurnState::PlayerTurn => Some(Seat::Player),
+        TurnState::OpponentTurn => Some(Seat::Opponent),
+        TurnState::NoTurn => None,
     }
 }
 
-fn draw_player_card(mut game: ResMut<Game>, mut commands: Commands)
-{
-    if let Some(card) = game.deck.pop() {
-        commands.entity(card).remove::<Deck>().insert(PlayerHand);
+// People sharing the screen take turns at it, so it is turned towards whichever of them is playing
+fn follow_turn(turn: Res<State<TurnState>>, seats: Res<Seats>, mut viewpoint: ResMut<Viewpoint>) {
+    if let Some(seat) = seat_for_turn(turn.get()).filter(|seat| seats.is_human(*seat)) {
+        viewpoint.set_if_neq(Viewpoint(seat));
     }
 }
 
-fn draw_opponent_card(mut game: ResMut<Game>, mut commands: Commands)
+// Escape gives up on the game and goes back to the menu
+fn abandon_game(keys: Res<Input<KeyCode>>,
+                mut next_state: ResMut<NextState<GameState>>,
+                mut next_turn: ResMut<NextState<TurnState>>)
 {
-    if let Some(card) = game.deck.pop() {
-        commands.entity(card).remove::<Deck>().insert(OpponentHand);
+    if keys.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Menu);
+        next_turn.set(TurnState::NoTurn);
     }
 }
 
-/************
- * GAME LOOP
- ************/
-fn process_player_turn(mut interaction_query: Query<(&Interaction, &UIToCardLink, &mut BackgroundColor),
+// The buttons of whichever human seat is taking its turn drive the game.
+// Holding shift discards the clicked card instead of playing it.
+fn process_turn(mut interaction_query: Query<(&Interaction, &UIToCardLink, &mut BackgroundColor),
                                              (Changed<Interaction>, With<Button>)>,
-                       mut commands: Commands,
-                       card_query: Query<(&CardName, &CardType, &SubType), With<PlayerHand>>,
-                       board_query: Query<(Entity, &CardType, &SubType), With<PlayerBoard>>,
-                       mut next_turn: ResMut<NextState<GameState>>) 
+                card_query: Query<&CardType>,
+                game: Res<Game>,
+                turn: Res<State<TurnState>>,
+                seats: Res<Seats>,
+                keys: Res<Input<KeyCode>>,
+                mut requests: EventWriter<PlayRequest>)
 {
+    let Some(seat) = seat_for_turn(turn.get()).filter(|seat| seats.is_human(*seat)) else { return };
+    let discard = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let view = game.view(seat);
+    let legal_actions = view.legal_actions();
+
     for (interaction, ui_link, mut colour) in &mut interaction_query {
+        let Ok(card_type) = card_query.get(ui_link.card_entity) else { continue };
 
-        if let Ok((card_name, card_type, sub_type)) = card_query.get(ui_link.card_entity) {
-            let mut board_card_type = SubType::NoCard;
-            if board_query.get_single().is_ok() {
-                let (_board, _board_card, board_sub_card) = board_query.single();
-                board_card_type = *board_sub_card;
-            }
+        // Face down cards keep their back colour
+        if !view.can_see(ui_link.card_entity) {
+            continue;
+        }
+
+        let action = legal_actions.iter()
+            .filter(|action| action.card() == ui_link.card_entity)
+            .find(|action| matches!(action, Action::Discard { .. }) == discard);
 
-            if Card::is_valid(&board_card_type, card_type, sub_type) {
-                match *interaction {
-                    Interaction::Pressed => {
-                        *colour = PRESSED_BUTTON.into();
-                        println!("Player clicked {}", card_name.0);
-                        if let Ok((entity, _, _)) = board_query.get_single() {
-                            commands.entity(entity).remove::<PlayerBoard>();
-                        }
-                        
-                        commands.entity(ui_link.card_entity).insert(PlayerBoard);
-                        commands.entity(ui_link.card_entity).remove::<PlayerHand>();
-
-                        next_turn.set(GameState::NextTurn);
-                    }
-                    Interaction::Hovered => {
-                        *colour = HOVERED_BUTTON.into();
-                    }
-                    Interaction::None => {
-                        *colour = get_card_colour(card_type).into()
-                    }
+        if let Some(action) = action {
+            match *interaction {
+                Interaction::Pressed => {
+                    *colour = PRESSED_BUTTON.into();
+                    requests.send(PlayRequest { seat, action: *action });
+                }
+                Interaction::Hovered => {
+                    *colour = HOVERED_BUTTON.into();
+                }
+                Interaction::None => {
+                    *colour = get_card_colour(card_type).into()
                 }
             }
-            else {
-                *colour = get_card_colour(card_type).into();
-            }
+        }
+        else {
+            *colour = get_card_colour(card_type).into();
         }
     }
 }
 
-fn process_opponent_turn(mut interaction_query: Query<(&Interaction, &UIToCardLink, &mut BackgroundColor),
-                                                      (Changed<Interaction>, With<Button>)>,
-                         mut commands: Commands,
-                         card_query: Query<(&CardName, &CardType, &SubType), With<OpponentHand>>,
-                         board_query: Query<(Entity, &CardType, &SubType), With<OpponentBoard>>,
-                         mut next_turn: ResMut<NextState<GameState>>) 
+fn resolve_play(mut requests: EventReader<PlayRequest>,
+                mut game: ResMut<Game>,
+                mut log: ResMut<ActionLog>,
+                mut next_turn: ResMut<NextState<GameState>>,
+                mut events: GameEventWriters)
 {
-    for (interaction, ui_link, mut colour) in &mut interaction_query {
+    for request in requests.iter() {
+        let before = game.clone();
 
-        if let Ok((card_name, card_type, sub_type)) = card_query.get(ui_link.card_entity) {
-            let mut board_card_type = SubType::NoCard;
-            if board_query.get_single().is_ok() {
-                board_card_type = *board_query.single().2;
-            }
+        // Anything the rules refuse is dropped, the seat still has to act
+        if let Ok(played) = game.apply(request.seat, request.action) {
+            log.0.push(LoggedAction { before, seat: request.seat, action: request.action });
+            events.send_all(played);
+            next_turn.set(GameState::NextTurn);
 
-            if Card::is_valid(&board_card_type, card_type, sub_type) {
-                match *interaction {
-                    Interaction::Pressed => {
-                        *colour = PRESSED_BUTTON.into();
-                        println!("Opponent clicked {}", card_name.0);
-                        if let Ok((entity, _, _)) = board_query.get_single() {
-                            commands.entity(entity).remove::<OpponentBoard>();
-                        }
-
-                        commands.entity(ui_link.card_entity).insert(OpponentBoard);
-                        commands.entity(ui_link.card_entity).remove::<OpponentHand>();
-
-                        next_turn.set(GameState::NextTurn);
-                    }
-                    Interaction::Hovered => {
-                        *colour = HOVERED_BUTTON.into();
-                    }
-                    Interaction::None => {
-                        *colour = get_card_colour(card_type).into()
-                    }
-                }
-            }
-            else {
-                *colour = get_card_colour(card_type).into();
-            }
+            // One card per turn
+            break;
         }
     }
 }
 
+// Mirror where `Game` says each card is onto the zone marker components
+fn sync_card_zones(game: Res<Game>, mut commands: Commands,
+                   card_query: Query<(Entity, Option<&DeckPosition>, Option<&DiscardPosition>,
+                                      Has<Deck>, Has<PlayerHand>, Has<OpponentHand>,
+                                      Has<PlayerBoard>, Has<OpponentBoard>, Has<DiscardPile>, Has<Covered>), With<Card>>)
+{
+    let zones: HashMap<Entity, Zone> = game.zones().into_iter().collect();
+
+    for (entity, deck_position, discard_position, deck, player_hand, opponent_hand,
+         player_board, opponent_board, discard, covered) in card_query.iter()
+    {
+        let zone = zones.get(&entity).copied();
+        let mut card = commands.entity(entity);
+
+        sync_marker(&mut card, deck, matches!(zone, Some(Zone::Deck(_))), Deck);
+        sync_marker(&mut card, player_hand, zone == Some(Zone::Hand(Seat::Player)), PlayerHand);
+        sync_marker(&mut card, opponent_hand, zone == Some(Zone::Hand(Seat::Opponent)), OpponentHand);
+        sync_marker(&mut card, player_board, matches!(zone, Some(Zone::Board { seat: Seat::Player, .. })), PlayerBoard);
+        sync_marker(&mut card, opponent_board, matches!(zone, Some(Zone::Board { seat: Seat::Opponent, .. })), OpponentBoard);
+        sync_marker(&mut card, discard, matches!(zone, Some(Zone::Discard(_))), DiscardPile);
+        sync_marker(&mut card, covered, matches!(zone, Some(Zone::Board { covered: true, .. })), Covered);
+
+        let wanted_deck_position = match zone {
+            Some(Zone::Deck(position)) => Some(DeckPosition(position)),
+            _ => None,
+        };
+        sync_position(&mut card, deck_position.copied(), wanted_deck_position);
+
+        let wanted_discard_position = match zone {
+            Some(Zone::Discard(position)) => Some(DiscardPosition(position)),
+            _ => None,
+        };
+        sync_position(&mut card, discard_position.copied(), wanted_discard_position);
+    }
+}
+
+// Only touch components which are wrong, so the UI doesn't see spurious additions and removals
+fn sync_marker<T: Component>(card: &mut EntityCommands, has: bool, wanted: bool, marker: T) {
+    if wanted && !has {
+        card.insert(marker);
+    }
+    else if has && !wanted {
+        card.remove::<T>();
+    }
+}
+
+fn sync_position<T: Component + PartialEq>(card: &mut EntityCommands, current: Option<T>, wanted: Option<T>) {
+    if current == wanted {
+        return;
+    }
+
+    match wanted {
+        Some(position) => { card.insert(position); }
+        None => { card.remove::<T>(); }
+    }
+}
+
 fn despawn_old_ui(mut commands: Commands,
                   mut player_board_removals: RemovedComponents<PlayerBoard>,
                   mut player_card_removals: RemovedComponents<PlayerHand>,
                   mut opponent_board_removals: RemovedComponents<OpponentBoard>,
                   mut opponent_card_removals: RemovedComponents<OpponentHand>,
+                  covered_query: Query<&CardToUILink, Added<Covered>>,
                   query: Query<&CardToUILink>)
 {
     for entity in player_board_removals.iter() {
@@ -275,22 +419,40 @@ fn despawn_old_ui(mut commands: Commands,
             commands.entity(ui_entity.ui_entity).despawn_recursive();
         }
     }
+
+    for ui_entity in covered_query.iter() {
+        commands.entity(ui_entity.ui_entity).despawn_recursive();
+    }
 }
 
-fn next_turn(current_state: Res<State<TurnState>>,
-             mut next_state: ResMut<NextState<TurnState>>,
-             mut next_game_state: ResMut<NextState<GameState>>)
+fn next_turn(mut next_state: ResMut<NextState<TurnState>>,
+             mut next_game_state: ResMut<NextState<GameState>>,
+             mut game: ResMut<Game>,
+             game_rules: Res<GameRules>,
+             mut score: ResMut<Score>,
+             mut events: GameEventWriters)
 {
-    match current_state.get() {
-        TurnState::PlayerTurn => next_state.set(TurnState::OpponentTurn),
-        TurnState::OpponentTurn => next_state.set(TurnState::PlayerTurn),
-        TurnState::NoTurn => next_state.set(TurnState::PlayerTurn),
+    for event in game.next_turn() {
+        if let GameEvent::HandEnded(hand) = event {
+            if let Some(ended) = score.record_hand(&hand, &game_rules) {
+                events.match_ended.send(ended);
+            }
+        }
+        events.send(event);
+    }
+
+    match game.turn {
+        Some(Seat::Player) => next_state.set(TurnState::PlayerTurn),
+        Some(Seat::Opponent) => next_state.set(TurnState::OpponentTurn),
+        None => {
+            next_state.set(TurnState::NoTurn);
+            next_game_state.set(GameState::EndOfHand);
+            return;
+        }
     }
 
     next_game_state.set(GameState::DuringTurn);
 }
 
 // todo
-// board
 // better UI plugin
-// ai
\ No newline at end of file
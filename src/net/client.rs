@@ -0,0 +1,543 @@
+// The app's client mode. The rules run on the server; this end forwards its seat's
+// moves and mirrors each `PlayerView` it is sent onto the local cards, so the board UI
+// works just as it does for a local game. If the connection drops during the match it
+// keeps trying to rejoin, and rebuilds the board from the snapshot the server sends back.
+// Watching mirrors the table the same way, with both seats remote and no input at all.
+use std::io::{self, BufReader};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use crate::ai::bot::{Controller, Seats};
+use crate::cards::*;
+use crate::constants::*;
+use crate::events::*;
+use crate::millebornes::{LocalRules, Score, TurnSet};
+use crate::rules::*;
+use crate::view::{PublicView, Viewpoint};
+use super::protocol::*;
+
+// The server to join once the app reaches the menu, given on the command line
+#[derive(Resource, Debug, Clone)]
+pub struct JoinServer {
+    pub address: String,
+    pub name: String,
+    // Look on instead of playing, `Some(seat)` asks to coach that seat
+    pub watch: Option<Option<Seat>>
+}
+
+// Looking on at somebody else's game
+#[derive(Resource, Debug, Clone)]
+pub struct Spectating {
+    // The seat whose hand we're shown, otherwise only what's on the table
+    pub coach: Option<Seat>
+}
+
+// Who we are to other people and where we last joined, for the menu's host and join buttons
+#[derive(Resource, Debug, Clone)]
+pub struct NetworkSettings {
+    pub name: String,
+    pub address: String
+}
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self { name: "Player".into(), address: format!("127.0.0.1:{}", DEFAULT_PORT) }
+    }
+}
+
+// The lobby as the server last described it, while waiting for the match to start
+#[derive(Resource, Debug, Clone)]
+pub struct LobbyInfo {
+    // Our own id in `lobby.players`, `None` when watching
+    pub me: Option<usize>,
+    pub lobby: Lobby
+}
+impl LobbyInfo {
+    pub fn is_host(&self) -> bool {
+        self.lobby.host().is_some_and(|host| Some(host.id) == self.me)
+    }
+
+    pub fn my_seat(&self) -> Option<Seat> {
+        self.lobby.player(self.me?).and_then(|player| player.seat)
+    }
+}
+
+// How often to try getting back to the server after the connection drops, and when to give up
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+const RECONNECT_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Resource)]
+pub struct Connection {
+    address: String,
+    writer: TcpStream,
+    // Messages from the server, read on their own thread
+    incoming: Mutex<Receiver<ServerMessage>>,
+    // Our id in the lobby, once the server has said
+    id: Option<usize>,
+    // For getting our seat back if the connection drops
+    session: Option<String>,
+    // When the connection dropped, and when we last tried to rejoin
+    lost: Option<(Instant, Instant)>,
+    // A table that came before there was a board to show it on
+    deferred: Option<ServerMessage>,
+    cards: CardMap
+}
+
+impl Connection {
+    pub fn connect(address: &str, name: &str) -> io::Result<Self> {
+        Self::new(address, &ClientMessage::Join { name: name.to_string() })
+    }
+
+    pub fn watch(address: &str, name: &str, coach: Option<Seat>) -> io::Result<Self> {
+        Self::new(address, &ClientMessage::Watch { name: name.to_string(), coach })
+    }
+
+    fn new(address: &str, first: &ClientMessage) -> io::Result<Self> {
+        let (writer, incoming) = Self::open(address, first)?;
+        Ok(Self {
+            address: address.to_string(),
+            writer,
+            incoming: Mutex::new(incoming),
+            id: None,
+            session: None,
+            lost: None,
+            deferred: None,
+            cards: CardMap::default()
+        })
+    }
+
+    // Tries the server again with our session, giving up quickly so the app doesn't stall
+    fn rejoin(&mut self) -> io::Result<()> {
+        let session = self.session.clone().unwrap_or_default();
+        let (writer, incoming) = Self::open(&self.address, &ClientMessage::Rejoin { session })?;
+        self.writer = writer;
+        self.incoming = Mutex::new(incoming);
+        self.lost = None;
+        Ok(())
+    }
+
+    // Connects, offers the versions we speak and sends `first`, with the server's replies read on their own thread
+    fn open(address: &str, first: &ClientMessage) -> io::Result<(TcpStream, Receiver<ServerMessage>)> {
+        let address = address.to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no address for the server"))?;
+        let mut writer = TcpStream::connect_timeout(&address, RECONNECT_INTERVAL)?;
+        writer.set_nodelay(true)?;
+        let reader = writer.try_clone()?;
+        send(&mut writer, &hello())?;
+        send(&mut writer, first)?;
+
+        let (sender, incoming) = mpsc::channel();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(reader);
+            while let Ok(Some(message)) = receive(&mut reader) {
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok((writer, incoming))
+    }
+
+    pub fn send(&mut self, message: &ClientMessage) -> io::Result<()> {
+        send(&mut self.writer, message)
+    }
+
+    fn try_receive(&mut self) -> Result<ServerMessage, TryRecvError> {
+        match self.deferred.take() {
+            Some(message) => Ok(message),
+            None => self.incoming.lock().unwrap().try_recv(),
+        }
+    }
+}
+
+/************
+ * CARD MAP
+ ************/
+
+// Which local card stands for each of the server's card ids. Ids only last one hand, and
+// the cards this seat can't see are filled in with whichever local cards are left over.
+#[derive(Debug, Default)]
+pub struct CardMap {
+    local: HashMap<Entity, Entity>
+}
+
+impl CardMap {
+    pub fn clear(&mut self) {
+        self.local.clear();
+    }
+
+    // The local card for a face up server card, picking a spare one of the same type the first time
+    fn local_card(&mut self, card: PileCard, cards: &[(Entity, SubType)]) -> PileCard {
+        let entity = match self.local.get(&card.entity) {
+            Some(entity) => *entity,
+            None => {
+                let used: HashSet<Entity> = self.local.values().copied().collect();
+                let spare = cards.iter()
+                    .find(|(entity, sub_type)| *sub_type == card.sub_type && !used.contains(entity))
+                    .map_or(Entity::PLACEHOLDER, |(entity, _)| *entity);
+                self.local.insert(card.entity, spare);
+                spare
+            }
+        };
+        PileCard { entity, sub_type: card.sub_type }
+    }
+
+    fn local_pile(&mut self, pile: &[PileCard], cards: &[(Entity, SubType)]) -> Vec<PileCard> {
+        pile.iter().map(|card| self.local_card(*card, cards)).collect()
+    }
+
+    // The server's id for a local card, if it has been seen this hand
+    pub fn server_card(&self, local: Entity) -> Option<Entity> {
+        self.local.iter().find(|(_, entity)| **entity == local).map(|(server, _)| *server)
+    }
+
+    // `event` with local cards, anything not seen yet stays a placeholder
+    pub fn local_event(&self, event: GameEvent) -> GameEvent {
+        match event.card() {
+            Some(card) => event.with_card(self.local.get(&card).copied().unwrap_or(Entity::PLACEHOLDER)),
+            None => event,
+        }
+    }
+
+    // A local `Game` matching `table` and the seat's `hand` if we know it, with the deck and the
+    // hands we can't see made up of spare cards. Spare cards stay where they were in `previous`
+    // where they can, so the UI moves as little as possible.
+    pub fn mirror(&mut self, table: &PublicView, hand: Option<(Seat, &[PileCard])>, previous: &Game, cards: &[(Entity, SubType)]) -> Game {
+        let tableau = |tableau: &Tableau, map: &mut Self| Tableau {
+            battle: map.local_pile(&tableau.battle, cards),
+            speed: map.local_pile(&tableau.speed, cards),
+            distance: map.local_pile(&tableau.distance, cards),
+            safeties: map.local_pile(&tableau.safeties, cards),
+            ..tableau.clone()
+        };
+
+        let mut game = Game {
+            discard: self.local_pile(&table.discard, cards),
+            player: tableau(&table.player, self),
+            opponent: tableau(&table.opponent, self),
+            turn: table.turn,
+            extra_turn: table.extra_turn,
+            last_hazard: table.last_hazard.map(|(seat, card)| (seat, self.local_card(card, cards))),
+            ..default()
+        };
+        if let Some((seat, hand)) = hand {
+            *game.hand_mut(seat) = self.local_pile(hand, cards);
+        }
+
+        // Hidden hands draw from the top of the deck
+        let hidden_seats: Vec<Seat> = [Seat::Player, Seat::Opponent].into_iter()
+            .filter(|seat| hand.is_none_or(|(known, _)| known != *seat))
+            .collect();
+        let mut taken: HashSet<Entity> = self.local.values().copied().collect();
+        let spares = hidden_seats.iter().flat_map(|seat| previous.hand(*seat).iter())
+            .chain(previous.deck.iter().rev())
+            .copied()
+            .chain(cards.iter().map(|(entity, sub_type)| PileCard { entity: *entity, sub_type: *sub_type }));
+        let mut hidden: Vec<PileCard> = spares.filter(|card| taken.insert(card.entity)).collect();
+
+        for seat in hidden_seats {
+            let hand: Vec<PileCard> = hidden.drain(..table.hand_size(seat).min(hidden.len())).collect();
+            *game.hand_mut(seat) = hand;
+        }
+        game.deck = hidden.into_iter().take(table.deck_size).collect();
+        game.deck.reverse();
+        game
+    }
+}
+
+/*********
+ * PLUGIN
+ *********/
+
+pub struct NetworkClient;
+impl Plugin for NetworkClient {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<NetworkSettings>()
+            // Nobody watching gets a say in the game
+            .configure_set(Update, TurnSet::Input.run_if(not(resource_exists::<Spectating>())))
+            // The server deals and judges the game
+            .configure_set(OnEnter(GameState::SetupGame), LocalRules.run_if(not(resource_exists::<Connection>())))
+            .configure_set(Update, LocalRules.run_if(not(resource_exists::<Connection>())))
+            .add_systems(
+                Update,
+                join_server
+                    .run_if(in_state(GameState::Menu))
+                    .run_if(resource_exists::<JoinServer>())
+            )
+            .add_systems(
+                Update,
+                (reconnect, receive_from_server)
+                    .chain()
+                    .before(TurnSet::Input)
+                    .run_if(resource_exists::<Connection>())
+            )
+            .add_systems(
+                Update,
+                forward_requests
+                    .in_set(TurnSet::Resolve)
+                    .run_if(resource_exists::<Connection>())
+            )
+            .add_systems(
+                OnEnter(GameState::SetupGame),
+                wait_for_deal
+                    .after(CardSet::CardInit)
+                    .run_if(resource_exists::<Connection>())
+            )
+            .add_systems(
+                OnEnter(GameState::Menu),
+                leave_server
+            );
+    }
+}
+
+fn join_server(mut commands: Commands, join: Res<JoinServer>, mut next_state: ResMut<NextState<GameState>>) {
+    let connected = match join.watch {
+        Some(coach) => Connection::watch(&join.address, &join.name, coach),
+        None => Connection::connect(&join.address, &join.name),
+    };
+    match connected {
+        Ok(connection) => {
+            info!("Connected to {} as {}", join.address, join.name);
+            commands.insert_resource(connection);
+            if let Some(coach) = join.watch {
+                commands.insert_resource(Spectating { coach });
+            }
+            next_state.set(GameState::Lobby);
+        }
+        Err(error) => error!("Couldn't connect to {}: {}", join.address, error),
+    }
+    commands.remove_resource::<JoinServer>();
+}
+
+// Going back to the menu leaves the game, and the seats go back to a local game
+fn leave_server(mut commands: Commands, mut seats: ResMut<Seats>, mut viewpoint: ResMut<Viewpoint>) {
+    commands.remove_resource::<Connection>();
+    commands.remove_resource::<LobbyInfo>();
+    commands.remove_resource::<Spectating>();
+    if seats.is_remote(Seat::Player) || seats.is_remote(Seat::Opponent) {
+        *seats = Seats::default();
+        *viewpoint = Viewpoint::default();
+    }
+}
+
+// While the connection is down, try to rejoin every so often until the server would have given up on us
+fn reconnect(mut commands: Commands,
+             mut connection: ResMut<Connection>,
+             mut next_state: ResMut<NextState<GameState>>,
+             mut next_turn: ResMut<NextState<TurnState>>)
+{
+    let Some((lost, tried)) = connection.lost else { return };
+    if tried.elapsed() < RECONNECT_INTERVAL {
+        return;
+    }
+
+    match connection.rejoin() {
+        Ok(()) => info!("Rejoined {}", connection.address),
+        Err(_) if lost.elapsed() >= RECONNECT_TIMEOUT => {
+            info!("Gave up rejoining {}", connection.address);
+            commands.remove_resource::<Connection>();
+            next_state.set(GameState::Menu);
+            next_turn.set(TurnState::NoTurn);
+        }
+        Err(_) => connection.lost = Some((lost, Instant::now())),
+    }
+}
+
+// Every card starts in the deck until the server deals
+fn wait_for_deal(mut connection: ResMut<Connection>,
+                 mut game: ResMut<Game>,
+                 game_rules: Res<GameRules>,
+                 spectating: Option<Res<Spectating>>,
+                 card_query: Query<(Entity, &SubType), With<Card>>)
+{
+    let deck: Vec<PileCard> = card_query.iter()
+        .map(|(entity, sub_type)| PileCard { entity, sub_type: *sub_type })
+        .collect();
+    *game = Game::new(deck, &game_rules);
+    connection.cards.clear();
+
+    // The players deal without waiting for anybody watching
+    if spectating.is_some() {
+        return;
+    }
+    if let Err(error) = connection.send(&ClientMessage::Ready) {
+        warn!("Couldn't reach the server: {}", error);
+    }
+}
+
+fn receive_from_server(mut commands: Commands,
+                       state: Res<State<GameState>>,
+                       mut spectating: Option<ResMut<Spectating>>,
+                       mut connection: ResMut<Connection>,
+                       mut game: ResMut<Game>,
+                       mut game_rules: ResMut<GameRules>,
+                       mut seats: ResMut<Seats>,
+                       mut viewpoint: ResMut<Viewpoint>,
+                       mut score: ResMut<Score>,
+                       card_query: Query<(Entity, &SubType), With<Card>>,
+                       mut next_state: ResMut<NextState<GameState>>,
+                       mut next_turn: ResMut<NextState<TurnState>>,
+                       mut events: GameEventWriters)
+{
+    if connection.lost.is_some() {
+        return;
+    }
+
+    loop {
+        let message = match connection.try_receive() {
+            Ok(message) => message,
+            Err(TryRecvError::Empty) => return,
+            // Our seat is held for us during the match, so try to get back to it
+            Err(TryRecvError::Disconnected) if connection.session.is_some() && seats.is_remote(viewpoint.0.other()) && score.match_winner.is_none() => {
+                warn!("Lost the connection to the server, trying to rejoin");
+                connection.lost = Some((Instant::now(), Instant::now()));
+                return;
+            }
+            Err(TryRecvError::Disconnected) => ServerMessage::Closed { reason: "Lost the connection to the server".into() },
+        };
+
+        // The table as the server sees it, and our seat's hand unless we're only watching
+        let (table, hand, sent) = match message {
+            ServerMessage::Hello { version } => {
+                info!("Speaking protocol version {}", version);
+                continue;
+            }
+            // The server changed nothing. Without a name it will take there's no joining at all.
+            ServerMessage::Error { error } => {
+                warn!("The server refused that: {:?}", error);
+                if error == ProtocolError::BadName {
+                    next_state.set(GameState::Menu);
+                    return;
+                }
+                continue;
+            }
+            ServerMessage::Joined { id, session } => {
+                connection.id = Some(id);
+                connection.session = Some(session);
+                continue;
+            }
+            ServerMessage::Lobby { lobby } => {
+                commands.insert_resource(LobbyInfo { me: connection.id, lobby });
+                continue;
+            }
+            ServerMessage::Started { seat, game_rules: rules } => {
+                commands.remove_resource::<LobbyInfo>();
+                *game_rules = rules;
+                *seats.controller_mut(seat) = Controller::Human;
+                *seats.controller_mut(seat.other()) = Controller::Remote;
+                *viewpoint = Viewpoint(seat);
+                next_state.set(GameState::SetupGame);
+                continue;
+            }
+            ServerMessage::Watching { coach, game_rules: rules, score: watched } => {
+                commands.remove_resource::<LobbyInfo>();
+                *game_rules = rules;
+                *seats.controller_mut(Seat::Player) = Controller::Remote;
+                *seats.controller_mut(Seat::Opponent) = Controller::Remote;
+                *viewpoint = Viewpoint(coach.unwrap_or(Seat::Player));
+                *score = watched;
+                if let Some(spectating) = spectating.as_mut() {
+                    spectating.coach = coach;
+                }
+                next_state.set(GameState::SetupGame);
+                continue;
+            }
+            // Somebody watching can be sent a table before there's a board to show it on, so it waits for a new one
+            ServerMessage::Update { .. } | ServerMessage::Watched { .. }
+                if spectating.is_some() && !matches!(state.get(), GameState::SetupGame | GameState::DuringTurn | GameState::NextTurn) =>
+            {
+                connection.deferred = Some(message);
+                next_state.set(GameState::SetupGame);
+                return;
+            }
+            // Back after a dropped connection: the board is rebuilt from scratch and the score
+            // already includes any hands that ended while we were away
+            ServerMessage::Resumed { seat, game_rules: rules, score: resumed, view, events: missed } => {
+                *game_rules = rules;
+                *seats.controller_mut(seat) = Controller::Human;
+                *seats.controller_mut(seat.other()) = Controller::Remote;
+                *viewpoint = Viewpoint(seat);
+                *score = resumed;
+
+                // Nothing has been dealt since we last asked, so ask again
+                if view.turn.is_none() && *state.get() == GameState::SetupGame {
+                    let _ = connection.send(&ClientMessage::Ready);
+                    continue;
+                }
+
+                let cards: Vec<(Entity, SubType)> = card_query.iter().map(|(entity, sub_type)| (entity, *sub_type)).collect();
+                connection.cards.clear();
+                *game = connection.cards.mirror(&view.public(), Some((view.seat, &view.hand)), &game, &cards);
+                for event in missed {
+                    events.send(connection.cards.local_event(event));
+                }
+
+                match view.turn {
+                    Some(Seat::Player) => next_turn.set(TurnState::PlayerTurn),
+                    Some(Seat::Opponent) => next_turn.set(TurnState::OpponentTurn),
+                    None => next_turn.set(TurnState::NoTurn),
+                }
+                let wanted = if view.turn.is_some() { GameState::DuringTurn } else { GameState::EndOfHand };
+                if *state.get() != wanted {
+                    next_state.set(wanted);
+                }
+                continue;
+            }
+            ServerMessage::Closed { reason } => {
+                info!("The server closed the game: {}", reason);
+                commands.remove_resource::<Connection>();
+
+                // The final scores stay up once the match is over
+                if score.match_winner.is_none() {
+                    next_state.set(GameState::Menu);
+                    next_turn.set(TurnState::NoTurn);
+                }
+                return;
+            }
+            ServerMessage::Update { view, events } => (view.public(), Some((view.seat, view.hand)), events),
+            ServerMessage::Watched { table, events } => (*table, None, events),
+        };
+
+        let cards: Vec<(Entity, SubType)> = card_query.iter().map(|(entity, sub_type)| (entity, *sub_type)).collect();
+        *game = connection.cards.mirror(&table, hand.as_ref().map(|(seat, hand)| (*seat, hand.as_slice())), &game, &cards);
+
+        // The server says when the match is over, with the final score
+        for event in sent {
+            match event {
+                GameEvent::HandEnded(hand) => {
+                    score.record_hand(&hand, &game_rules);
+                }
+                GameEvent::MatchEnded(ended) => {
+                    score.player_score = ended.player_score;
+                    score.opponent_score = ended.opponent_score;
+                    score.match_winner = Some(ended.winner);
+                }
+                _ => {}
+            }
+            events.send(connection.cards.local_event(event));
+        }
+
+        match table.turn {
+            Some(Seat::Player) => next_turn.set(TurnState::PlayerTurn),
+            Some(Seat::Opponent) => next_turn.set(TurnState::OpponentTurn),
+            None => next_turn.set(TurnState::NoTurn),
+        }
+        next_state.set(if table.turn.is_some() { GameState::DuringTurn } else { GameState::EndOfHand });
+    }
+}
+
+// This seat's moves go to the server, which sends back the result
+fn forward_requests(mut requests: EventReader<PlayRequest>, mut connection: ResMut<Connection>) {
+    for request in requests.iter() {
+        let Some(card) = connection.cards.server_card(request.action.card()) else { continue };
+        let play = ClientMessage::Play { action: request.action.with_card(card) };
+        if let Err(error) = connection.send(&play) {
+            warn!("Couldn't reach the server: {}", error);
+        }
+    }
+}
@@ -0,0 +1,5 @@
+// Play between machines. A headless server owns the only real `Game` and each client
+// is told no more than its own seat's `PlayerView`.
+pub mod client;
+pub mod protocol;
+pub mod server;
@@ -0,0 +1,198 @@
+// What the server and its clients say to each other over TCP, one JSON object per line:
+//
+//   client  {"type":"hello","oldest":2,"newest":2}         first thing on a new connection, the versions it speaks
+//           {"type":"join","name":"Ann"}                   then this
+//           {"type":"rejoin","session":"..."}              or this, to get a seat back after the connection dropped
+//           {"type":"watch","name":"Cat","coach":null}     or this, to look on without playing
+//           {"type":"take_seat","seat":"Opponent"}         sit down, or stand up with null
+//           {"type":"set_bot","seat":"Opponent","bot":"hard"}   the host fills a seat, or empties it with null
+//           {"type":"set_rules","game_rules":{...}}        the host changes the rules
+//           {"type":"ready"}                               start the match, or deal the next hand, once everyone is
+//           {"type":"play","action":{...}}                 an action for the seat, with the server's card ids
+//   server  {"type":"hello","version":2}                   the version both ends will speak
+//           {"type":"joined","id":3,"session":"..."}       who you are in the lobby, and how to rejoin
+//           {"type":"lobby","lobby":{...}}                 whenever anything in the lobby changes
+//           {"type":"started","seat":...,"game_rules":{...}}   the match is under way
+//           {"type":"watching","coach":...,"game_rules":{...},"score":{...}}   the same, for somebody looking on
+//           {"type":"update","view":{...},"events":[...]}  after every deal and every action
+//           {"type":"resumed","seat":...,"game_rules":{...},"score":{...},"view":{...},"events":[...]}
+//                                                          after a rejoin, with everything missed while away
+//           {"type":"watched","table":{...},"events":[...]}   what somebody looking on is sent instead of updates
+//           {"type":"error","error":{"code":"..."}}        something the client sent was refused, nothing changed
+//           {"type":"closed","reason":"..."}               the game is over for this connection
+//
+// Card ids are handed out afresh each hand and say nothing about the cards. Another seat's
+// draws are sent with `Entity::PLACEHOLDER` as the card.
+//
+// A seated player whose connection drops during the match keeps the seat for the server's
+// grace period. Rejoining with the session from `joined` picks up where they left off.
+//
+// Somebody watching only sees what's on the table, unless the server allows coaching and they
+// asked to coach a seat, when they're sent that seat's updates. Nothing they send is acted on.
+//
+// No line may be longer than `MAX_MESSAGE_SIZE`. The server answers anything it won't act on
+// with an `error`, and closes the connection when the versions don't match, the handshake is
+// skipped or a line is too long to find the end of.
+use std::io::{self, BufRead, Read, Write};
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use crate::cards::Seat;
+use crate::events::GameEvent;
+use crate::millebornes::Score;
+use crate::rules::{Action, GameRules, RuleError};
+use crate::view::{PlayerView, PublicView};
+
+pub const DEFAULT_PORT: u16 = 7878;
+
+// The version this build speaks and the oldest it still understands. Version 1 had no handshake.
+pub const PROTOCOL_VERSION: u32 = 2;
+pub const OLDEST_PROTOCOL_VERSION: u32 = 2;
+
+pub const MAX_MESSAGE_SIZE: usize = 64 * 1024;
+pub const MAX_NAME_LENGTH: usize = 24;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Hello { oldest: u32, newest: u32 },
+    Join { name: String },
+    Rejoin { session: String },
+    // `coach` asks to see that seat's hand as well
+    Watch { name: String, coach: Option<Seat> },
+    TakeSeat { seat: Option<Seat> },
+    // Only the bots `sim::lobby_bot_named` builds, anything else is an `UnknownBot`
+    SetBot { seat: Seat, bot: Option<String> },
+    SetRules { game_rules: GameRules },
+    Ready,
+    Play { action: Action }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Hello { version: u32 },
+    Joined { id: usize, session: String },
+    Lobby { lobby: Lobby },
+    Started { seat: Seat, game_rules: GameRules },
+    // `coach` is the seat whose hand we'll be shown, if the server allowed it
+    Watching { coach: Option<Seat>, game_rules: GameRules, score: Score },
+    // The seat's view as it now stands, with what happened since the last update
+    Update { view: Box<PlayerView>, events: Vec<GameEvent> },
+    // Everything a rejoining seat needs to rebuild its board, with the events it missed
+    Resumed { seat: Seat, game_rules: GameRules, score: Score, view: Box<PlayerView>, events: Vec<GameEvent> },
+    Watched { table: Box<PublicView>, events: Vec<GameEvent> },
+    Error { error: ProtocolError },
+    Closed { reason: String }
+}
+
+// Why the server wouldn't act on a message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum ProtocolError {
+    // No version both ends speak, with the ones the server does
+    Incompatible { oldest: u32, newest: u32 },
+    // Something other than `hello` came first
+    NoHandshake,
+    TooLarge,
+    Malformed { detail: String },
+    // Empty, too long or with control characters in it
+    BadName,
+    // Not yours to ask for: a lobby change by somebody who isn't the host, or anything from somebody watching
+    NotAllowed,
+    SeatTaken,
+    UnknownBot,
+    BadRules,
+    // Not at this point in the match, like playing between hands
+    NotNow,
+    Rule { error: RuleError },
+    // The server has as many connections as it will take, sent just before hanging up
+    Full
+}
+
+// Why `receive` couldn't read a message
+#[derive(Debug)]
+pub enum ReceiveError {
+    Io(io::Error),
+    // The line came through but wasn't a message, `TooLarge` leaves the rest of it unread
+    Invalid(ProtocolError)
+}
+
+// The newest version both ends speak, given the ones the other end does
+pub fn negotiate(oldest: u32, newest: u32) -> Option<u32> {
+    let version = newest.min(PROTOCOL_VERSION);
+    (version >= oldest.max(OLDEST_PROTOCOL_VERSION)).then_some(version)
+}
+
+pub fn hello() -> ClientMessage {
+    ClientMessage::Hello { oldest: OLDEST_PROTOCOL_VERSION, newest: PROTOCOL_VERSION }
+}
+
+// Names are shown to everybody, so keep them short and printable
+pub fn valid_name(name: &str) -> bool {
+    !name.trim().is_empty() && name.chars().count() <= MAX_NAME_LENGTH && !name.chars().any(char::is_control)
+}
+
+// Everybody waiting for the match, and what it will be played with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lobby {
+    // In the order they joined, the first is the host
+    pub players: Vec<LobbyPlayer>,
+    // The bot in each seat by the name `sim::bot_named` knows it by, indexed by `Seat`
+    pub bots: [Option<String>; 2],
+    pub watchers: Vec<LobbyWatcher>,
+    pub game_rules: GameRules
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LobbyPlayer {
+    pub id: usize,
+    pub name: String,
+    pub seat: Option<Seat>,
+    pub ready: bool
+}
+
+// Somebody looking on, and whose hand they're shown if they're coaching
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LobbyWatcher {
+    pub name: String,
+    pub coach: Option<Seat>
+}
+
+impl Lobby {
+    pub fn host(&self) -> Option<&LobbyPlayer> {
+        self.players.first()
+    }
+
+    pub fn player(&self, id: usize) -> Option<&LobbyPlayer> {
+        self.players.iter().find(|player| player.id == id)
+    }
+
+    // Who is in `seat`, if anybody
+    pub fn sitter(&self, seat: Seat) -> Option<String> {
+        self.players.iter()
+            .find(|player| player.seat == Some(seat))
+            .map(|player| player.name.clone())
+            .or_else(|| self.bots[seat as usize].as_ref().map(|bot| format!("Bot ({})", bot)))
+    }
+}
+
+pub fn send<M: Serialize>(writer: &mut impl Write, message: &M) -> io::Result<()> {
+    let line = serde_json::to_string(message)?;
+    writeln!(writer, "{}", line)?;
+    writer.flush()
+}
+
+// The next message, `None` once the other end has closed the connection
+pub fn receive<M: DeserializeOwned>(reader: &mut impl BufRead) -> Result<Option<M>, ReceiveError> {
+    let mut line = Vec::new();
+    let read = reader.take(MAX_MESSAGE_SIZE as u64 + 1).read_until(b'\n', &mut line).map_err(ReceiveError::Io)?;
+    if read == 0 {
+        return Ok(None);
+    }
+    if line.len() > MAX_MESSAGE_SIZE {
+        return Err(ReceiveError::Invalid(ProtocolError::TooLarge));
+    }
+    serde_json::from_slice(&line)
+        .map(Some)
+        .map_err(|error| ReceiveError::Invalid(ProtocolError::Malformed { detail: error.to_string() }))
+}
@@ -0,0 +1,770 @@
+// The headless side of network play. One match is hosted per run: people join a lobby,
+// choose their seats while the host fills the rest with bots and sets the rules, and the
+// match starts once every seat is taken and everybody sitting down is ready. Somebody whose
+// connection drops during the match keeps their seat for a grace period to rejoin in.
+// Anybody can watch, before or during the match, without a say in anything. Every message is
+// checked before it touches the table, and anything refused gets an error back saying why.
+use std::io::{self, BufReader};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::ai::bot::Bot;
+use crate::cards::*;
+use crate::events::*;
+use crate::millebornes::Score;
+use crate::rules::*;
+use crate::sim::{bot_named, lobby_bot_named, shuffled_deck};
+use super::protocol::*;
+
+// How often the listener checks whether the match is over while nobody is connecting
+const ACCEPT_POLL: Duration = Duration::from_millis(50);
+
+// How long a send waits on a client that isn't reading before hanging up on it
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub game_rules: GameRules,
+    // A bot `sim::bot_named` knows for the second seat, otherwise it waits for another person
+    pub bot: Option<String>,
+    // How long the bot waits before playing so people can follow what it did
+    pub bot_delay: Duration,
+    // How long a seat is held for somebody whose connection dropped during the match
+    pub grace: Duration,
+    // Whether somebody watching may ask to see one seat's hand
+    pub coaching: bool,
+    // How many connections are served at once, anybody over that is told the server is full
+    pub max_connections: usize
+}
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            game_rules: GameRules::default(),
+            bot: None,
+            bot_delay: Duration::from_millis(800),
+            grace: Duration::from_secs(60),
+            coaching: false,
+            max_connections: 32
+        }
+    }
+}
+
+pub struct Server {
+    listener: TcpListener,
+    config: ServerConfig
+}
+
+impl Server {
+    pub fn bind(address: impl ToSocketAddrs, config: ServerConfig) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self { listener, config })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    // Hosts one match, returning how it ended or why it was cut short.
+    // The port is free again once this returns.
+    pub fn run(self) -> Result<MatchEnded, String> {
+        let (sender, incoming) = mpsc::channel();
+        let open = Arc::new(AtomicBool::new(true));
+        let listener = self.listener;
+        let max_connections = self.config.max_connections;
+        let accepting = {
+            let open = open.clone();
+            thread::spawn(move || accept(listener, max_connections, sender, open))
+        };
+
+        let result = Table::new(self.config, incoming).and_then(Table::run);
+        open.store(false, Ordering::Relaxed);
+        let _ = accepting.join();
+        result
+    }
+}
+
+/**************
+ * CONNECTIONS
+ **************/
+
+enum Incoming {
+    Connected(usize, TcpStream),
+    Message(usize, ClientMessage),
+    // A line that wasn't a message
+    Invalid(usize, ProtocolError),
+    Disconnected(usize)
+}
+
+// Every connection gets a thread reading its messages onto the table's channel,
+// up to `max_connections` of them at once
+fn accept(listener: TcpListener, max_connections: usize, sender: Sender<Incoming>, open: Arc<AtomicBool>) {
+    let mut next_connection = 0;
+    let live = Arc::new(AtomicUsize::new(0));
+    while open.load(Ordering::Relaxed) {
+        let mut stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                thread::sleep(ACCEPT_POLL);
+                continue;
+            }
+            Err(_) => continue,
+        };
+
+        let _ = stream.set_nonblocking(false);
+        let _ = stream.set_nodelay(true);
+        let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+        if live.load(Ordering::Relaxed) >= max_connections {
+            let _ = send(&mut stream, &ServerMessage::Error { error: ProtocolError::Full });
+            let _ = stream.shutdown(Shutdown::Both);
+            continue;
+        }
+
+        let connection = next_connection;
+        next_connection += 1;
+        let Ok(reader) = stream.try_clone() else { continue };
+        if sender.send(Incoming::Connected(connection, stream)).is_err() {
+            return;
+        }
+
+        live.fetch_add(1, Ordering::Relaxed);
+        let live = live.clone();
+        let sender = sender.clone();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(reader);
+            loop {
+                let incoming = match receive(&mut reader) {
+                    Ok(Some(message)) => Incoming::Message(connection, message),
+                    Err(ReceiveError::Invalid(error)) => Incoming::Invalid(connection, error),
+                    Ok(None) | Err(ReceiveError::Io(_)) => break,
+                };
+                // The rest of a line too long to read can't be told apart from the next message
+                let too_large = matches!(incoming, Incoming::Invalid(_, ProtocolError::TooLarge));
+                if sender.send(incoming).is_err() {
+                    return;
+                }
+                if too_large {
+                    break;
+                }
+            }
+            live.fetch_sub(1, Ordering::Relaxed);
+            let _ = sender.send(Incoming::Disconnected(connection));
+        });
+    }
+}
+
+/********
+ * TABLE
+ ********/
+
+// Somebody who has joined, whether or not they are sitting down
+struct Member {
+    connection: usize,
+    name: String,
+    ready: bool,
+    // Proves who somebody is when they rejoin
+    session: String,
+    // When the connection dropped, while the seat is held for them
+    away: Option<Instant>,
+    // What their seat was told while they were away
+    missed: Vec<GameEvent>
+}
+
+// Somebody looking on
+struct Watcher {
+    connection: usize,
+    name: String,
+    coach: Option<Seat>
+}
+
+enum Sitter {
+    Human(usize),
+    Computer { name: String, bot: Box<dyn Bot> }
+}
+
+struct Table {
+    config: ServerConfig,
+    incoming: Receiver<Incoming>,
+    connections: HashMap<usize, TcpStream>,
+    // The protocol version agreed with each connection, nothing else is read until there is one
+    versions: HashMap<usize, u32>,
+    // In the order they joined, the first is the host
+    members: Vec<Member>,
+    watchers: Vec<Watcher>,
+    // Indexed by `Seat`
+    seats: [Option<Sitter>; 2],
+    game: Game,
+    score: Score,
+    rng: StdRng,
+    // The lobby is over and the match under way
+    started: bool,
+    // Between the deal and the end of the hand
+    playing: bool,
+    // A hand has been dealt, so there's a table to show
+    dealt: bool,
+    // When a bot whose turn it is gets to move, so people can follow what it did
+    bot_due: Option<Instant>
+}
+
+impl Table {
+    fn new(config: ServerConfig, incoming: Receiver<Incoming>) -> Result<Self, String> {
+        let mut table = Self {
+            config,
+            incoming,
+            connections: HashMap::default(),
+            versions: HashMap::default(),
+            members: Vec::new(),
+            watchers: Vec::new(),
+            seats: [None, None],
+            game: Game::default(),
+            score: Score::default(),
+            rng: StdRng::from_entropy(),
+            started: false,
+            playing: false,
+            dealt: false,
+            bot_due: None
+        };
+
+        // Whoever runs the server can seat any bot, including programs and policy files
+        if let Some(name) = table.config.bot.clone() {
+            let bot = bot_named(&name, table.rng.gen()).ok_or(format!("unknown bot {}", name))?;
+            table.seats[Seat::Opponent as usize] = Some(Sitter::Computer { name, bot });
+        }
+        Ok(table)
+    }
+
+    fn run(mut self) -> Result<MatchEnded, String> {
+        loop {
+            if self.everyone_ready() {
+                if !self.started {
+                    self.start_match();
+                }
+                else if !self.playing {
+                    self.start_hand();
+                }
+            }
+
+            let bot_seat = self.game.turn
+                .filter(|seat| self.playing && matches!(self.seats[*seat as usize], Some(Sitter::Computer { .. })));
+            let bot_due = bot_seat.map(|_| *self.bot_due.get_or_insert_with(|| Instant::now() + self.config.bot_delay));
+            if let Some(seat) = bot_seat.filter(|_| bot_due.is_some_and(|due| due <= Instant::now())) {
+                self.bot_due = None;
+                if let Some(ended) = self.play_bot(seat) {
+                    return Ok(ended);
+                }
+                continue;
+            }
+
+            // Messages keep coming in while a bot waits to move
+            let wait = [self.grace_left(), bot_due.map(|due| due.saturating_duration_since(Instant::now()))]
+                .into_iter().flatten().min();
+            let incoming = match wait {
+                Some(left) => match self.incoming.recv_timeout(left) {
+                    Ok(incoming) => incoming,
+                    Err(RecvTimeoutError::Timeout) => {
+                        self.check_away()?;
+                        continue;
+                    }
+                    Err(RecvTimeoutError::Disconnected) => return Err("stopped listening for connections".into()),
+                },
+                None => self.incoming.recv().map_err(|_| "stopped listening for connections".to_string())?,
+            };
+            if let Some(ended) = self.handle(incoming)? {
+                return Ok(ended);
+            }
+        }
+    }
+
+    fn handle(&mut self, incoming: Incoming) -> Result<Option<MatchEnded>, String> {
+        let (connection, message) = match incoming {
+            Incoming::Connected(connection, stream) => {
+                self.connections.insert(connection, stream);
+                return Ok(None);
+            }
+            Incoming::Disconnected(connection) => return self.leave(connection).map(|_| None),
+            Incoming::Invalid(connection, error) => {
+                self.reject(connection, error);
+                return Ok(None);
+            }
+            Incoming::Message(connection, message) => (connection, message),
+        };
+
+        match self.act(connection, message) {
+            Ok(ended) => Ok(ended),
+            Err(error) => {
+                self.reject(connection, error);
+                Ok(None)
+            }
+        }
+    }
+
+    // Does what `connection` asked if it's theirs to ask for, returning the end of the match if it came
+    fn act(&mut self, connection: usize, message: ClientMessage) -> Result<Option<MatchEnded>, ProtocolError> {
+        if !self.versions.contains_key(&connection) {
+            let ClientMessage::Hello { oldest, newest } = message else { return Err(ProtocolError::NoHandshake) };
+            let version = negotiate(oldest, newest)
+                .ok_or(ProtocolError::Incompatible { oldest: OLDEST_PROTOCOL_VERSION, newest: PROTOCOL_VERSION })?;
+            self.versions.insert(connection, version);
+            self.send(connection, &ServerMessage::Hello { version });
+            return Ok(None);
+        }
+
+        match message {
+            ClientMessage::Hello { .. } => return Err(ProtocolError::NotAllowed),
+            ClientMessage::Join { name } => self.join(connection, name)?,
+            ClientMessage::Rejoin { session } => self.rejoin(connection, &session)?,
+            ClientMessage::Watch { name, coach } => self.watch(connection, name, coach)?,
+            ClientMessage::TakeSeat { seat } => {
+                self.member(connection)?;
+                self.in_lobby()?;
+                self.take_seat(connection, seat)?;
+            }
+            ClientMessage::SetBot { seat, bot } => {
+                self.host(connection)?;
+                self.in_lobby()?;
+                self.set_bot(seat, bot)?;
+                self.lobby_changed();
+            }
+            ClientMessage::SetRules { game_rules } => {
+                self.host(connection)?;
+                self.in_lobby()?;
+                if !game_rules.is_playable() {
+                    return Err(ProtocolError::BadRules);
+                }
+                self.config.game_rules = game_rules;
+                self.lobby_changed();
+            }
+            ClientMessage::Ready => {
+                let member = self.member(connection)?;
+                if self.playing {
+                    return Err(ProtocolError::NotNow);
+                }
+                self.members[member].ready = true;
+                if !self.started {
+                    self.send_lobby();
+                }
+            }
+            ClientMessage::Play { action } => {
+                let seat = self.seat_of(connection).ok_or(ProtocolError::NotAllowed)?;
+                if !self.playing {
+                    return Err(ProtocolError::NotNow);
+                }
+                return self.play(seat, action).map_err(|error| ProtocolError::Rule { error });
+            }
+        }
+        Ok(None)
+    }
+
+    // Where `connection` is in `members`, as long as they joined rather than just watching
+    fn member(&self, connection: usize) -> Result<usize, ProtocolError> {
+        self.members.iter().position(|member| member.connection == connection).ok_or(ProtocolError::NotAllowed)
+    }
+
+    fn host(&self, connection: usize) -> Result<(), ProtocolError> {
+        if self.member(connection)? == 0 { Ok(()) } else { Err(ProtocolError::NotAllowed) }
+    }
+
+    fn in_lobby(&self) -> Result<(), ProtocolError> {
+        if self.started { Err(ProtocolError::NotNow) } else { Ok(()) }
+    }
+
+    /********
+     * LOBBY
+     ********/
+
+    fn join(&mut self, connection: usize, name: String) -> Result<(), ProtocolError> {
+        if self.is_known(connection) {
+            return Err(ProtocolError::NotAllowed);
+        }
+        if !valid_name(&name) {
+            return Err(ProtocolError::BadName);
+        }
+        if self.started {
+            self.close(connection, "The game has already started");
+            return Ok(());
+        }
+
+        info!("{} joined", name);
+        let session = format!("{:032x}", self.rng.gen::<u128>());
+        self.members.push(Member { connection, name, ready: false, session: session.clone(), away: None, missed: Vec::new() });
+        self.send(connection, &ServerMessage::Joined { id: connection, session });
+
+        // Newcomers sit down wherever there's room, they can move once they're in
+        match [Seat::Player, Seat::Opponent].into_iter().find(|seat| self.seats[*seat as usize].is_none()) {
+            Some(seat) => self.take_seat(connection, Some(seat)),
+            None => {
+                self.send_lobby();
+                Ok(())
+            }
+        }
+    }
+
+    // Somebody who has already joined or is watching
+    fn is_known(&self, connection: usize) -> bool {
+        self.members.iter().any(|member| member.connection == connection) || self.watchers.iter().any(|watcher| watcher.connection == connection)
+    }
+
+    // Leaving the lobby frees the seat, during a match the seat is held for a while in case they come back
+    fn leave(&mut self, connection: usize) -> Result<(), String> {
+        self.connections.remove(&connection);
+        self.versions.remove(&connection);
+        if let Some(watcher) = self.watchers.iter().position(|watcher| watcher.connection == connection) {
+            self.watchers.remove(watcher);
+            if !self.started {
+                self.send_lobby();
+            }
+            return Ok(());
+        }
+        let Some(member) = self.members.iter().position(|member| member.connection == connection) else { return Ok(()) };
+        let seat = self.seat_of(connection);
+        if self.started && seat.is_some() {
+            info!("{} dropped out, holding their seat", self.members[member].name);
+            self.members[member].away = Some(Instant::now());
+            return Ok(());
+        }
+
+        self.members.remove(member);
+        if let Some(seat) = seat {
+            self.seats[seat as usize] = None;
+        }
+        if self.members.is_empty() {
+            return Err("Everybody left the lobby".into());
+        }
+        if seat.is_some() {
+            self.lobby_changed();
+        }
+        else {
+            self.send_lobby();
+        }
+        Ok(())
+    }
+
+    // Somebody looking on, who sees the lobby until the match starts and the table after that
+    fn watch(&mut self, connection: usize, name: String, coach: Option<Seat>) -> Result<(), ProtocolError> {
+        if self.is_known(connection) {
+            return Err(ProtocolError::NotAllowed);
+        }
+        if !valid_name(&name) {
+            return Err(ProtocolError::BadName);
+        }
+
+        info!("{} is watching", name);
+        let coach = coach.filter(|_| self.config.coaching);
+        self.watchers.push(Watcher { connection, name, coach });
+        if !self.started {
+            self.send_lobby();
+            return Ok(());
+        }
+
+        self.send_watching(connection, coach);
+        if self.dealt {
+            self.send_table(connection, coach, Vec::new());
+        }
+        Ok(())
+    }
+
+    // Gives somebody back their seat on a new connection, with everything they missed
+    fn rejoin(&mut self, connection: usize, session: &str) -> Result<(), ProtocolError> {
+        if self.is_known(connection) {
+            return Err(ProtocolError::NotAllowed);
+        }
+        let Some(member) = self.members.iter().position(|member| member.session == session) else {
+            self.close(connection, "Your seat is gone");
+            return Ok(());
+        };
+        let previous = self.members[member].connection;
+        let Some(seat) = self.seat_of(previous) else {
+            self.close(connection, "Your seat is gone");
+            return Ok(());
+        };
+
+        // The old connection may not have noticed it's gone yet
+        if let Some(stream) = self.connections.remove(&previous) {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        info!("{} rejoined", self.members[member].name);
+        self.seats[seat as usize] = Some(Sitter::Human(connection));
+        let member = &mut self.members[member];
+        member.connection = connection;
+        member.away = None;
+        let events = std::mem::take(&mut member.missed);
+
+        let resumed = ServerMessage::Resumed {
+            seat,
+            game_rules: self.config.game_rules.clone(),
+            score: self.score.clone(),
+            view: Box::new(self.game.view(seat)),
+            events
+        };
+        self.send(connection, &resumed);
+        Ok(())
+    }
+
+    // How long until the next held seat is given up, if any are
+    fn grace_left(&self) -> Option<Duration> {
+        self.members.iter()
+            .filter_map(|member| member.away)
+            .map(|away| self.config.grace.saturating_sub(away.elapsed()))
+            .min()
+    }
+
+    // Anybody away for longer than the grace period has left the match
+    fn check_away(&mut self) -> Result<(), String> {
+        let grace = self.config.grace;
+        let Some(gone) = self.members.iter().find(|member| member.away.is_some_and(|away| away.elapsed() >= grace)) else { return Ok(()) };
+
+        let reason = format!("{} left the game", gone.name);
+        self.close_all(&reason);
+        Err(reason)
+    }
+
+    // Moves to `seat` if nobody else is in it, or stands up with `None`
+    fn take_seat(&mut self, connection: usize, seat: Option<Seat>) -> Result<(), ProtocolError> {
+        if seat.is_some() && seat == self.seat_of(connection) {
+            return Ok(());
+        }
+        if seat.is_some_and(|seat| self.seats[seat as usize].is_some()) {
+            return Err(ProtocolError::SeatTaken);
+        }
+
+        if let Some(current) = self.seat_of(connection) {
+            self.seats[current as usize] = None;
+        }
+        if let Some(seat) = seat {
+            self.seats[seat as usize] = Some(Sitter::Human(connection));
+        }
+        self.lobby_changed();
+        Ok(())
+    }
+
+    // Puts a bot in a seat nobody is sitting in or takes one out
+    fn set_bot(&mut self, seat: Seat, name: Option<String>) -> Result<(), ProtocolError> {
+        if let Some(Sitter::Human(_)) = self.seats[seat as usize] {
+            return Err(ProtocolError::SeatTaken);
+        }
+        self.seats[seat as usize] = match name {
+            Some(name) => {
+                let bot = lobby_bot_named(&name, self.rng.gen()).ok_or(ProtocolError::UnknownBot)?;
+                Some(Sitter::Computer { name, bot })
+            }
+            None => None,
+        };
+        Ok(())
+    }
+
+    // Anybody who was ready agreed to a different table, so they have to say so again
+    fn lobby_changed(&mut self) {
+        for member in &mut self.members {
+            member.ready = false;
+        }
+        self.send_lobby();
+    }
+
+    fn lobby(&self) -> Lobby {
+        Lobby {
+            players: self.members.iter()
+                .map(|member| LobbyPlayer {
+                    id: member.connection,
+                    name: member.name.clone(),
+                    seat: self.seat_of(member.connection),
+                    ready: member.ready
+                })
+                .collect(),
+            bots: [Seat::Player, Seat::Opponent].map(|seat| match &self.seats[seat as usize] {
+                Some(Sitter::Computer { name, .. }) => Some(name.clone()),
+                _ => None,
+            }),
+            watchers: self.watchers.iter()
+                .map(|watcher| LobbyWatcher { name: watcher.name.clone(), coach: watcher.coach })
+                .collect(),
+            game_rules: self.config.game_rules.clone()
+        }
+    }
+
+    fn send_lobby(&mut self) {
+        let message = ServerMessage::Lobby { lobby: self.lobby() };
+        let connections: Vec<usize> = self.members.iter().map(|member| member.connection)
+            .chain(self.watchers.iter().map(|watcher| watcher.connection))
+            .collect();
+        for connection in connections {
+            self.send(connection, &message);
+        }
+    }
+
+    fn seat_of(&self, connection: usize) -> Option<Seat> {
+        [Seat::Player, Seat::Opponent].into_iter()
+            .find(|seat| matches!(self.seats[*seat as usize], Some(Sitter::Human(seated)) if seated == connection))
+    }
+
+    // Every seat is filled and everybody in one is ready
+    fn everyone_ready(&self) -> bool {
+        self.seats.iter().all(|sitter| match sitter {
+            Some(Sitter::Human(connection)) => self.members.iter().any(|member| member.connection == *connection && member.ready),
+            Some(Sitter::Computer { .. }) => true,
+            None => false,
+        })
+    }
+
+    fn start_match(&mut self) {
+        self.started = true;
+        for member in &mut self.members {
+            member.ready = false;
+        }
+
+        let standing: Vec<usize> = self.members.iter()
+            .map(|member| member.connection)
+            .filter(|connection| self.seat_of(*connection).is_none())
+            .collect();
+        for connection in standing {
+            self.members.retain(|member| member.connection != connection);
+            self.close(connection, "The game started without you");
+        }
+
+        for seat in [Seat::Player, Seat::Opponent] {
+            if let Some(Sitter::Human(connection)) = self.seats[seat as usize] {
+                self.send(connection, &ServerMessage::Started { seat, game_rules: self.config.game_rules.clone() });
+            }
+        }
+        let watchers: Vec<(usize, Option<Seat>)> = self.watchers.iter().map(|watcher| (watcher.connection, watcher.coach)).collect();
+        for (connection, coach) in watchers {
+            self.send_watching(connection, coach);
+        }
+    }
+
+    /**********
+     * PLAYING
+     **********/
+
+    fn start_hand(&mut self) {
+        for member in &mut self.members {
+            member.ready = false;
+        }
+
+        self.game = Game::new(shuffled_deck(&mut self.rng), &self.config.game_rules);
+        let mut events = self.game.deal(self.config.game_rules.hand_size);
+        events.extend(self.game.next_turn());
+        self.playing = true;
+        self.dealt = true;
+        self.broadcast(&events);
+    }
+
+    // Takes `action` for `seat` and tells everybody, returning the end of the match if it came
+    fn play(&mut self, seat: Seat, action: Action) -> Result<Option<MatchEnded>, RuleError> {
+        let mut events = self.game.apply(seat, action)?;
+        events.extend(self.game.next_turn());
+        self.broadcast(&events);
+
+        for event in events {
+            let GameEvent::HandEnded(hand) = event else { continue };
+            self.playing = false;
+            if let Some(ended) = self.score.record_hand(&hand, &self.config.game_rules) {
+                self.broadcast(&[GameEvent::MatchEnded(ended)]);
+                self.close_all("The match is over");
+                return Ok(Some(ended));
+            }
+        }
+        Ok(None)
+    }
+
+    fn play_bot(&mut self, seat: Seat) -> Option<MatchEnded> {
+        let view = self.game.view(seat);
+        let Some(Sitter::Computer { bot, .. }) = &mut self.seats[seat as usize] else { return None };
+        let chosen = bot.choose(&view).filter(|action| self.game.check(seat, action).is_ok());
+        let action = chosen.or_else(|| self.game.legal_actions(seat).first().copied())?;
+        self.play(seat, action).ok().flatten()
+    }
+
+    /***********
+     * SENDING
+     ***********/
+
+    // Each seat and everybody watching hears about `events` as far as they are allowed to
+    fn broadcast(&mut self, events: &[GameEvent]) {
+        for seat in [Seat::Player, Seat::Opponent] {
+            match &mut self.seats[seat as usize] {
+                Some(Sitter::Human(connection)) => {
+                    let connection = *connection;
+                    let events: Vec<GameEvent> = events.iter().map(|event| event.seen_by(Some(seat))).collect();
+                    if let Some(member) = self.members.iter_mut().find(|member| member.connection == connection && member.away.is_some()) {
+                        member.missed.extend(events);
+                        continue;
+                    }
+                    let update = ServerMessage::Update { view: Box::new(self.game.view(seat)), events };
+                    self.send(connection, &update);
+                }
+                Some(Sitter::Computer { bot, .. }) => {
+                    for event in events {
+                        bot.observe(&event.seen_by(Some(seat)));
+                    }
+                }
+                None => {}
+            }
+        }
+
+        let watchers: Vec<(usize, Option<Seat>)> = self.watchers.iter().map(|watcher| (watcher.connection, watcher.coach)).collect();
+        for (connection, coach) in watchers {
+            self.send_table(connection, coach, events.to_vec());
+        }
+    }
+
+    fn send_watching(&mut self, connection: usize, coach: Option<Seat>) {
+        let watching = ServerMessage::Watching { coach, game_rules: self.config.game_rules.clone(), score: self.score.clone() };
+        self.send(connection, &watching);
+    }
+
+    // What somebody watching sees of the table: a coach gets the seat's own updates
+    fn send_table(&mut self, connection: usize, coach: Option<Seat>, events: Vec<GameEvent>) {
+        let events = events.into_iter().map(|event| event.seen_by(coach)).collect();
+        let message = match coach {
+            Some(seat) => ServerMessage::Update { view: Box::new(self.game.view(seat)), events },
+            None => ServerMessage::Watched { table: Box::new(self.game.public_view()), events },
+        };
+        self.send(connection, &message);
+    }
+
+    // Tells `connection` why nothing happened, and hangs up when there's no carrying on
+    fn reject(&mut self, connection: usize, error: ProtocolError) {
+        let reason = match error {
+            ProtocolError::Incompatible { .. } => Some("Incompatible protocol version"),
+            ProtocolError::NoHandshake => Some("Expected a hello first"),
+            ProtocolError::TooLarge => Some("Message too large"),
+            _ => None,
+        };
+        self.send(connection, &ServerMessage::Error { error });
+        if let Some(reason) = reason {
+            self.close(connection, reason);
+        }
+    }
+
+    // A connection which can't be written to is left for its reader to report as gone
+    fn send(&mut self, connection: usize, message: &ServerMessage) {
+        // Rather than hold up the table, a client whose sends fail is hung up on.
+        // Its reader then reports it gone like any other dropped connection.
+        let Some(stream) = self.connections.get_mut(&connection) else { return };
+        if send(stream, message).is_err() {
+            let _ = stream.shutdown(Shutdown::Both);
+            self.connections.remove(&connection);
+        }
+    }
+
+    fn close(&mut self, connection: usize, reason: &str) {
+        if let Some(mut stream) = self.connections.remove(&connection) {
+            let _ = send(&mut stream, &ServerMessage::Closed { reason: reason.to_string() });
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+
+    fn close_all(&mut self, reason: &str) {
+        let connections: Vec<usize> = self.connections.keys().copied().collect();
+        for connection in connections {
+            self.close(connection, reason);
+        }
+    }
+}
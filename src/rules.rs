@@ -0,0 +1,435 @@
+// The rules of the game as plain data, with no systems or commands.
+// The ECS zone markers are kept in step with `Game` by `sync_card_zones`.
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::cards::*;
+use crate::events::*;
+
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameRules {
+    pub miles: i32,
+    pub hand_size: i32,
+    pub winning_score: i32
+}
+impl Default for GameRules {
+    fn default() -> Self {
+        Self {
+            miles: 700,
+            hand_size: 6,
+            winning_score: 5000
+        }
+    }
+}
+impl GameRules {
+    // Rules a hand can be played with: a trip made of whole cards and hands the deck can deal
+    pub fn is_playable(&self) -> bool {
+        self.miles > 0
+            && self.miles % 25 == 0
+            && (1..=10).contains(&self.hand_size)
+            && self.winning_score > 0
+    }
+}
+
+// The piles in front of each seat
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Pile {
+    Battle,
+    Speed,
+    Distance,
+    Safety
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    Play { card: Entity, target: Seat, pile: Pile },
+    Discard { card: Entity },
+    // Answer the hazard just played on us with the safety against it
+    CoupFourre { card: Entity }
+}
+impl Action {
+    pub fn card(&self) -> Entity {
+        match self {
+            Action::Play { card, .. } => *card,
+            Action::Discard { card } => *card,
+            Action::CoupFourre { card } => *card,
+        }
+    }
+
+    // The same action with another card, for when cards go by different ids elsewhere
+    pub fn with_card(self, card: Entity) -> Self {
+        match self {
+            Action::Play { target, pile, .. } => Action::Play { card, target, pile },
+            Action::Discard { .. } => Action::Discard { card },
+            Action::CoupFourre { .. } => Action::CoupFourre { card },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum RuleError {
+    NotYourTurn,
+    NotInHand,
+    Illegal
+}
+
+// Where a card is, as far as the rules are concerned
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Zone {
+    Deck(usize),
+    Hand(Seat),
+    Board { seat: Seat, pile: Pile, covered: bool },
+    Discard(usize)
+}
+
+// Where a card goes when it is played: hazards go on the opponent, everything else in front of us
+pub fn play_target(seat: Seat, sub_type: SubType) -> (Seat, Pile) {
+    match sub_type.card_type() {
+        CardType::Hazard if sub_type == SubType::SpeedLimit => (seat.other(), Pile::Speed),
+        CardType::Hazard => (seat.other(), Pile::Battle),
+        CardType::Remedy if sub_type == SubType::EndOfLimit => (seat, Pile::Speed),
+        CardType::Remedy => (seat, Pile::Battle),
+        CardType::Distance => (seat, Pile::Distance),
+        CardType::Safety => (seat, Pile::Safety),
+    }
+}
+
+// The actions for a seat holding `hand`. Everything else it needs is on the table,
+// so a `PlayerView` can work them out without seeing the hidden cards.
+pub fn hand_actions(seat: Seat, hand: &[PileCard], player: &Tableau, opponent: &Tableau,
+                    last_hazard: Option<(Seat, PileCard)>) -> Vec<Action>
+{
+    let tableau = |seat: Seat| match seat {
+        Seat::Player => player,
+        Seat::Opponent => opponent,
+    };
+    let mut actions = Vec::new();
+
+    for card in hand {
+        let coup_fourre = last_hazard.is_some_and(|(target, hazard)|
+            target == seat && hazard.sub_type.safety() == Some(card.sub_type));
+        if coup_fourre {
+            actions.push(Action::CoupFourre { card: card.entity });
+            continue;
+        }
+
+        let (target, pile) = play_target(seat, card.sub_type);
+        if Card::is_valid(tableau(target), &card.sub_type.card_type(), &card.sub_type) {
+            actions.push(Action::Play { card: card.entity, target, pile });
+        }
+    }
+
+    actions.extend(hand.iter().map(|card| Action::Discard { card: card.entity }));
+    actions
+}
+
+// An action which was taken, with the game as it stood just before
+#[derive(Debug, Clone)]
+pub struct LoggedAction {
+    pub before: Game,
+    pub seat: Seat,
+    pub action: Action
+}
+
+// Everything played this hand, so it can be gone back over once the hand is done
+#[derive(Resource, Debug, Clone, Default)]
+pub struct ActionLog(pub Vec<LoggedAction>);
+
+#[derive(Resource, Debug, Clone, Default)]
+pub struct Game {
+    // The draw pile, the last card is drawn next
+    pub deck: Vec<PileCard>,
+    pub discard: Vec<PileCard>,
+    pub player_hand: Vec<PileCard>,
+    pub opponent_hand: Vec<PileCard>,
+    pub player: Tableau,
+    pub opponent: Tableau,
+    // Whose turn it is, `None` before the deal and once the hand is over
+    pub turn: Option<Seat>,
+    // Playing a safety gives the same seat another turn
+    pub extra_turn: bool,
+    // The hazard played on the previous turn, which its target can still answer with a coup fourré
+    pub last_hazard: Option<(Seat, PileCard)>
+}
+
+impl Game {
+    // A new hand using `deck` as the (already shuffled) draw pile
+    pub fn new(deck: Vec<PileCard>, game_rules: &GameRules) -> Self {
+        let mut game = Self { deck, ..default() };
+        game.player.target_miles = game_rules.miles;
+        game.opponent.target_miles = game_rules.miles;
+        game
+    }
+
+    pub fn tableau(&self, seat: Seat) -> &Tableau {
+        match seat {
+            Seat::Player => &self.player,
+            Seat::Opponent => &self.opponent,
+        }
+    }
+
+    pub fn tableau_mut(&mut self, seat: Seat) -> &mut Tableau {
+        match seat {
+            Seat::Player => &mut self.player,
+            Seat::Opponent => &mut self.opponent,
+        }
+    }
+
+    pub fn hand(&self, seat: Seat) -> &Vec<PileCard> {
+        match seat {
+            Seat::Player => &self.player_hand,
+            Seat::Opponent => &self.opponent_hand,
+        }
+    }
+
+    pub fn hand_mut(&mut self, seat: Seat) -> &mut Vec<PileCard> {
+        match seat {
+            Seat::Player => &mut self.player_hand,
+            Seat::Opponent => &mut self.opponent_hand,
+        }
+    }
+
+    /*************
+     * TURN ORDER
+     *************/
+
+    pub fn deal(&mut self, hand_size: i32) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        for _i in 0..hand_size {
+            events.extend(self.draw(Seat::Player));
+            events.extend(self.draw(Seat::Opponent));
+        }
+        events
+    }
+
+    pub fn draw(&mut self, seat: Seat) -> Option<GameEvent> {
+        let card = self.deck.pop()?;
+        self.hand_mut(seat).push(card);
+        Some(GameEvent::CardDrawn(CardDrawn { seat, card: card.entity }))
+    }
+
+    pub fn winner(&self) -> Option<Seat> {
+        [Seat::Player, Seat::Opponent].into_iter().find(|seat| self.tableau(*seat).has_finished())
+    }
+
+    // The hand is over once a trip is completed or every card has been used
+    pub fn is_hand_over(&self) -> bool {
+        self.winner().is_some()
+            || (self.deck.is_empty() && self.player_hand.is_empty() && self.opponent_hand.is_empty())
+    }
+
+    // Hand the turn to the next seat and draw for them, or finish the hand
+    pub fn next_turn(&mut self) -> Vec<GameEvent> {
+        if self.is_hand_over() {
+            self.turn = None;
+            return vec![GameEvent::HandEnded(HandEnded {
+                winner: self.winner(),
+                player_points: self.hand_points(Seat::Player),
+                opponent_points: self.hand_points(Seat::Opponent)
+            })];
+        }
+
+        let mut seat = match self.turn {
+            Some(seat) if self.extra_turn => seat,
+            Some(seat) => seat.other(),
+            None => Seat::Player,
+        };
+        self.extra_turn = false;
+
+        // Once the deck runs out a seat with an empty hand can only pass
+        if self.deck.is_empty() && self.hand(seat).is_empty() {
+            seat = seat.other();
+        }
+
+        self.turn = Some(seat);
+        self.draw(seat).into_iter().collect()
+    }
+
+    /**************
+     * LEGAL MOVES
+     **************/
+
+    // Every action `seat` can take right now: plays and responses first, then discards
+    pub fn legal_actions(&self, seat: Seat) -> Vec<Action> {
+        if self.turn != Some(seat) {
+            return Vec::new();
+        }
+
+        hand_actions(seat, self.hand(seat), &self.player, &self.opponent, self.last_hazard)
+    }
+
+    pub fn check(&self, seat: Seat, action: &Action) -> Result<(), RuleError> {
+        if self.turn != Some(seat) {
+            return Err(RuleError::NotYourTurn);
+        }
+
+        if !self.hand(seat).iter().any(|card| card.entity == action.card()) {
+            return Err(RuleError::NotInHand);
+        }
+
+        if !self.legal_actions(seat).contains(action) {
+            return Err(RuleError::Illegal);
+        }
+
+        Ok(())
+    }
+
+    /*****************
+     * TAKING ACTIONS
+     *****************/
+
+    // Take a card off a seat's pile and throw it away
+    fn discard_top(&mut self, seat: Seat, pile: Pile) {
+        let tableau = self.tableau_mut(seat);
+        let pile = match pile {
+            Pile::Battle => &mut tableau.battle,
+            Pile::Speed => &mut tableau.speed,
+            Pile::Distance => &mut tableau.distance,
+            Pile::Safety => &mut tableau.safeties,
+        };
+
+        if let Some(card) = pile.pop() {
+            self.discard.push(card);
+        }
+    }
+
+    fn play_safety(&mut self, seat: Seat, card: PileCard) {
+        let tableau = self.tableau(seat);
+
+        // A safety also clears any hazard it protects against
+        if tableau.battle_top().safety() == Some(card.sub_type) {
+            self.discard_top(seat, Pile::Battle);
+        }
+        if self.tableau(seat).speed_top().safety() == Some(card.sub_type) {
+            self.discard_top(seat, Pile::Speed);
+        }
+
+        self.tableau_mut(seat).safeties.push(card);
+        self.extra_turn = true;
+    }
+
+    // Carry out an action for `seat`, returning what happened
+    pub fn apply(&mut self, seat: Seat, action: Action) -> Result<Vec<GameEvent>, RuleError> {
+        self.check(seat, &action)?;
+
+        let hand = self.hand_mut(seat);
+        let index = hand.iter().position(|card| card.entity == action.card()).unwrap();
+        let card = hand.remove(index);
+
+        // Only the very next move can answer a hazard
+        let last_hazard = self.last_hazard.take();
+        let mut events = Vec::new();
+
+        match action {
+            Action::Discard { .. } => {
+                self.discard.push(card);
+                events.push(GameEvent::CardDiscarded(CardDiscarded { seat, card: card.entity, sub_type: card.sub_type }));
+            }
+            Action::CoupFourre { .. } => {
+                events.push(GameEvent::CardPlayed(CardPlayed { seat, card: card.entity, sub_type: card.sub_type }));
+                self.play_safety(seat, card);
+                self.tableau_mut(seat).coup_fourres += 1;
+
+                // The seat has already drawn for this turn, so the extra turn's draw
+                // stands in for the card that replaces the safety
+                let hazard = last_hazard.map_or(SubType::NoCard, |(_, hazard)| hazard.sub_type);
+                events.push(GameEvent::CoupFourre(CoupFourre { seat, card: card.entity, safety: card.sub_type, hazard }));
+            }
+            Action::Play { target, pile, .. } => {
+                events.push(GameEvent::CardPlayed(CardPlayed { seat, card: card.entity, sub_type: card.sub_type }));
+
+                if pile == Pile::Safety {
+                    self.play_safety(seat, card);
+                }
+                else {
+                    let tableau = self.tableau_mut(target);
+                    match pile {
+                        Pile::Battle => tableau.battle.push(card),
+                        Pile::Speed => tableau.speed.push(card),
+                        _ => tableau.distance.push(card),
+                    }
+                }
+
+                match card.sub_type.card_type() {
+                    CardType::Hazard => {
+                        self.last_hazard = Some((target, card));
+                        events.push(GameEvent::HazardApplied(HazardApplied { seat, target, card: card.entity, hazard: card.sub_type }));
+                    }
+                    CardType::Safety => {
+                        events.push(GameEvent::SafetyPlayed(SafetyPlayed { seat, card: card.entity, safety: card.sub_type }));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(events)
+    }
+
+    /**********
+     * SCORING
+     **********/
+
+    pub fn hand_points(&self, seat: Seat) -> i32 {
+        let tableau = self.tableau(seat);
+        let safeties = tableau.safeties.len() as i32;
+        let mut points = tableau.miles() + 100 * safeties + 300 * tableau.coup_fourres;
+
+        if safeties == 4 {
+            points += 300;
+        }
+
+        if tableau.has_finished() {
+            // Trip completed
+            points += 400;
+
+            // Delayed action
+            if self.deck.is_empty() {
+                points += 300;
+            }
+
+            // Safe trip
+            if tableau.two_hundreds() == 0 {
+                points += 300;
+            }
+
+            // Shutout
+            if self.tableau(seat.other()).miles() == 0 {
+                points += 500;
+            }
+        }
+
+        points
+    }
+
+    /********
+     * ZONES
+     ********/
+
+    // Where every card in the game currently is
+    pub fn zones(&self) -> Vec<(Entity, Zone)> {
+        let mut zones = Vec::new();
+
+        zones.extend(self.deck.iter().enumerate().map(|(position, card)| (card.entity, Zone::Deck(position))));
+        zones.extend(self.discard.iter().enumerate().map(|(position, card)| (card.entity, Zone::Discard(position))));
+
+        for seat in [Seat::Player, Seat::Opponent] {
+            zones.extend(self.hand(seat).iter().map(|card| (card.entity, Zone::Hand(seat))));
+
+            let tableau = self.tableau(seat);
+            for (pile, cards) in [
+                (Pile::Battle, &tableau.battle),
+                (Pile::Speed, &tableau.speed),
+                (Pile::Distance, &tableau.distance),
+                (Pile::Safety, &tableau.safeties),
+            ] {
+                for (index, card) in cards.iter().enumerate() {
+                    // Safeties are laid out side by side, every other pile only shows its top card
+                    let covered = pile != Pile::Safety && index + 1 < cards.len();
+                    zones.push((card.entity, Zone::Board { seat, pile, covered }));
+                }
+            }
+        }
+
+        zones
+    }
+}
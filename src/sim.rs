@@ -0,0 +1,314 @@
+// Whole matches between bots on the pure rules core, with no app or window.
+// Everything is driven from one seed so a run can be repeated exactly.
+use std::time::Duration;
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+use crate::ai::bot::Bot;
+use crate::ai::external::ExternalBot;
+use crate::ai::heuristic::{Difficulty, HeuristicBot};
+use crate::ai::ismcts::{Budget, IsmctsBot};
+use crate::ai::personality::{Personalities, PERSONALITIES_FILE};
+use crate::ai::policy::PolicyBot;
+use crate::ai::random::RandomBot;
+use crate::cards::*;
+use crate::events::{GameEvent, HandEnded};
+use crate::millebornes::Score;
+use crate::rules::*;
+
+// Time budgets would make runs depend on the machine, so searches are limited by iterations
+const DEFAULT_ITERATIONS: u32 = 1000;
+
+// A guard against a bot which never ends a hand
+const MAX_TURNS_PER_HAND: usize = 1000;
+
+// Builds a bot from its name on the command line: random, easy, medium, hard,
+// expert, ismcts:<iterations>, external:<command> or policy:<file>. Every level
+// takes a personality from the personalities file too, as in hard:bully or expert:bully.
+pub fn bot_named(name: &str, seed: u64) -> Option<Box<dyn Bot>> {
+    let (kind, argument) = name.split_once(':').unwrap_or((name, ""));
+    let personality = || Personalities::load_or_default(PERSONALITIES_FILE).named(argument).cloned();
+    let heuristic = |difficulty| {
+        let bot = HeuristicBot::seeded(difficulty, seed);
+        if argument.is_empty() {
+            return Some(bot);
+        }
+        Some(bot.with_personality(personality()?))
+    };
+    let bot: Box<dyn Bot> = match kind {
+        "random" => Box::new(RandomBot::seeded(seed)),
+        "easy" => Box::new(heuristic(Difficulty::Easy)?),
+        "medium" => Box::new(heuristic(Difficulty::Medium)?),
+        "hard" => Box::new(heuristic(Difficulty::Hard)?),
+        // A number is how long to search for, anything else a personality
+        "expert" | "ismcts" => match argument.parse() {
+            Ok(iterations) => Box::new(IsmctsBot::seeded(Budget::Iterations(iterations), seed)),
+            Err(_) if argument.is_empty() => Box::new(IsmctsBot::seeded(Budget::Iterations(DEFAULT_ITERATIONS), seed)),
+            Err(_) => Box::new(IsmctsBot::seeded(Budget::Iterations(DEFAULT_ITERATIONS), seed).with_personality(personality()?)),
+        },
+        "external" if !argument.is_empty() => Box::new(ExternalBot::seeded(argument, Duration::from_secs(2), seed)),
+        "policy" => Box::new(PolicyBot::load(argument).ok()?),
+        _ => return None,
+    };
+    Some(bot)
+}
+
+// The bots anybody at a networked table may ask for: the built-in levels, with a personality
+// if they like. Programs, policy files and search sizes stay with whoever runs the server.
+pub fn lobby_bot_named(name: &str, seed: u64) -> Option<Box<dyn Bot>> {
+    let (kind, argument) = name.split_once(':').unwrap_or((name, ""));
+    match kind {
+        "easy" | "medium" | "hard" => bot_named(name, seed),
+        "expert" if argument.parse::<u32>().is_err() => bot_named(name, seed),
+        "random" if argument.is_empty() => bot_named(name, seed),
+        _ => None,
+    }
+}
+
+// A full deck standing in for the card entities, shuffled. The cards are numbered
+// after shuffling so an id tells a bot nothing about the card behind it.
+pub fn shuffled_deck(rng: &mut StdRng) -> Vec<PileCard> {
+    let mut sub_types: Vec<SubType> = DECK_COMPOSITION.iter()
+        .flat_map(|(sub_type, count)| std::iter::repeat_n(*sub_type, *count as usize))
+        .collect();
+    sub_types.shuffle(rng);
+
+    sub_types.into_iter().enumerate()
+        .map(|(index, sub_type)| PileCard { entity: Entity::from_raw(index as u32), sub_type })
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HandRecord {
+    pub winner: Option<Seat>,
+    // Actions taken, by both seats
+    pub turns: usize,
+    pub player_points: i32,
+    pub opponent_points: i32,
+    pub player_coup_fourres: i32,
+    pub opponent_coup_fourres: i32
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchRecord {
+    pub seed: u64,
+    // The names of the bots in each seat
+    pub player: String,
+    pub opponent: String,
+    pub winner: Option<Seat>,
+    pub player_score: i32,
+    pub opponent_score: i32,
+    pub hands: Vec<HandRecord>
+}
+
+impl MatchRecord {
+    pub fn winner_name(&self) -> Option<&str> {
+        self.winner.map(|seat| match seat {
+            Seat::Player => self.player.as_str(),
+            Seat::Opponent => self.opponent.as_str(),
+        })
+    }
+
+    pub fn turns(&self) -> usize {
+        self.hands.iter().map(|hand| hand.turns).sum()
+    }
+
+    pub fn coup_fourres(&self) -> i32 {
+        self.hands.iter().map(|hand| hand.player_coup_fourres + hand.opponent_coup_fourres).sum()
+    }
+}
+
+fn bot_for<'a>(seat: Seat, player: &'a mut dyn Bot, opponent: &'a mut dyn Bot) -> &'a mut dyn Bot {
+    match seat {
+        Seat::Player => player,
+        Seat::Opponent => opponent,
+    }
+}
+
+fn show(events: &[GameEvent], player: &mut dyn Bot, opponent: &mut dyn Bot) {
+    for event in events {
+        player.observe(&event.seen_by(Some(Seat::Player)));
+        opponent.observe(&event.seen_by(Some(Seat::Opponent)));
+    }
+}
+
+// Plays one hand to the end, `None` if a bot gets stuck.
+// Every action is added to `log` when there is one.
+pub fn play_hand(game_rules: &GameRules, player: &mut dyn Bot, opponent: &mut dyn Bot, rng: &mut StdRng,
+                 mut log: Option<&mut Vec<LoggedAction>>) -> Option<HandRecord>
+{
+    let mut game = Game::new(shuffled_deck(rng), game_rules);
+    let dealt = game.deal(game_rules.hand_size);
+    show(&dealt, player, opponent);
+    let mut events = game.next_turn();
+    show(&events, player, opponent);
+
+    let mut turns = 0;
+    while let Some(seat) = game.turn {
+        if turns >= MAX_TURNS_PER_HAND {
+            return None;
+        }
+
+        let bot = bot_for(seat, player, opponent);
+        let action = bot.choose(&game.view(seat))?;
+        let action = if game.check(seat, &action).is_ok() { action } else { *game.legal_actions(seat).first()? };
+        if let Some(log) = log.as_mut() {
+            log.push(LoggedAction { before: game.clone(), seat, action });
+        }
+
+        events = game.apply(seat, action).ok()?;
+        events.extend(game.next_turn());
+        show(&events, player, opponent);
+        turns += 1;
+    }
+
+    Some(HandRecord {
+        winner: game.winner(),
+        turns,
+        player_points: game.hand_points(Seat::Player),
+        opponent_points: game.hand_points(Seat::Opponent),
+        player_coup_fourres: game.player.coup_fourres,
+        opponent_coup_fourres: game.opponent.coup_fourres
+    })
+}
+
+// Plays hands until somebody reaches the winning score. The bots are built from `player`
+// and `opponent` so every match starts them fresh.
+pub fn play_match(game_rules: &GameRules, player: &str, opponent: &str, seed: u64) -> Option<MatchRecord> {
+    run_match(game_rules, player, opponent, seed, false).map(|(record, _)| record)
+}
+
+// The same match as `play_match`, with the action log of every hand
+pub fn play_logged_match(game_rules: &GameRules, player: &str, opponent: &str, seed: u64) -> Option<(MatchRecord, Vec<Vec<LoggedAction>>)> {
+    run_match(game_rules, player, opponent, seed, true)
+}
+
+fn run_match(game_rules: &GameRules, player: &str, opponent: &str, seed: u64, keep_logs: bool) -> Option<(MatchRecord, Vec<Vec<LoggedAction>>)> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut player_bot = bot_named(player, rng.gen())?;
+    let mut opponent_bot = bot_named(opponent, rng.gen())?;
+    let mut score = Score::default();
+    let mut hands = Vec::new();
+    let mut logs = Vec::new();
+
+    while score.match_winner.is_none() {
+        let mut log = Vec::new();
+        let Some(hand) = play_hand(game_rules, player_bot.as_mut(), opponent_bot.as_mut(), &mut rng, keep_logs.then_some(&mut log)) else { break };
+
+        let ended = HandEnded {
+            winner: hand.winner,
+            player_points: hand.player_points,
+            opponent_points: hand.opponent_points
+        };
+        score.record_hand(&ended, game_rules);
+        hands.push(hand);
+        if keep_logs {
+            logs.push(log);
+        }
+    }
+
+    let record = MatchRecord {
+        seed,
+        player: player.to_string(),
+        opponent: opponent.to_string(),
+        winner: score.match_winner,
+        player_score: score.player_score,
+        opponent_score: score.opponent_score,
+        hands
+    };
+    Some((record, logs))
+}
+
+/**************
+ * STATISTICS
+ **************/
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BotStats {
+    pub name: String,
+    pub matches: usize,
+    pub wins: usize,
+    pub win_rate: f64,
+    pub average_score: f64
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Summary {
+    pub matches: usize,
+    pub hands: usize,
+    // Unfinished matches, where a bot got stuck
+    pub unfinished: usize,
+    pub average_hand_turns: f64,
+    pub coup_fourres_per_hand: f64,
+    pub bots: Vec<BotStats>
+}
+
+pub fn summarise(records: &[MatchRecord]) -> Summary {
+    let hands: usize = records.iter().map(|record| record.hands.len()).sum();
+    let turns: usize = records.iter().map(|record| record.turns()).sum();
+    let coup_fourres: i32 = records.iter().map(|record| record.coup_fourres()).sum();
+
+    let mut bots: Vec<BotStats> = Vec::new();
+    for record in records {
+        let seats = [(Seat::Player, &record.player, record.player_score), (Seat::Opponent, &record.opponent, record.opponent_score)];
+        for (seat, name, score) in seats {
+            let index = match bots.iter().position(|bot| bot.name == *name) {
+                Some(index) => index,
+                None => {
+                    bots.push(BotStats { name: name.clone(), ..default() });
+                    bots.len() - 1
+                }
+            };
+            let bot = &mut bots[index];
+            bot.matches += 1;
+            bot.average_score += score as f64;
+            if record.winner == Some(seat) {
+                bot.wins += 1;
+            }
+        }
+    }
+
+    for bot in bots.iter_mut() {
+        bot.win_rate = bot.wins as f64 / bot.matches.max(1) as f64;
+        bot.average_score /= bot.matches.max(1) as f64;
+    }
+
+    Summary {
+        matches: records.len(),
+        hands,
+        unfinished: records.iter().filter(|record| record.winner.is_none()).count(),
+        average_hand_turns: turns as f64 / hands.max(1) as f64,
+        coup_fourres_per_hand: coup_fourres as f64 / hands.max(1) as f64,
+        bots
+    }
+}
+
+// One line per match
+pub fn matches_csv(records: &[MatchRecord]) -> String {
+    let mut csv = String::from("match,seed,player,opponent,winner,player_score,opponent_score,hands,turns,coup_fourres\n");
+    for (index, record) in records.iter().enumerate() {
+        csv.push_str(&format!("{},{},{},{},{},{},{},{},{},{}\n",
+            index,
+            record.seed,
+            csv_field(&record.player),
+            csv_field(&record.opponent),
+            csv_field(record.winner_name().unwrap_or("")),
+            record.player_score,
+            record.opponent_score,
+            record.hands.len(),
+            record.turns(),
+            record.coup_fourres()));
+    }
+    csv
+}
+
+// Bot names can hold a whole command line
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,164 @@
+// Tournaments between named bots, built on the matches in `sim`, ranked with Elo ratings
+use serde::Serialize;
+use crate::cards::Seat;
+use crate::rules::GameRules;
+use crate::sim::{play_match, MatchRecord};
+
+pub const INITIAL_RATING: f64 = 1500.;
+
+// How far one match can move a rating
+const K_FACTOR: f64 = 32.;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Format {
+    // Everybody plays everybody
+    RoundRobin,
+    // Each round pairs bots with similar records who haven't met yet
+    Swiss { rounds: usize }
+}
+
+#[derive(Debug, Clone)]
+pub struct Tournament {
+    // Bot names as `sim::bot_named` understands them
+    pub entrants: Vec<String>,
+    pub format: Format,
+    // Deals each time two bots meet. Each is played twice, with the bots swapping seats.
+    pub games: usize,
+    pub seed: u64
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Standing {
+    pub name: String,
+    pub rating: f64,
+    pub played: usize,
+    pub wins: usize,
+    pub losses: usize
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TournamentResult {
+    // Best rated first
+    pub leaderboard: Vec<Standing>,
+    pub matches: Vec<MatchRecord>
+}
+
+// The chance `rating` beats `other`
+pub fn expected_score(rating: f64, other: f64) -> f64 {
+    1. / (1. + 10f64.powf((other - rating) / 400.))
+}
+
+// `score` is 1 for a win, 0 for a loss and a half for anything else
+pub fn elo_update(rating: f64, other: f64, score: f64) -> f64 {
+    rating + K_FACTOR * (score - expected_score(rating, other))
+}
+
+impl Tournament {
+    pub fn run(&self, game_rules: &GameRules) -> TournamentResult {
+        let mut standings: Vec<Standing> = self.entrants.iter()
+            .map(|name| Standing { name: name.clone(), rating: INITIAL_RATING, played: 0, wins: 0, losses: 0 })
+            .collect();
+        let mut matches = Vec::new();
+        let mut met = Vec::new();
+
+        let rounds = match self.format {
+            Format::RoundRobin => 1,
+            Format::Swiss { rounds } => rounds,
+        };
+
+        for round in 0..rounds {
+            let pairs = match self.format {
+                Format::RoundRobin => self.round_robin_pairs(),
+                Format::Swiss { .. } => swiss_pairs(&standings, &met, round == 0),
+            };
+
+            for (pairing, (a, b)) in pairs.into_iter().enumerate() {
+                met.push((a, b));
+                for game in 0..self.games {
+                    let seed = match_seed(self.seed, round, pairing, game);
+                    for (player, opponent) in [(a, b), (b, a)] {
+                        let Some(record) = play_match(game_rules, &self.entrants[player], &self.entrants[opponent], seed) else { continue };
+
+                        rate(&mut standings, player, opponent, record.winner);
+                        matches.push(record);
+                    }
+                }
+            }
+        }
+
+        standings.sort_by(|a, b| b.rating.total_cmp(&a.rating));
+        TournamentResult { leaderboard: standings, matches }
+    }
+
+    fn round_robin_pairs(&self) -> Vec<(usize, usize)> {
+        let count = self.entrants.len();
+        (0..count).flat_map(|a| (a + 1..count).map(move |b| (a, b))).collect()
+    }
+}
+
+// The same for a game wherever it falls in the tournament, whatever else got played
+fn match_seed(seed: u64, round: usize, pairing: usize, game: usize) -> u64 {
+    // SplitMix64, so that neighbouring games get nothing alike
+    [round, pairing, game].into_iter().fold(seed, |seed, index| {
+        let mut mixed = (seed ^ index as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+        mixed = (mixed ^ (mixed >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        mixed = (mixed ^ (mixed >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        mixed ^ (mixed >> 31)
+    })
+}
+
+fn rate(standings: &mut [Standing], player: usize, opponent: usize, winner: Option<Seat>) {
+    let score = match winner {
+        Some(Seat::Player) => 1.,
+        Some(Seat::Opponent) => 0.,
+        None => 0.5,
+    };
+    let (player_rating, opponent_rating) = (standings[player].rating, standings[opponent].rating);
+    standings[player].rating = elo_update(player_rating, opponent_rating, score);
+    standings[opponent].rating = elo_update(opponent_rating, player_rating, 1. - score);
+
+    for (index, seat) in [(player, Seat::Player), (opponent, Seat::Opponent)] {
+        standings[index].played += 1;
+        match winner {
+            Some(won) if won == seat => standings[index].wins += 1,
+            Some(_) => standings[index].losses += 1,
+            None => {}
+        }
+    }
+}
+
+// Pairs each bot with the closest one below it in the table that it hasn't played.
+// The first round goes in entry order. With an odd number of bots the last one sits out.
+fn swiss_pairs(standings: &[Standing], met: &[(usize, usize)], first_round: bool) -> Vec<(usize, usize)> {
+    let mut order: Vec<usize> = (0..standings.len()).collect();
+    if !first_round {
+        order.sort_by(|a, b| standings[*b].wins.cmp(&standings[*a].wins)
+            .then(standings[*b].rating.total_cmp(&standings[*a].rating)));
+    }
+    let has_met = |a: usize, b: usize| met.contains(&(a, b)) || met.contains(&(b, a));
+
+    let mut pairs = Vec::new();
+    while order.len() >= 2 {
+        let a = order.remove(0);
+        // Everybody left may have been played already, then a rematch beats sitting out
+        let partner = order.iter().position(|b| !has_met(a, *b)).unwrap_or(0);
+        let b = order.remove(partner);
+        pairs.push((a, b));
+    }
+    pairs
+}
+
+// The leaderboard as CSV, best first
+pub fn leaderboard_csv(leaderboard: &[Standing]) -> String {
+    let mut csv = String::from("rank,name,rating,played,wins,losses\n");
+    for (index, standing) in leaderboard.iter().enumerate() {
+        csv.push_str(&format!("{},\"{}\",{:.0},{},{},{}\n",
+            index + 1,
+            standing.name.replace('"', "\"\""),
+            standing.rating,
+            standing.played,
+            standing.wins,
+            standing.losses));
+    }
+    csv
+}
@@ -0,0 +1,187 @@
+// Training data for supervised policies, taken from action logs of simulated or human games.
+// Each sample is what a seat saw, what it could do, what it did and how the hand went for it.
+//
+// The file is a header followed by fixed-size records, all little-endian:
+//   header  b"MBTD", version: u32, observation size: u32, action count: u32
+//   record  seat: u8 (0 player, 1 opponent), action: u8, legal mask: u64 (bit n for action n),
+//           margin: f32, result: i8, observation: f32 * observation size
+use std::fs::OpenOptions;
+use std::io::{self, Read, Write};
+use bevy::prelude::*;
+use crate::ai::bot::Seats;
+use crate::cards::Seat;
+use crate::env::*;
+use crate::rules::*;
+
+const MAGIC: &[u8; 4] = b"MBTD";
+const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    pub seat: Seat,
+    // As `env::encode_observation` gives it, from `seat`'s side
+    pub observation: Vec<f32>,
+    pub legal_mask: Vec<bool>,
+    // The index `env::action_index` gives the action taken
+    pub action: usize,
+    // The seat's points margin at the end of the hand, over `env::REWARD_SCALE`
+    pub margin: f32,
+    // 1 if the seat completed its trip, -1 if the other seat did, 0 if neither
+    pub result: i8
+}
+
+// A sample for every action `seats` took in a finished hand's log
+pub fn samples_from_log(log: &[LoggedAction], seats: &[Seat]) -> Vec<Sample> {
+    let Some(last) = log.last() else { return Vec::new() };
+    let mut end = last.before.clone();
+    if end.apply(last.seat, last.action).is_err() {
+        return Vec::new();
+    }
+    end.next_turn();
+
+    log.iter()
+        .filter(|logged| seats.contains(&logged.seat))
+        .filter_map(|logged| {
+            let view = logged.before.view(logged.seat);
+            let seat = logged.seat;
+            Some(Sample {
+                seat,
+                observation: encode_observation(&view),
+                legal_mask: legal_mask(&view),
+                action: action_index(&view, logged.action)?,
+                margin: (end.hand_points(seat) - end.hand_points(seat.other())) as f32 / REWARD_SCALE,
+                result: match end.winner() {
+                    Some(winner) if winner == seat => 1,
+                    Some(_) => -1,
+                    None => 0,
+                }
+            })
+        })
+        .collect()
+}
+
+/***********
+ * WRITING
+ ***********/
+
+pub fn write_header(writer: &mut impl Write) -> io::Result<()> {
+    writer.write_all(MAGIC)?;
+    for value in [VERSION, OBSERVATION_SIZE as u32, ACTION_COUNT as u32] {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+pub fn write_sample(writer: &mut impl Write, sample: &Sample) -> io::Result<()> {
+    let mask = sample.legal_mask.iter()
+        .enumerate()
+        .fold(0u64, |mask, (index, legal)| if *legal { mask | 1 << index } else { mask });
+
+    writer.write_all(&[sample.seat as u8, sample.action as u8])?;
+    writer.write_all(&mask.to_le_bytes())?;
+    writer.write_all(&sample.margin.to_le_bytes())?;
+    writer.write_all(&sample.result.to_le_bytes())?;
+    for value in &sample.observation {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+// Adds `samples` to the end of the file at `path`, starting it with a header if it is new.
+// Anything but training data in this version's format is left alone.
+pub fn append_samples(path: &str, samples: &[Sample]) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).read(true).append(true).open(path)?;
+    let new = file.metadata()?.len() == 0;
+    if !new {
+        read_header(&mut file)?;
+    }
+    let mut writer = io::BufWriter::new(&mut file);
+    if new {
+        write_header(&mut writer)?;
+    }
+    for sample in samples {
+        write_sample(&mut writer, sample)?;
+    }
+    writer.flush()
+}
+
+/***********
+ * READING
+ ***********/
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+// Checks the file was written by this version of the module
+fn read_header(reader: &mut impl Read) -> io::Result<()> {
+    if &read_array::<4>(reader)? != MAGIC {
+        return Err(invalid("not a training data file"));
+    }
+    let version = u32::from_le_bytes(read_array(reader)?);
+    let observation_size = u32::from_le_bytes(read_array(reader)?) as usize;
+    let action_count = u32::from_le_bytes(read_array(reader)?) as usize;
+    if version != VERSION || observation_size != OBSERVATION_SIZE || action_count != ACTION_COUNT {
+        return Err(invalid("training data from a different version"));
+    }
+    Ok(())
+}
+
+// Every sample in a file written by this module
+pub fn read_samples(reader: &mut impl Read) -> io::Result<Vec<Sample>> {
+    read_header(reader)?;
+
+    let mut samples = Vec::new();
+    loop {
+        // The file may end between records but not inside one
+        let [seat, action] = match read_array::<2>(reader) {
+            Ok(bytes) => bytes,
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(error) => return Err(error),
+        };
+        let mask = u64::from_le_bytes(read_array(reader)?);
+        let margin = f32::from_le_bytes(read_array(reader)?);
+        let result = i8::from_le_bytes(read_array(reader)?);
+        let observation = (0..OBSERVATION_SIZE)
+            .map(|_| read_array(reader).map(f32::from_le_bytes))
+            .collect::<io::Result<Vec<f32>>>()?;
+
+        samples.push(Sample {
+            seat: if seat == 0 { Seat::Player } else { Seat::Opponent },
+            observation,
+            legal_mask: (0..ACTION_COUNT).map(|index| mask & 1 << index != 0).collect(),
+            action: action as usize,
+            margin,
+            result
+        });
+    }
+    Ok(samples)
+}
+
+pub fn load_samples(path: &str) -> io::Result<Vec<Sample>> {
+    read_samples(&mut io::BufReader::new(std::fs::File::open(path)?))
+}
+
+/*****************
+ * FROM THE GAME
+ *****************/
+
+// Where the human seats' moves are saved after each hand, given on the command line
+#[derive(Resource, Debug, Clone)]
+pub struct TrainingExport {
+    pub path: String
+}
+
+pub fn export_training_data(export: Res<TrainingExport>, log: Res<ActionLog>, seats: Res<Seats>) {
+    let humans: Vec<Seat> = [Seat::Player, Seat::Opponent].into_iter().filter(|seat| seats.is_human(*seat)).collect();
+    let samples = samples_from_log(&log.0, &humans);
+    if let Err(error) = append_samples(&export.path, &samples) {
+        error!("Couldn't save training data to {}: {}", export.path, error);
+    }
+}
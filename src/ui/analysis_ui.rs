@@ -0,0 +1,172 @@
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task};
+use futures_lite::future;
+use rand::{thread_rng, Rng};
+use crate::ai::analysis::{analyse_hand, endgame, Endgame, Mistake, ANALYSIS_SAMPLES};
+use crate::ai::bot::Seats;
+use crate::cards::Seat;
+use crate::constants::*;
+use crate::rules::ActionLog;
+
+// Mistakes listed at once, the arrow keys move through the rest
+const SHOWN_MISTAKES: usize = 6;
+
+/****************
+ * MOVE REVIEW
+ ****************/
+
+// Goes back over the human seats' moves on the end of hand screen
+pub struct AnalysisUI;
+impl Plugin for AnalysisUI {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<HandReview>()
+            .add_systems(
+                OnEnter(GameState::EndOfHand),
+                start_review
+            )
+            .add_systems(
+                Update,
+                (finish_review, scroll_review, update_review_ui).chain().run_if(in_state(GameState::EndOfHand))
+            )
+            // Leaving early drops the task, which cancels it
+            .add_systems(
+                OnExit(GameState::EndOfHand),
+                cleanup_review
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct HandReview {
+    task: Option<Task<(Vec<Mistake>, Option<Endgame>)>>,
+    // `None` until the analysis is done
+    pub mistakes: Option<Vec<Mistake>>,
+    // How the hand stood once the deck ran out, if it did
+    pub endgame: Option<Endgame>,
+    first: usize,
+    root: Option<Entity>,
+    text: Option<Entity>
+}
+
+fn start_review(mut commands: Commands, log: Res<ActionLog>, seats: Res<Seats>, mut review: ResMut<HandReview>) {
+    let humans: Vec<Seat> = [Seat::Player, Seat::Opponent].into_iter().filter(|seat| seats.is_human(*seat)).collect();
+    let log = log.0.clone();
+    let seed = thread_rng().gen();
+
+    *review = HandReview::default();
+    if !humans.is_empty() {
+        review.task = Some(AsyncComputeTaskPool::get().spawn(async move {
+            (analyse_hand(&log, &humans, ANALYSIS_SAMPLES, seed), endgame(&log))
+        }));
+    }
+    else {
+        review.mistakes = Some(Vec::new());
+    }
+
+    let mut text = None;
+    let root = commands.spawn(
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(10.),
+                bottom: Val::Px(10.),
+                max_width: Val::Percent(45.),
+                padding: UiRect::all(Val::Px(10.)),
+                ..default()
+            },
+            background_color: PRESSED_BUTTON.into(),
+            // Over the end of hand screen
+            z_index: ZIndex::Global(1),
+            ..default()
+        }).with_children(|parent| {
+            text = Some(parent.spawn(
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 18.,
+                        color: TEXT_COLOUR,
+                        ..default()
+                    })).id());
+        }).id();
+
+    review.root = Some(root);
+    review.text = text;
+}
+
+fn finish_review(mut review: ResMut<HandReview>) {
+    let Some(task) = review.task.as_mut() else { return };
+    if let Some((mistakes, endgame)) = future::block_on(future::poll_once(task)) {
+        review.mistakes = Some(mistakes);
+        review.endgame = endgame;
+        review.task = None;
+    }
+}
+
+fn scroll_review(keys: Res<Input<KeyCode>>, mut review: ResMut<HandReview>) {
+    let count = review.mistakes.as_ref().map_or(0, |mistakes| mistakes.len());
+    let last_page = count.saturating_sub(SHOWN_MISTAKES);
+
+    if keys.just_pressed(KeyCode::Down) && review.first < last_page {
+        review.first += 1;
+    }
+    if keys.just_pressed(KeyCode::Up) && review.first > 0 {
+        review.first -= 1;
+    }
+}
+
+fn update_review_ui(review: Res<HandReview>, seats: Res<Seats>, mut text_query: Query<&mut Text>) {
+    if !review.is_changed() {
+        return;
+    }
+    let Some(mut text) = review.text.and_then(|text| text_query.get_mut(text).ok()) else { return };
+
+    let Some(mistakes) = &review.mistakes else {
+        text.sections[0].value = "Reviewing your moves...".to_string();
+        return;
+    };
+
+    // Name the seat when more than one person is being reviewed
+    let shared = seats.is_human(Seat::Player) && seats.is_human(Seat::Opponent);
+
+    let mut lines = vec![
+        "Move review".to_string(),
+        "Against the hard bot playing on for both sides, and perfect play once the deck is empty".to_string()
+    ];
+    if let Some(endgame) = &review.endgame {
+        let human = if seats.is_human(Seat::Player) { Seat::Player } else { Seat::Opponent };
+        let verdict = match endgame.outcome.winner {
+            None => "neither trip could be finished".to_string(),
+            Some(winner) if shared => format!("a forced win for {:?}", winner),
+            Some(winner) if winner == human => "a forced win".to_string(),
+            Some(_) => "a forced loss".to_string(),
+        };
+        lines.push(format!("From move {}, with the deck empty: {}", endgame.move_number, verdict));
+    }
+    if mistakes.is_empty() {
+        lines.push("Nothing to point out this hand".to_string());
+    }
+
+    for mistake in mistakes.iter().skip(review.first).take(SHOWN_MISTAKES) {
+        if shared {
+            lines.push(format!("{:?}: {}", mistake.seat, mistake.describe()));
+        }
+        else {
+            lines.push(mistake.describe());
+        }
+    }
+
+    if mistakes.len() > SHOWN_MISTAKES {
+        lines.push(format!("{}-{} of {}, up and down to scroll",
+            review.first + 1, (review.first + SHOWN_MISTAKES).min(mistakes.len()), mistakes.len()));
+    }
+
+    text.sections[0].value = lines.join("\n");
+}
+
+fn cleanup_review(mut commands: Commands, mut review: ResMut<HandReview>) {
+    if let Some(root) = review.root.take() {
+        commands.entity(root).despawn_recursive();
+    }
+    *review = HandReview::default();
+}
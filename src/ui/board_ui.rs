@@ -1,14 +1,27 @@
 use bevy::prelude::*;
 use crate::cards::*;
-use super::card_ui::{build_card_ui, UIToCardLink, CardToUILink};
+use crate::rules::Game;
+use crate::net::client::Spectating;
+use crate::view::Viewpoint;
+use super::card_ui::{build_card_ui, build_card_back, UIToCardLink, CardToUILink};
 
 #[derive(Resource)]
 pub struct BoardUI {
+    root: Entity,
     player_hand: Entity,
     opponent_hand: Entity,
     play_area: Entity,
     opponent_play_area: Entity
 }
+impl BoardUI {
+    // Where `seat`'s tableau is drawn
+    pub fn play_area(&self, seat: Seat) -> Entity {
+        match seat {
+            Seat::Player => self.play_area,
+            Seat::Opponent => self.opponent_play_area,
+        }
+    }
+}
 
 pub fn create_board_ui(mut commands: Commands) {
     let board = commands.spawn(
@@ -66,8 +79,10 @@ pub fn create_board_ui(mut commands: Commands) {
                 height: Val::Percent(100.),
                 justify_content: JustifyContent::SpaceEvenly,
                 align_items: AlignItems::Center,
+                border: UiRect::all(Val::Px(4.)),
                 ..default()
             },
+            border_color: Color::NONE.into(),
             ..default()
         }
     ).id();
@@ -81,8 +96,10 @@ pub fn create_board_ui(mut commands: Commands) {
                 height: Val::Percent(100.),
                 justify_content: JustifyContent::SpaceEvenly,
                 align_items: AlignItems::Center,
+                border: UiRect::all(Val::Px(4.)),
                 ..default()
             },
+            border_color: Color::NONE.into(),
             ..default()
         }
     ).id();
@@ -104,7 +121,8 @@ pub fn create_board_ui(mut commands: Commands) {
 
     commands.entity(board_card_holder).push_children(&[player_area, opponent_area]);
     commands.entity(board).push_children(&[player_card_holder, board_card_holder, opponent_card_holder]);
-    commands.insert_resource(BoardUI { 
+    commands.insert_resource(BoardUI {
+        root: board,
         player_hand: player_card_holder,
         opponent_hand: opponent_card_holder,
         play_area: player_area,
@@ -112,26 +130,51 @@ pub fn create_board_ui(mut commands: Commands) {
     });
 }
 
+// Runs at the end of a hand and again if a game is abandoned, so the board may already be gone
+pub fn cleanup_board_ui(mut commands: Commands, board_ui: Option<Res<BoardUI>>) {
+    let Some(board_ui) = board_ui else { return };
+    commands.entity(board_ui.root).despawn_recursive();
+    commands.remove_resource::<BoardUI>();
+}
+
+// Only the top card of each pile is shown, cards which have been covered are left off the board.
+// Hands are drawn from the viewpoint's `PlayerView`, so cards it can't see are shown face down.
+// Somebody watching without coaching a seat sees both hands face down.
 pub fn update_board_ui(mut commands: Commands, board_ui: Res<BoardUI>,
+    game: Res<Game>,
+    viewpoint: Res<Viewpoint>,
+    spectating: Option<Res<Spectating>>,
     card_ui_query: Query<&UIToCardLink>,
     mut player_cards: Query<(Entity, &mut CardToUILink, &CardName, &CardType), (With<PlayerHand>, Without<OpponentHand>)>,
     mut opponent_cards: Query<(Entity, &mut CardToUILink, &CardName, &CardType), (With<OpponentHand>, Without<PlayerHand>)>,
-    mut player_board_card: Query<(Entity, &mut CardToUILink, &CardName, &CardType), (With<PlayerBoard>, Without<OpponentBoard>, Without<PlayerHand>, Without<OpponentHand>)>,
-    mut opponent_board_card: Query<(Entity, &mut CardToUILink, &CardName, &CardType), (With<OpponentBoard>, Without<PlayerBoard>, Without<OpponentHand>, Without<PlayerHand>)>)
+    mut player_board_cards: Query<(Entity, &mut CardToUILink, &CardName, &CardType), (With<PlayerBoard>, Without<OpponentBoard>, Without<PlayerHand>, Without<OpponentHand>, Without<Covered>)>,
+    mut opponent_board_cards: Query<(Entity, &mut CardToUILink, &CardName, &CardType), (With<OpponentBoard>, Without<PlayerBoard>, Without<OpponentHand>, Without<PlayerHand>, Without<Covered>)>)
 {
+    // Turning the table round means every hand card has to be redrawn next frame
+    if viewpoint.is_changed() {
+        for ui_entity in player_cards.iter().chain(opponent_cards.iter()).map(|(_, link, ..)| link.ui_entity) {
+            if card_ui_query.contains(ui_entity) {
+                commands.entity(ui_entity).despawn_recursive();
+            }
+        }
+        return;
+    }
 
-    if let Ok((entity, mut ui_entity, card_name, card_type)) = player_board_card.get_single_mut() {
+    let view = game.view(viewpoint.0);
+    let public = spectating.is_some_and(|spectating| spectating.coach.is_none());
+
+    for (entity, mut ui_entity, card_name, card_type) in player_board_cards.iter_mut() {
         if !card_ui_query.contains(ui_entity.ui_entity) {
-            let board_card = build_card_ui(&card_name.0, &card_type, entity, &mut commands);
+            let board_card = build_card_ui(&card_name.0, card_type, entity, &mut commands);
 
             ui_entity.ui_entity = board_card;
             commands.entity(board_ui.play_area).push_children(&[board_card]);
         }
     }
 
-    if let Ok((entity, mut ui_entity, card_name, card_type)) = opponent_board_card.get_single_mut() {
+    for (entity, mut ui_entity, card_name, card_type) in opponent_board_cards.iter_mut() {
         if !card_ui_query.contains(ui_entity.ui_entity) {
-            let board_card = build_card_ui(&card_name.0, &card_type, entity, &mut commands);
+            let board_card = build_card_ui(&card_name.0, card_type, entity, &mut commands);
 
             ui_entity.ui_entity = board_card;
             commands.entity(board_ui.opponent_play_area).push_children(&[board_card]);
@@ -140,7 +183,12 @@ pub fn update_board_ui(mut commands: Commands, board_ui: Res<BoardUI>,
 
     for (entity, mut ui_entity, card_name, card_type) in player_cards.iter_mut() {
         if !card_ui_query.contains(ui_entity.ui_entity) {
-            let player_card = build_card_ui(&card_name.0, &card_type, entity, &mut commands);
+            let player_card = if view.can_see(entity) && !public {
+                build_card_ui(&card_name.0, card_type, entity, &mut commands)
+            }
+            else {
+                build_card_back(entity, &mut commands)
+            };
 
             ui_entity.ui_entity = player_card;
             commands.entity(board_ui.player_hand).push_children(&[player_card]);
@@ -149,10 +197,15 @@ pub fn update_board_ui(mut commands: Commands, board_ui: Res<BoardUI>,
 
     for (entity, mut ui_entity, card_name, card_type) in opponent_cards.iter_mut() {
         if !card_ui_query.contains(ui_entity.ui_entity) {
-            let opponent_card = build_card_ui(&card_name.0, &card_type, entity, &mut commands);
+            let opponent_card = if view.can_see(entity) {
+                build_card_ui(&card_name.0, card_type, entity, &mut commands)
+            }
+            else {
+                build_card_back(entity, &mut commands)
+            };
 
             ui_entity.ui_entity = opponent_card;
             commands.entity(board_ui.opponent_hand).push_children(&[opponent_card]);
         }
     }
-}
\ No newline at end of file
+}
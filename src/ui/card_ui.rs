@@ -25,7 +25,16 @@ pub struct CardToUILink {
 #[derive(Component)]
 pub struct MarkedForDelete;
 
-pub fn build_card_ui(name: &String, card_type: &CardType, card_entity: Entity, commands: &mut Commands) -> Entity {
+pub fn build_card_ui(name: &str, card_type: &CardType, card_entity: Entity, commands: &mut Commands) -> Entity {
+    build_card_button(name, get_card_colour(card_type), card_entity, commands)
+}
+
+// A card the viewing seat isn't allowed to see, still linked so it is tidied up when it moves
+pub fn build_card_back(card_entity: Entity, commands: &mut Commands) -> Entity {
+    build_card_button("Mille Bornes", CARD_BACK, card_entity, commands)
+}
+
+fn build_card_button(name: &str, colour: Color, card_entity: Entity, commands: &mut Commands) -> Entity {
     let mut binding = commands.spawn((
                 UIToCardLink {
                     card_entity
@@ -37,22 +46,25 @@ pub fn build_card_ui(name: &String, card_type: &CardType, card_entity: Entity, c
                         justify_content: JustifyContent::Center,
                         align_items: AlignItems::Center,
                         align_content: AlignContent::Center,
+                        // Left clear so a card can be picked out, e.g. by a hint
+                        border: UiRect::all(Val::Px(4.)),
                         ..default()
                     },
-                    background_color: get_card_colour(card_type).into(),
+                    background_color: colour.into(),
+                    border_color: Color::NONE.into(),
                     ..default()
                 }));
     let node_bundle = 
         binding.with_children(|parent| {
             parent.spawn(
                 TextBundle::from_section(
-                    name.clone(),
+                    name,
                     TextStyle {
                         font_size: 24.,
-                        color: TEXT_COLOUR.into(),
+                        color: TEXT_COLOUR,
                         ..default()
                     }
                 ));
             });
-    return node_bundle.id();
+    node_bundle.id()
 }
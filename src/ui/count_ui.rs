@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+use crate::ai::belief::CardCounter;
+use crate::cards::*;
+use crate::constants::*;
+use crate::events::GameEventReaders;
+use crate::rules::Game;
+use crate::view::Viewpoint;
+
+/*********************
+ * CARD COUNTING PANEL
+ *********************/
+
+// Toggled with C, lists the unseen cards and how likely the other seat is to hold each safety
+pub struct CardCountUI;
+impl Plugin for CardCountUI {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<CardCountPanel>()
+            .init_resource::<ViewerCardCounter>()
+            .add_systems(
+                OnEnter(GameState::SetupGame),
+                setup_count_ui
+            )
+            .add_systems(
+                Update, (
+                    count_cards,
+                    toggle_count_ui,
+                    update_count_ui
+                ).chain()
+            )
+            .add_systems(
+                OnEnter(GameState::EndOfHand),
+                cleanup_count_ui
+            )
+            .add_systems(
+                OnEnter(GameState::Menu),
+                cleanup_count_ui
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct CardCountPanel {
+    pub visible: bool,
+    root: Option<Entity>,
+    text: Option<Entity>
+}
+
+// What the seat at the screen has worked out so far
+#[derive(Resource, Default)]
+pub struct ViewerCardCounter(pub CardCounter);
+
+const SAFETIES: [SubType; 4] = [SubType::DrivingAce, SubType::ExtraTank, SubType::PunctureProof, SubType::RightOfWay];
+
+fn setup_count_ui(mut commands: Commands, mut panel: ResMut<CardCountPanel>) {
+    let mut text = None;
+    let root = commands.spawn(
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(10.),
+                top: Val::Px(10.),
+                padding: UiRect::all(Val::Px(10.)),
+                ..default()
+            },
+            background_color: NORMAL_BUTTON.into(),
+            visibility: if panel.visible { Visibility::Inherited } else { Visibility::Hidden },
+            ..default()
+        }).with_children(|parent| {
+            text = Some(parent.spawn(
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 18.,
+                        color: TEXT_COLOUR,
+                        ..default()
+                    })).id());
+        }).id();
+
+    panel.root = Some(root);
+    panel.text = text;
+}
+
+fn count_cards(mut events: GameEventReaders, mut counter: ResMut<ViewerCardCounter>) {
+    for event in events.read() {
+        counter.0.observe(&event);
+    }
+}
+
+fn toggle_count_ui(keys: Res<Input<KeyCode>>, mut panel: ResMut<CardCountPanel>,
+                   mut visibility_query: Query<&mut Visibility>)
+{
+    if !keys.just_pressed(KeyCode::C) {
+        return;
+    }
+
+    panel.visible = !panel.visible;
+    if let Some(mut visibility) = panel.root.and_then(|root| visibility_query.get_mut(root).ok()) {
+        *visibility = if panel.visible { Visibility::Inherited } else { Visibility::Hidden };
+    }
+}
+
+fn update_count_ui(game: Res<Game>, viewpoint: Res<Viewpoint>, panel: Res<CardCountPanel>,
+                   counter: Res<ViewerCardCounter>, mut text_query: Query<&mut Text>)
+{
+    if !panel.visible || !(game.is_changed() || panel.is_changed() || viewpoint.is_changed()) {
+        return;
+    }
+    let Some(mut text) = panel.text.and_then(|text| text_query.get_mut(text).ok()) else { return };
+
+    let view = game.view(viewpoint.0);
+    let mut lines = vec!["Unseen cards".to_string()];
+    for (sub_type, count) in counter.0.remaining(&view) {
+        if count > 0 {
+            lines.push(format!("{:?}: {}", sub_type, count));
+        }
+    }
+
+    lines.push(String::new());
+    lines.push("Their safeties".to_string());
+    for safety in SAFETIES {
+        lines.push(format!("{:?}: {:.0}%", safety, 100. * counter.0.hand_chance(&view, safety)));
+    }
+
+    text.sections[0].value = lines.join("\n");
+}
+
+fn cleanup_count_ui(mut commands: Commands, mut panel: ResMut<CardCountPanel>) {
+    if let Some(root) = panel.root.take() {
+        commands.entity(root).despawn_recursive();
+    }
+    panel.text = None;
+}
@@ -0,0 +1,209 @@
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task};
+use futures_lite::future;
+use crate::ai::bot::Seats;
+use crate::ai::hint::{hint, Hint};
+use crate::cards::Seat;
+use crate::constants::*;
+use crate::rules::{Action, Game};
+use super::board_ui::BoardUI;
+use super::card_ui::{CardToUILink, UIToCardLink};
+use super::count_ui::ViewerCardCounter;
+
+pub const HINT_HIGHLIGHT: Color = Color::GOLD;
+
+/**************
+ * HINT BUTTON
+ **************/
+
+// Asks the engine what the seat at the screen should play, shown until the next move
+pub struct HintUI;
+impl Plugin for HintUI {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<ShownHint>()
+            .add_systems(
+                OnEnter(GameState::SetupGame),
+                setup_hint_ui
+            )
+            .add_systems(
+                Update, (
+                    clear_old_hint,
+                    ask_for_hint,
+                    receive_hint,
+                    show_hint
+                ).chain().run_if(in_state(GameState::DuringTurn))
+            )
+            .add_systems(
+                OnEnter(GameState::EndOfHand),
+                cleanup_hint_ui
+            )
+            .add_systems(
+                OnEnter(GameState::Menu),
+                cleanup_hint_ui
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct ShownHint {
+    pub hint: Option<(Seat, Hint)>,
+    // A hint still being worked out, off the main thread as the endgame solver can take a while
+    pending: Option<(Seat, Task<Option<Hint>>)>,
+    root: Option<Entity>,
+    text: Option<Entity>
+}
+
+#[derive(Component)]
+struct HintButton;
+
+fn setup_hint_ui(mut commands: Commands, mut shown: ResMut<ShownHint>) {
+    let mut text = None;
+    let root = commands.spawn(
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(10.),
+                bottom: Val::Px(10.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::FlexEnd,
+                row_gap: Val::Px(10.),
+                ..default()
+            },
+            ..default()
+        }).with_children(|parent| {
+            text = Some(parent.spawn(
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 20.,
+                        color: TEXT_COLOUR,
+                        ..default()
+                    })).id());
+
+            parent.spawn((
+                HintButton,
+                ButtonBundle {
+                    style: Style {
+                        width: Val::Px(120.),
+                        height: Val::Px(45.),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: NORMAL_BUTTON.into(),
+                    ..default()
+                })).with_children(|parent| {
+                    parent.spawn(
+                        TextBundle::from_section(
+                            "Hint",
+                            TextStyle {
+                                font_size: 28.,
+                                color: TEXT_COLOUR,
+                                ..default()
+                            }));
+                });
+        }).id();
+
+    shown.root = Some(root);
+    shown.text = text;
+    shown.hint = None;
+    shown.pending = None;
+}
+
+// Any move makes the hint stale, along with one still being worked out
+fn clear_old_hint(game: Res<Game>, mut shown: ResMut<ShownHint>) {
+    if game.is_changed() && (shown.hint.is_some() || shown.pending.is_some()) {
+        shown.hint = None;
+        shown.pending = None;
+    }
+}
+
+// The button or H asks for a hint, for a human seat on its turn
+fn ask_for_hint(keys: Res<Input<KeyCode>>,
+                game: Res<Game>,
+                seats: Res<Seats>,
+                counter: Res<ViewerCardCounter>,
+                mut shown: ResMut<ShownHint>,
+                mut button_query: Query<(&Interaction, &mut BackgroundColor), (Changed<Interaction>, With<HintButton>)>)
+{
+    let mut pressed = keys.just_pressed(KeyCode::H);
+    for (interaction, mut colour) in &mut button_query {
+        match *interaction {
+            Interaction::Pressed => {
+                *colour = PRESSED_BUTTON.into();
+                pressed = true;
+            }
+            Interaction::Hovered => *colour = HOVERED_BUTTON.into(),
+            Interaction::None => *colour = NORMAL_BUTTON.into(),
+        }
+    }
+
+    let Some(seat) = game.turn.filter(|seat| seats.is_human(*seat)) else { return };
+    if pressed && shown.pending.is_none() {
+        let view = game.view(seat);
+        let counter = counter.0.clone();
+        shown.pending = Some((seat, AsyncComputeTaskPool::get().spawn(async move { hint(&view, &counter) })));
+    }
+}
+
+fn receive_hint(mut shown: ResMut<ShownHint>) {
+    let Some((seat, task)) = shown.pending.as_mut() else { return };
+    let seat = *seat;
+    if let Some(hint) = future::block_on(future::poll_once(task)) {
+        shown.pending = None;
+        shown.hint = hint.map(|hint| (seat, hint));
+    }
+}
+
+// Outlines the hinted card and the tableau it goes on. Hand cards are redrawn
+// as the board changes, so this is reapplied every frame.
+fn show_hint(shown: Res<ShownHint>,
+             board_ui: Option<Res<BoardUI>>,
+             card_links: Query<&CardToUILink>,
+             mut card_borders: Query<(Entity, &mut BorderColor), With<UIToCardLink>>,
+             mut area_borders: Query<&mut BorderColor, Without<UIToCardLink>>,
+             mut text_query: Query<&mut Text>)
+{
+    let card_ui = shown.hint.as_ref()
+        .and_then(|(_, hint)| card_links.get(hint.action.card()).ok())
+        .map(|link| link.ui_entity);
+    let target = shown.hint.as_ref().and_then(|(seat, hint)| match hint.action {
+        Action::Play { target, .. } => Some(target),
+        Action::CoupFourre { .. } => Some(*seat),
+        Action::Discard { .. } => None,
+    });
+
+    for (entity, mut border) in &mut card_borders {
+        let colour = if Some(entity) == card_ui { HINT_HIGHLIGHT } else { Color::NONE };
+        if border.0 != colour {
+            border.0 = colour;
+        }
+    }
+
+    if let Some(board_ui) = board_ui {
+        for seat in [Seat::Player, Seat::Opponent] {
+            if let Ok(mut border) = area_borders.get_mut(board_ui.play_area(seat)) {
+                let colour = if Some(seat) == target { HINT_HIGHLIGHT } else { Color::NONE };
+                if border.0 != colour {
+                    border.0 = colour;
+                }
+            }
+        }
+    }
+
+    if shown.is_changed() {
+        if let Some(mut text) = shown.text.and_then(|text| text_query.get_mut(text).ok()) {
+            text.sections[0].value = shown.hint.as_ref().map_or(String::new(), |(_, hint)| hint.explanation.clone());
+        }
+    }
+}
+
+fn cleanup_hint_ui(mut commands: Commands, mut shown: ResMut<ShownHint>) {
+    if let Some(root) = shown.root.take() {
+        commands.entity(root).despawn_recursive();
+    }
+    shown.text = None;
+    shown.hint = None;
+    shown.pending = None;
+}
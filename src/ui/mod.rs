@@ -1,2 +1,7 @@
 pub mod card_ui;
-pub mod board_ui;
\ No newline at end of file
+pub mod board_ui;
+pub mod score_ui;
+pub mod count_ui;
+pub mod thinking_ui;
+pub mod hint_ui;
+pub mod analysis_ui;
\ No newline at end of file
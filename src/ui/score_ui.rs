@@ -0,0 +1,117 @@
+use bevy::prelude::*;
+use crate::cards::Seat;
+use crate::constants::*;
+use crate::millebornes::Score;
+
+/*******************
+ * END OF HAND SETUP
+ *******************/
+
+pub struct ScoreUI;
+impl Plugin for ScoreUI {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(
+                OnEnter(GameState::EndOfHand),
+                setup_score_ui
+            )
+            .add_systems(
+                Update,
+                update_score_ui.run_if(in_state(GameState::EndOfHand))
+            )
+            .add_systems(
+                OnExit(GameState::EndOfHand),
+                cleanup_score_ui
+            );
+    }
+}
+
+#[derive(Resource)]
+pub struct ScoreUIData {
+    root_entity: Entity,
+}
+
+fn score_text(text: String, font_size: f32) -> TextBundle {
+    TextBundle::from_section(
+        text,
+        TextStyle {
+            font_size,
+            color: TEXT_COLOUR,
+            ..default()
+        })
+}
+
+pub fn setup_score_ui(mut commands: Commands, score: Res<Score>) {
+    let title = match score.match_winner {
+        Some(Seat::Player) => "Player wins the match!",
+        Some(Seat::Opponent) => "Opponent wins the match!",
+        None => "Hand over",
+    };
+    let button_text = if score.match_winner.is_some() { "Main Menu" } else { "Next Hand" };
+
+    let root_entity = commands.spawn(
+        NodeBundle {
+            style: Style {
+                width: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(20.),
+                ..default()
+            },
+            background_color: NORMAL_BUTTON.into(),
+            ..default()
+        }).with_children(|parent| {
+            parent.spawn(score_text(title.into(), 50.));
+            parent.spawn(score_text(
+                format!("Player: +{} ({} total)", score.player_hand_score, score.player_score), 30.));
+            parent.spawn(score_text(
+                format!("Opponent: +{} ({} total)", score.opponent_hand_score, score.opponent_score), 30.));
+            parent.spawn(
+                ButtonBundle {
+                    style: Style {
+                        width: Val::Px(250.),
+                        height: Val::Px(65.),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: HOVERED_BUTTON.into(),
+                    ..default()
+                }).with_children(|parent| {
+                    parent.spawn(score_text(button_text.into(), 40.));
+                });
+        }).id();
+
+    commands.insert_resource(ScoreUIData { root_entity });
+}
+
+pub fn update_score_ui(mut next_state: ResMut<NextState<GameState>>,
+                       score: Res<Score>,
+                       mut interaction_query: Query<(&Interaction, &mut BackgroundColor),
+                                                    (Changed<Interaction>, With<Button>)>)
+{
+    for (interaction, mut colour) in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => {
+                *colour = PRESSED_BUTTON.into();
+                if score.match_winner.is_some() {
+                    next_state.set(GameState::Menu);
+                }
+                else {
+                    next_state.set(GameState::SetupGame);
+                }
+            }
+            Interaction::Hovered => {
+                *colour = PRESSED_BUTTON.into();
+            }
+            Interaction::None => {
+                *colour = HOVERED_BUTTON.into();
+            }
+        }
+    }
+}
+
+pub fn cleanup_score_ui(mut commands: Commands, score_data: Res<ScoreUIData>) {
+    commands.entity(score_data.root_entity).despawn_recursive();
+}
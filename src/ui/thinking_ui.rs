@@ -0,0 +1,82 @@
+use bevy::prelude::*;
+use crate::ai::bot::BotThinking;
+use crate::constants::*;
+
+/*********************
+ * THINKING INDICATOR
+ *********************/
+
+// Lets people know a computer player is working on its move rather than stuck
+pub struct ThinkingUI;
+impl Plugin for ThinkingUI {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<ThinkingIndicator>()
+            .add_systems(
+                OnEnter(GameState::SetupGame),
+                setup_thinking_ui
+            )
+            .add_systems(
+                Update,
+                update_thinking_ui
+            )
+            .add_systems(
+                OnEnter(GameState::EndOfHand),
+                cleanup_thinking_ui
+            )
+            .add_systems(
+                OnEnter(GameState::Menu),
+                cleanup_thinking_ui
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+struct ThinkingIndicator {
+    text: Option<Entity>
+}
+
+fn setup_thinking_ui(mut commands: Commands, mut indicator: ResMut<ThinkingIndicator>) {
+    let text = commands.spawn(
+        TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(10.),
+                bottom: Val::Px(10.),
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            ..TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 24.,
+                    color: TEXT_COLOUR,
+                    ..default()
+                })
+        }).id();
+
+    indicator.text = Some(text);
+}
+
+fn update_thinking_ui(thinking: Res<BotThinking>, indicator: Res<ThinkingIndicator>,
+                      mut text_query: Query<(&mut Text, &mut Visibility)>)
+{
+    if !thinking.is_changed() {
+        return;
+    }
+    let Some((mut text, mut visibility)) = indicator.text.and_then(|text| text_query.get_mut(text).ok()) else { return };
+
+    match thinking.seat() {
+        Some(seat) => {
+            text.sections[0].value = format!("{:?} is thinking...", seat);
+            *visibility = Visibility::Inherited;
+        }
+        None => *visibility = Visibility::Hidden,
+    }
+}
+
+fn cleanup_thinking_ui(mut commands: Commands, mut indicator: ResMut<ThinkingIndicator>) {
+    if let Some(text) = indicator.text.take() {
+        commands.entity(text).despawn_recursive();
+    }
+}
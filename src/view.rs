@@ -0,0 +1,141 @@
+// What one seat is allowed to know about the game. The UI, bots and network clients
+// all work from a `PlayerView` so nobody reads the other hand or the deck order.
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::cards::*;
+use crate::rules::*;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerView {
+    pub seat: Seat,
+    pub hand: Vec<PileCard>,
+    pub player: Tableau,
+    pub opponent: Tableau,
+    // Discards are played face up, so the whole pile is public
+    pub discard: Vec<PileCard>,
+    pub deck_size: usize,
+    pub opponent_hand_size: usize,
+    pub turn: Option<Seat>,
+    pub extra_turn: bool,
+    pub last_hazard: Option<(Seat, PileCard)>
+}
+
+impl PlayerView {
+    pub fn tableau(&self, seat: Seat) -> &Tableau {
+        match seat {
+            Seat::Player => &self.player,
+            Seat::Opponent => &self.opponent,
+        }
+    }
+
+    pub fn hand_size(&self, seat: Seat) -> usize {
+        if seat == self.seat { self.hand.len() } else { self.opponent_hand_size }
+    }
+
+    pub fn discard_size(&self) -> usize {
+        self.discard.len()
+    }
+
+    pub fn is_my_turn(&self) -> bool {
+        self.turn == Some(self.seat)
+    }
+
+    // The same actions `Game::legal_actions` gives for this seat
+    pub fn legal_actions(&self) -> Vec<Action> {
+        if !self.is_my_turn() {
+            return Vec::new();
+        }
+
+        hand_actions(self.seat, &self.hand, &self.player, &self.opponent, self.last_hazard)
+    }
+
+    // The card types this seat hasn't seen, which are in the deck or the other hand
+    pub fn unseen(&self) -> Vec<SubType> {
+        let mut unseen = Vec::new();
+        for (sub_type, count) in DECK_COMPOSITION {
+            let seen = self.seen().filter(|card| card.sub_type == sub_type).count() as i32;
+            unseen.extend(std::iter::repeat_n(sub_type, (count - seen).max(0) as usize));
+        }
+        unseen
+    }
+
+    fn seen(&self) -> impl Iterator<Item = &PileCard> {
+        self.hand.iter()
+            .chain(&self.discard)
+            .chain(self.player.cards())
+            .chain(self.opponent.cards())
+    }
+
+    // Whether `card` is face up for this seat, anything in the other hand or the deck is not
+    pub fn can_see(&self, card: Entity) -> bool {
+        self.seen().any(|seen| seen.entity == card)
+    }
+
+    // The same view without this seat's hand
+    pub fn public(&self) -> PublicView {
+        PublicView {
+            player: self.player.clone(),
+            opponent: self.opponent.clone(),
+            discard: self.discard.clone(),
+            deck_size: self.deck_size,
+            player_hand_size: self.hand_size(Seat::Player),
+            opponent_hand_size: self.hand_size(Seat::Opponent),
+            turn: self.turn,
+            extra_turn: self.extra_turn,
+            last_hazard: self.last_hazard
+        }
+    }
+}
+
+// What anybody watching the table can know: the piles, and how many cards each hand holds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublicView {
+    pub player: Tableau,
+    pub opponent: Tableau,
+    pub discard: Vec<PileCard>,
+    pub deck_size: usize,
+    pub player_hand_size: usize,
+    pub opponent_hand_size: usize,
+    pub turn: Option<Seat>,
+    pub extra_turn: bool,
+    pub last_hazard: Option<(Seat, PileCard)>
+}
+
+impl PublicView {
+    pub fn hand_size(&self, seat: Seat) -> usize {
+        match seat {
+            Seat::Player => self.player_hand_size,
+            Seat::Opponent => self.opponent_hand_size,
+        }
+    }
+}
+
+impl Game {
+    pub fn view(&self, seat: Seat) -> PlayerView {
+        PlayerView {
+            seat,
+            hand: self.hand(seat).clone(),
+            player: self.player.clone(),
+            opponent: self.opponent.clone(),
+            discard: self.discard.clone(),
+            deck_size: self.deck.len(),
+            opponent_hand_size: self.hand(seat.other()).len(),
+            turn: self.turn,
+            extra_turn: self.extra_turn,
+            last_hazard: self.last_hazard
+        }
+    }
+
+    pub fn public_view(&self) -> PublicView {
+        self.view(Seat::Player).public()
+    }
+}
+
+// Which seat the screen is drawn for
+#[derive(Resource, Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Viewpoint(pub Seat);
+impl Default for Viewpoint {
+    fn default() -> Self {
+        Self(Seat::Player)
+    }
+}
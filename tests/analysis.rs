@@ -0,0 +1,92 @@
+mod harness;
+
+use bevy_test::ai::analysis::*;
+use bevy_test::cards::*;
+use bevy_test::rules::*;
+use harness::{game_with, Harness};
+
+use SubType::*;
+
+
+// Plays the first non-discard action for `sub_type` and logs it
+fn play(game: &mut Game, log: &mut Vec<LoggedAction>, seat: Seat, sub_type: SubType) {
+    let card = game.hand(seat).iter().find(|card| card.sub_type == sub_type).unwrap().entity;
+    let action = *game.legal_actions(seat).iter().find(|action| action.card() == card).unwrap();
+    act(game, log, seat, action);
+}
+
+fn discard(game: &mut Game, log: &mut Vec<LoggedAction>, seat: Seat, sub_type: SubType) {
+    let card = game.hand(seat).iter().find(|card| card.sub_type == sub_type).unwrap().entity;
+    act(game, log, seat, Action::Discard { card });
+}
+
+fn act(game: &mut Game, log: &mut Vec<LoggedAction>, seat: Seat, action: Action) {
+    log.push(LoggedAction { before: game.clone(), seat, action });
+    game.apply(seat, action).unwrap();
+    game.next_turn();
+}
+
+#[test]
+fn passing_up_a_coup_fourre_is_flagged() {
+    let mut game = game_with(
+        &[Roll, Accident, Fifty, Fifty, Fifty, Fifty],
+        &[Roll, DrivingAce, Fifty, Fifty, Fifty, Fifty],
+        &[Fifty, Fifty, Fifty, Fifty]);
+    let mut log = Vec::new();
+    play(&mut game, &mut log, Seat::Player, Roll);
+    play(&mut game, &mut log, Seat::Opponent, Roll);
+    play(&mut game, &mut log, Seat::Player, Accident);
+    discard(&mut game, &mut log, Seat::Opponent, Fifty);
+
+    let mistakes = analyse_hand(&log, &[Seat::Opponent], ANALYSIS_SAMPLES, 1);
+
+    let missed = mistakes.iter().find(|mistake| mistake.kind == MistakeKind::MissedCoupFourre).unwrap();
+    assert_eq!(missed.move_number, 4);
+    assert_eq!(missed.better.0, DrivingAce);
+    assert!(missed.cost > 0., "{:?}", mistakes);
+    assert!(missed.describe().starts_with("Move 4: Missed a coup fourré"), "{}", missed.describe());
+}
+
+#[test]
+fn throwing_away_a_safety_is_flagged() {
+    let mut game = game_with(
+        &[Roll, ExtraTank, Fifty, Fifty, TwentyFive, TwentyFive],
+        &[Fifty, Fifty, Fifty, Fifty, Fifty, Fifty],
+        &[Fifty, Fifty]);
+    let mut log = Vec::new();
+    discard(&mut game, &mut log, Seat::Player, ExtraTank);
+
+    let mistakes = analyse_hand(&log, &[Seat::Player], ANALYSIS_SAMPLES, 2);
+
+    assert_eq!(mistakes.len(), 1);
+    assert_eq!(mistakes[0].kind, MistakeKind::WastedSafety);
+    assert_eq!(mistakes[0].played.0, ExtraTank);
+}
+
+#[test]
+fn sensible_moves_and_other_seats_are_left_alone() {
+    let mut game = game_with(
+        &[Roll, Fifty, Fifty, Fifty, TwentyFive, TwentyFive],
+        &[Roll, Fifty, Fifty, Fifty, Fifty, ExtraTank],
+        &[Fifty, Fifty, Fifty]);
+    let mut log = Vec::new();
+    play(&mut game, &mut log, Seat::Player, Roll);
+    discard(&mut game, &mut log, Seat::Opponent, ExtraTank);
+
+    assert!(analyse_hand(&log, &[Seat::Player], ANALYSIS_SAMPLES, 3).is_empty());
+}
+
+#[test]
+fn the_rules_log_every_action_taken() {
+    let mut harness = Harness::new();
+    harness.start_with(&[Roll, Fifty, Fifty, Fifty, Fifty, Fifty], &[TwentyFive; 6], &[Fifty, Fifty, Fifty]);
+
+    assert!(harness.play(Seat::Player, Roll));
+    assert!(harness.discard(Seat::Opponent, TwentyFive));
+    assert!(harness.play(Seat::Player, Fifty));
+
+    let log = &harness.app.world.resource::<ActionLog>().0;
+    assert_eq!(log.iter().map(|logged| logged.seat).collect::<Vec<_>>(), [Seat::Player, Seat::Opponent, Seat::Player]);
+    assert_eq!(log[2].before.player.battle_top(), Roll);
+    assert!(matches!(log[1].action, Action::Discard { .. }));
+}
@@ -0,0 +1,196 @@
+mod harness;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use bevy::prelude::*;
+use bevy_test::ai::bot::*;
+use bevy_test::ai::heuristic::*;
+use bevy_test::ai::random::RandomBot;
+use bevy_test::cards::*;
+use bevy_test::constants::*;
+use bevy_test::rules::*;
+use harness::{shuffled_game, Harness};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use SubType::*;
+
+const JUNK: [SubType; 6] = [TwentyFive, TwentyFive, Fifty, Fifty, SeventyFive, SeventyFive];
+
+fn harness_with(player: Controller, opponent: Controller, delay: f32) -> Harness {
+    let mut harness = Harness::new();
+    harness.app.add_plugins(ComputerPlayers);
+    harness.app.insert_resource(Seats { player, opponent });
+    harness.app.insert_resource(BotSettings { delay });
+    harness
+}
+
+#[test]
+fn two_bots_play_a_whole_hand() {
+    let mut harness = harness_with(
+        Controller::computer(Box::new(RandomBot::seeded(1))),
+        Controller::computer(Box::new(RandomBot::seeded(2))),
+        0.);
+    harness.start();
+    harness.play_out(2000);
+
+    assert_eq!(harness.state(), GameState::EndOfHand);
+}
+
+#[test]
+fn the_bot_answers_once_the_human_has_played() {
+    let mut harness = harness_with(Controller::Human, Controller::computer(Box::new(RandomBot::seeded(3))), 0.);
+    harness.start_with(&JUNK, &JUNK, &[Fifty, Fifty, Fifty]);
+
+    // The bot leaves the human's turn alone
+    for _ in 0..4 {
+        harness.app.update();
+    }
+    assert_eq!(harness.turn(), Some(Seat::Player));
+    assert_eq!(harness.game().discard.len(), 0);
+
+    assert!(harness.discard(Seat::Player, TwentyFive));
+    harness.wait_for_turn(Seat::Player);
+
+    assert_eq!(harness.game().discard.len(), 2);
+}
+
+#[test]
+fn the_bot_waits_for_its_delay() {
+    let mut harness = harness_with(Controller::Human, Controller::computer(Box::new(RandomBot::seeded(4))), 60.);
+    harness.start_with(&JUNK, &JUNK, &[Fifty, Fifty, Fifty]);
+
+    assert!(harness.discard(Seat::Player, TwentyFive));
+    for _ in 0..8 {
+        harness.app.update();
+    }
+
+    assert_eq!(harness.turn(), Some(Seat::Opponent));
+    assert_eq!(harness.hand(Seat::Opponent).len(), 7);
+}
+
+#[test]
+fn abandoning_the_game_cancels_the_bots_turn() {
+    let mut harness = harness_with(Controller::Human, Controller::computer(Box::new(RandomBot::seeded(5))), 60.);
+    harness.start_with(&JUNK, &JUNK, &[Fifty, Fifty, Fifty]);
+
+    assert!(harness.discard(Seat::Player, TwentyFive));
+    harness.wait_for_turn(Seat::Opponent);
+    harness.app.update();
+    assert_eq!(harness.app.world.resource::<BotThinking>().seat(), Some(Seat::Opponent));
+
+    harness.app.world.resource_mut::<NextState<GameState>>().set(GameState::Menu);
+    harness.app.update();
+
+    assert_eq!(harness.state(), GameState::Menu);
+    assert_eq!(harness.app.world.resource::<BotThinking>().seat(), None);
+}
+
+// Thinks about its first move until it is told to stop, then plays straight away
+#[derive(Default)]
+struct Ponderer {
+    cancelled: Arc<AtomicBool>,
+    thought: bool
+}
+impl Bot for Ponderer {
+    fn choose(&mut self, view: &bevy_test::view::PlayerView) -> Option<Action> {
+        let started = Instant::now();
+        while !self.thought && !self.cancelled.load(Ordering::Relaxed) && started.elapsed() < Duration::from_secs(30) {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        self.thought = true;
+        view.legal_actions().first().copied()
+    }
+
+    fn cancel_with(&mut self, cancelled: Arc<AtomicBool>) {
+        self.cancelled = cancelled;
+    }
+}
+
+#[test]
+fn a_bot_left_thinking_is_free_for_the_next_game() {
+    let mut harness = harness_with(Controller::Human, Controller::computer(Box::<Ponderer>::default()), 0.);
+    harness.start_with(&JUNK, &JUNK, &[Fifty, Fifty, Fifty]);
+    assert!(harness.discard(Seat::Player, TwentyFive));
+    harness.wait_for_turn(Seat::Opponent);
+    harness.app.update();
+
+    harness.app.world.resource_mut::<NextState<GameState>>().set(GameState::Menu);
+    harness.app.update();
+
+    let started = Instant::now();
+    harness.start_with(&JUNK, &JUNK, &[Fifty, Fifty, Fifty]);
+    assert!(harness.discard(Seat::Player, TwentyFive));
+    harness.wait_for_turn(Seat::Player);
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+fn choose(harness: &Harness, seat: Seat) -> SubType {
+    let view = harness.game().view(seat);
+    let action = HeuristicBot::seeded(Difficulty::Hard, 0).choose(&view).unwrap();
+    view.hand.iter().find(|card| card.entity == action.card()).unwrap().sub_type
+}
+
+#[test]
+fn the_heuristic_bot_gets_rolling_first() {
+    let mut harness = Harness::new();
+    harness.start_with(&[OneHundred, Stop, Roll, TwentyFive, Fifty, Fifty], &JUNK, &[Fifty]);
+
+    assert_eq!(choose(&harness, Seat::Player), Roll);
+}
+
+#[test]
+fn the_heuristic_bot_keeps_safeties_for_a_coup_fourre() {
+    let mut harness = Harness::new();
+    harness.app.world.resource_mut::<GameRules>().miles = 100;
+    harness.start_with(&[DrivingAce, TwoHundred, Repairs, Gasoline, OneHundred, OneHundred], &JUNK, &[Fifty]);
+
+    // The safety could be played, but the 200 can never be so it goes instead
+    assert!(harness.game().legal_actions(Seat::Player).iter()
+        .any(|action| matches!(action, Action::Play { pile: Pile::Safety, .. })));
+    assert_eq!(choose(&harness, Seat::Player), TwoHundred);
+}
+
+#[test]
+fn the_heuristic_bot_stops_a_rolling_leader() {
+    let mut harness = Harness::new();
+    harness.start_with(
+        &[Roll, Accident, TwentyFive, TwentyFive, Fifty, Fifty],
+        &[Roll, OneHundred, Fifty, Fifty, SeventyFive, SeventyFive],
+        &[Fifty, Fifty, Fifty, Fifty]);
+
+    assert!(harness.play(Seat::Player, Roll));
+    assert!(harness.play(Seat::Opponent, Roll));
+    assert!(harness.discard(Seat::Player, TwentyFive));
+    assert!(harness.play(Seat::Opponent, OneHundred));
+
+    assert_eq!(choose(&harness, Seat::Player), Accident);
+}
+
+#[test]
+fn the_hard_bot_beats_a_random_one() {
+    let mut rng = StdRng::seed_from_u64(33);
+    let mut heuristic = HeuristicBot::seeded(Difficulty::Hard, 33);
+    let mut random = RandomBot::seeded(33);
+    let (mut heuristic_points, mut random_points) = (0, 0);
+
+    for _ in 0..20 {
+        let mut game = shuffled_game(&mut rng);
+
+        while let Some(seat) = game.turn {
+            let view = game.view(seat);
+            let action = match seat {
+                Seat::Player => heuristic.choose(&view),
+                Seat::Opponent => random.choose(&view),
+            };
+            game.apply(seat, action.unwrap()).unwrap();
+            game.next_turn();
+        }
+
+        heuristic_points += game.hand_points(Seat::Player);
+        random_points += game.hand_points(Seat::Opponent);
+    }
+
+    assert!(heuristic_points > 2 * random_points, "{} vs {}", heuristic_points, random_points);
+}
@@ -0,0 +1,92 @@
+mod harness;
+
+use bevy::prelude::*;
+use bevy_test::ai::belief::CardCounter;
+use bevy_test::cards::*;
+use bevy_test::rules::*;
+use harness::game_with;
+
+use SubType::*;
+
+fn card(game: &Game, seat: Seat, sub_type: SubType) -> Entity {
+    game.hand(seat).iter().find(|card| card.sub_type == sub_type).unwrap().entity
+}
+
+// Take the action for the card and tell the counter everything that happened
+fn act(game: &mut Game, counter: &mut CardCounter, seat: Seat, sub_type: SubType, discard: bool) {
+    let card = card(game, seat, sub_type);
+    let action = *game.legal_actions(seat).iter()
+        .find(|action| action.card() == card && matches!(action, Action::Discard { .. }) == discard)
+        .unwrap();
+
+    let mut events = game.apply(seat, action).unwrap();
+    events.extend(game.next_turn());
+    for event in &events {
+        counter.observe(event);
+    }
+}
+
+fn remaining(counter: &CardCounter, game: &Game, sub_type: SubType) -> i32 {
+    counter.remaining(&game.view(Seat::Player)).into_iter()
+        .find(|(counted, _)| *counted == sub_type)
+        .unwrap().1
+}
+
+#[test]
+fn counts_drop_as_cards_are_seen() {
+    let mut counter = CardCounter::default();
+    let mut game = game_with(&[Roll, Roll, Fifty, Fifty, Fifty, Fifty], &[Roll, Fifty, Fifty, Fifty, Fifty, Fifty], &[Fifty, Fifty]);
+
+    assert_eq!(remaining(&counter, &game, Roll), 12);
+
+    act(&mut game, &mut counter, Seat::Player, Roll, false);
+    assert_eq!(remaining(&counter, &game, Roll), 12);
+
+    act(&mut game, &mut counter, Seat::Opponent, Roll, true);
+    assert_eq!(remaining(&counter, &game, Roll), 11);
+}
+
+#[test]
+fn an_unknown_safety_is_as_likely_as_its_share_of_the_hidden_cards() {
+    let counter = CardCounter::default();
+    let game = game_with(&[Roll, Roll, Fifty, Fifty, Fifty, Fifty], &[Roll, Fifty, Fifty, Fifty, Fifty, Fifty], &[Fifty]);
+    let view = game.view(Seat::Player);
+
+    let expected = view.opponent_hand_size as f32 / view.unseen().len() as f32;
+    assert!((counter.hand_chance(&view, DrivingAce) - expected).abs() < 1e-4);
+    assert!(counter.hand_chance(&view, Roll) > counter.hand_chance(&view, DrivingAce));
+}
+
+#[test]
+fn passing_up_a_coup_fourre_rules_the_safety_out() {
+    let mut counter = CardCounter::default();
+    let mut game = game_with(&[Roll, Accident, Fifty, Fifty, Fifty, Fifty], &[Roll, Fifty, Fifty, Fifty, Fifty, Fifty],
+                             &[Fifty, Fifty, Fifty, Fifty, Fifty, Fifty, Fifty]);
+
+    act(&mut game, &mut counter, Seat::Player, Roll, false);
+    act(&mut game, &mut counter, Seat::Opponent, Roll, false);
+    act(&mut game, &mut counter, Seat::Player, Accident, false);
+    act(&mut game, &mut counter, Seat::Opponent, Fifty, true);
+
+    assert!(counter.has_declined(Seat::Opponent, DrivingAce));
+    assert_eq!(counter.hand_chance(&game.view(Seat::Player), DrivingAce), 0.);
+
+    // Once they draw again they might have picked it up
+    act(&mut game, &mut counter, Seat::Player, Fifty, true);
+    assert!(counter.hand_chance(&game.view(Seat::Player), DrivingAce) > 0.);
+}
+
+#[test]
+fn a_coup_fourre_is_not_a_pass() {
+    let mut counter = CardCounter::default();
+    let mut game = game_with(&[Roll, Accident, Fifty, Fifty, Fifty, Fifty], &[Roll, DrivingAce, Fifty, Fifty, Fifty, Fifty],
+                             &[Fifty, Fifty, Fifty, Fifty]);
+
+    act(&mut game, &mut counter, Seat::Player, Roll, false);
+    act(&mut game, &mut counter, Seat::Opponent, Roll, false);
+    act(&mut game, &mut counter, Seat::Player, Accident, false);
+    act(&mut game, &mut counter, Seat::Opponent, DrivingAce, false);
+
+    assert!(!counter.has_declined(Seat::Opponent, DrivingAce));
+    assert_eq!(counter.hand_chance(&game.view(Seat::Player), DrivingAce), 0.);
+}
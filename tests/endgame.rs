@@ -0,0 +1,203 @@
+mod harness;
+
+use bevy::prelude::*;
+use bevy_test::ai::analysis::*;
+use bevy_test::ai::belief::CardCounter;
+use bevy_test::ai::bot::Bot;
+use bevy_test::ai::endgame::*;
+use bevy_test::ai::heuristic::{Difficulty, HeuristicBot};
+use bevy_test::ai::hint::hint;
+use bevy_test::cards::*;
+use bevy_test::rules::*;
+use harness::{game_with, shuffled_game};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use SubType::*;
+
+// Lays `cards` in front of `seat` as though they had been played
+fn lay_down(game: &mut Game, seat: Seat, battle: &[SubType], distance: &[SubType]) {
+    let mut next = 1000 + game.discard.len() as u32 + game.player.cards().count() as u32 + game.opponent.cards().count() as u32;
+    let mut card = |sub_type: &SubType| {
+        next += 1;
+        PileCard { entity: Entity::from_raw(next), sub_type: *sub_type }
+    };
+    let battle: Vec<PileCard> = battle.iter().map(&mut card).collect();
+    let distance: Vec<PileCard> = distance.iter().map(&mut card).collect();
+    game.tableau_mut(seat).battle.extend(battle);
+    game.tableau_mut(seat).distance.extend(distance);
+}
+
+// Everything not already somewhere goes on the discard pile, so each card is accounted for
+fn discard_the_rest(game: &mut Game) {
+    for (sub_type, count) in DECK_COMPOSITION {
+        let placed = game.player_hand.iter().chain(&game.opponent_hand)
+            .chain(game.player.cards()).chain(game.opponent.cards())
+            .filter(|card| card.sub_type == sub_type)
+            .count();
+        for index in placed..count as usize {
+            let entity = Entity::from_raw(5000 + game.discard.len() as u32 + index as u32);
+            game.discard.push(PileCard { entity, sub_type });
+        }
+    }
+}
+
+fn sub_type(game: &Game, action: Action) -> SubType {
+    game.player_hand.iter().chain(&game.opponent_hand).find(|card| card.entity == action.card()).unwrap().sub_type
+}
+
+#[test]
+fn the_solver_keeps_the_safe_trip_bonus() {
+    let mut game = game_with(
+        &[TwoHundred, OneHundred, OneHundred, OneHundred, TwentyFive, TwentyFive],
+        &[TwentyFive, TwentyFive, TwentyFive, TwentyFive, TwentyFive, TwentyFive],
+        &[]);
+    lay_down(&mut game, Seat::Player, &[Roll], &[OneHundred, OneHundred, OneHundred, OneHundred]);
+
+    let (action, outcome) = best_action(&game).unwrap();
+
+    assert_eq!(sub_type(&game, action), OneHundred);
+    assert_eq!(outcome.winner, Some(Seat::Player));
+    // 700 miles, the trip, delayed action, safe trip and a shutout
+    assert_eq!(outcome.margin, 700 + 400 + 300 + 300 + 500);
+}
+
+#[test]
+fn the_solver_stops_a_trip_it_cannot_beat() {
+    let mut game = game_with(
+        &[Stop, TwentyFive, TwentyFive, TwentyFive, TwentyFive, TwentyFive],
+        &[OneHundred, Accident, Accident, OutOfGas, OutOfGas, FlatTyre],
+        &[]);
+    lay_down(&mut game, Seat::Opponent, &[Roll], &[OneHundred, OneHundred, OneHundred, OneHundred, OneHundred, OneHundred]);
+
+    let (action, outcome) = best_action(&game).unwrap();
+    let discard = action_outcomes(&game).unwrap().into_iter()
+        .find(|(action, _)| matches!(action, Action::Discard { .. }))
+        .unwrap();
+
+    assert_eq!(sub_type(&game, action), Stop);
+    assert_eq!(outcome.winner, None);
+    assert_eq!(discard.1.winner, Some(Seat::Opponent));
+}
+
+#[test]
+fn there_is_nothing_to_solve_while_cards_are_left_to_draw() {
+    let game = shuffled_game(&mut StdRng::seed_from_u64(1));
+
+    assert!(solve(&game).is_none());
+    assert!(known_game(&game.view(Seat::Player)).is_none());
+}
+
+#[test]
+fn a_safety_uncovers_whatever_is_under_the_hazard() {
+    // The opponent starts on an Accident with nothing under it. Playing Repairs and Roll before
+    // being hit again leaves a Roll under the same Accident, which Driving Ace then uncovers.
+    // Throwing them away instead leaves them stuck, as the Stop answers a Roll played later.
+    let mut game = game_with(
+        &[Accident, Accident, Stop, EndOfLimit, EndOfLimit, EndOfLimit],
+        &[Repairs, Roll, DrivingAce, TwentyFive, EndOfLimit, EndOfLimit],
+        &[]);
+    lay_down(&mut game, Seat::Opponent, &[Accident], &[]);
+    game.player_hand.truncate(4);
+    game.turn = Some(Seat::Opponent);
+
+    let repairs = game.opponent_hand.iter().find(|card| card.sub_type == Repairs).unwrap().entity;
+    let (action, outcome) = action_outcomes(&game).unwrap().into_iter()
+        .find(|(action, _)| *action == Action::Discard { card: repairs })
+        .unwrap();
+    let mut after = game.clone();
+    after.apply(Seat::Opponent, action).unwrap();
+    after.next_turn();
+
+    assert_eq!(outcome, solve(&after).unwrap());
+}
+
+#[test]
+fn the_other_hand_is_known_once_the_deck_is_empty() {
+    let mut game = game_with(
+        &[Stop, TwentyFive, TwentyFive, TwentyFive, TwentyFive, TwentyFive],
+        &[OneHundred, Accident, Accident, OutOfGas, OutOfGas, FlatTyre],
+        &[]);
+    discard_the_rest(&mut game);
+
+    let known = known_game(&game.view(Seat::Player)).unwrap();
+
+    let mut hand: Vec<SubType> = known.opponent_hand.iter().map(|card| card.sub_type).collect();
+    hand.sort_by_key(|sub_type| *sub_type as u8);
+    assert_eq!(hand, vec![Accident, Accident, OutOfGas, OutOfGas, FlatTyre, OneHundred]);
+    assert_eq!(solve(&known), solve(&game));
+}
+
+#[test]
+fn the_hard_bot_plays_the_endgame_exactly() {
+    let mut game = game_with(
+        &[TwoHundred, OneHundred, OneHundred, OneHundred, TwentyFive, TwentyFive],
+        &[TwentyFive, TwentyFive, TwentyFive, TwentyFive, TwentyFive, TwentyFive],
+        &[]);
+    lay_down(&mut game, Seat::Player, &[Roll], &[OneHundred, OneHundred, OneHundred, OneHundred]);
+    discard_the_rest(&mut game);
+    let view = game.view(Seat::Player);
+
+    let medium = HeuristicBot::seeded(Difficulty::Medium, 0).best(&view).unwrap();
+    let hard = HeuristicBot::seeded(Difficulty::Hard, 0).best(&view).unwrap();
+
+    // The rules of thumb go for the bigger card
+    assert_eq!(sub_type(&game, medium), TwoHundred);
+    assert_eq!(sub_type(&game, hard), OneHundred);
+}
+
+#[test]
+fn the_hint_in_a_solved_endgame_says_so() {
+    let mut game = game_with(
+        &[TwoHundred, OneHundred, OneHundred, OneHundred, TwentyFive, TwentyFive],
+        &[TwentyFive, TwentyFive, TwentyFive, TwentyFive, TwentyFive, TwentyFive],
+        &[]);
+    lay_down(&mut game, Seat::Player, &[Roll], &[OneHundred, OneHundred, OneHundred, OneHundred]);
+    discard_the_rest(&mut game);
+
+    let hint = hint(&game.view(Seat::Player), &CardCounter::default()).unwrap();
+
+    assert_eq!(sub_type(&game, hint.action), OneHundred);
+    assert!(hint.explanation.starts_with("Solved endgame"), "{}", hint.explanation);
+    assert!(hint.explanation.contains("ahead"), "{}", hint.explanation);
+}
+
+#[test]
+fn analysis_reports_a_thrown_away_win() {
+    let mut game = game_with(
+        &[OneHundred, TwentyFive, TwentyFive, TwentyFive, TwentyFive, TwentyFive],
+        &[Stop, TwentyFive, TwentyFive, TwentyFive, TwentyFive, TwentyFive],
+        &[]);
+    lay_down(&mut game, Seat::Player, &[Roll], &[OneHundred, OneHundred, OneHundred, OneHundred, OneHundred, OneHundred]);
+    let card = game.player_hand.iter().find(|card| card.sub_type == TwentyFive).unwrap().entity;
+    let action = Action::Discard { card };
+    let log = vec![LoggedAction { before: game.clone(), seat: Seat::Player, action }];
+
+    let mistakes = analyse_hand(&log, &[Seat::Player], ANALYSIS_SAMPLES, 1);
+    let verdict = endgame(&log).unwrap();
+
+    assert_eq!(mistakes[0].kind, MistakeKind::ThrewAwayWin);
+    assert_eq!(mistakes[0].better.0, OneHundred);
+    assert!(mistakes[0].describe().starts_with("Move 1: Threw away a forced win"), "{}", mistakes[0].describe());
+    assert_eq!(verdict.move_number, 1);
+    assert_eq!(verdict.outcome.winner, Some(Seat::Player));
+}
+
+#[test]
+fn real_endgames_are_solved_within_the_node_limit() {
+    for seed in 0..20 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut game = shuffled_game(&mut rng);
+        let mut bots = [HeuristicBot::seeded(Difficulty::Medium, seed), HeuristicBot::seeded(Difficulty::Medium, seed + 1)];
+
+        while let Some(seat) = game.turn.filter(|_| !game.deck.is_empty()) {
+            let action = bots[seat as usize].choose(&game.view(seat)).unwrap();
+            game.apply(seat, action).unwrap();
+            game.next_turn();
+        }
+
+        if game.turn.is_some() {
+            assert!(solve(&game).is_some(), "seed {}", seed);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use bevy_test::cards::Seat;
+use bevy_test::env::*;
+use bevy_test::rules::*;
+
+fn first_legal(step: &Step) -> usize {
+    step.legal_mask.iter().position(|legal| *legal).unwrap()
+}
+
+// Plays the first legal action every turn until the hand is over
+fn play_out(env: &mut Env, mut step: Step) -> (Step, f32) {
+    let mut total = step.reward;
+    while !step.done {
+        step = env.step(first_legal(&step)).unwrap();
+        total += step.reward;
+    }
+    (step, total)
+}
+
+#[test]
+fn a_reset_starts_the_agent_on_its_turn() {
+    for seat in [Seat::Player, Seat::Opponent] {
+        let mut env = Env::new(GameRules::default(), seat, "hard").unwrap();
+        let step = env.reset(3);
+
+        assert_eq!(env.game().turn, Some(seat));
+        assert_eq!(step.observation.len(), OBSERVATION_SIZE);
+        assert_eq!(step.legal_mask.len(), ACTION_COUNT);
+        assert!(!step.done);
+    }
+}
+
+#[test]
+fn the_mask_matches_the_legal_actions() {
+    let mut env = Env::new(GameRules::default(), Seat::Player, "medium").unwrap();
+    env.reset(5);
+    let view = env.view();
+    let mask = legal_mask(&view);
+
+    for action in view.legal_actions() {
+        let index = action_index(&view, action).unwrap();
+        assert!(mask[index]);
+        // Cards of the same type share an index, so the action found may be a twin
+        let found = index_action(&view, index).unwrap();
+        assert_eq!(action_index(&view, found), Some(index));
+    }
+    for index in (0..ACTION_COUNT).filter(|index| !mask[*index]) {
+        assert!(index_action(&view, index).is_none());
+    }
+}
+
+#[test]
+fn rewards_add_up_to_the_final_margin() {
+    for seat in [Seat::Player, Seat::Opponent] {
+        let mut env = Env::new(GameRules::default(), seat, "hard").unwrap();
+        let step = env.reset(11);
+        let (last, total) = play_out(&mut env, step);
+
+        let game = env.game();
+        let margin = game.hand_points(seat) - game.hand_points(seat.other());
+        assert!((total - margin as f32 / REWARD_SCALE).abs() < 1e-3);
+        assert!(last.legal_mask.iter().all(|legal| !legal));
+        assert_eq!(env.step(0).unwrap_err(), RuleError::NotYourTurn);
+    }
+}
+
+#[test]
+fn an_illegal_action_changes_nothing() {
+    let mut env = Env::new(GameRules::default(), Seat::Player, "hard").unwrap();
+    let step = env.reset(2);
+    let illegal = step.legal_mask.iter().position(|legal| !legal).unwrap();
+    let before = env.view();
+
+    assert_eq!(env.step(illegal).unwrap_err(), RuleError::Illegal);
+    assert_eq!(encode_observation(&env.view()), encode_observation(&before));
+}
+
+#[test]
+fn the_same_seed_deals_the_same_hand() {
+    let mut a = Env::new(GameRules::default(), Seat::Player, "hard").unwrap();
+    let mut b = Env::new(GameRules::default(), Seat::Player, "hard").unwrap();
+
+    assert_eq!(a.reset(9).observation, b.reset(9).observation);
+    assert!(Env::new(GameRules::default(), Seat::Player, "nobody").is_none());
+}
+
+#[test]
+fn vectorised_envs_play_like_single_ones_and_deal_again_when_done() {
+    let count = 4;
+    let mut envs = VecEnv::new(count, GameRules::default(), Seat::Player, "hard").unwrap();
+    let mut singles: Vec<Env> = (0..count).map(|_| Env::new(GameRules::default(), Seat::Player, "hard").unwrap()).collect();
+
+    let mut steps = envs.reset(100);
+    for (index, env) in singles.iter_mut().enumerate() {
+        assert_eq!(env.reset(100 + index as u64).observation, steps[index].observation);
+    }
+
+    while !steps.iter().any(|step| step.done) {
+        let actions: Vec<usize> = steps.iter().map(first_legal).collect();
+        steps = envs.step(&actions).into_iter().map(Result::unwrap).collect();
+
+        for (index, step) in steps.iter().enumerate() {
+            let single = singles[index].step(actions[index]).unwrap();
+            assert_eq!(step.reward, single.reward);
+            assert_eq!(step.done, single.done);
+            if !step.done {
+                assert_eq!(step.observation, single.observation);
+            }
+        }
+    }
+
+    // The first hand to finish is dealt again with the next seed
+    let (index, step) = steps.iter().enumerate().find(|(_, step)| step.done).unwrap();
+    assert_eq!(step.observation, singles[index].reset(100 + count as u64).observation);
+}
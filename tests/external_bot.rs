@@ -0,0 +1,113 @@
+mod harness;
+
+use std::time::{Duration, Instant};
+use bevy::prelude::Entity;
+use bevy_test::ai::bot::Bot;
+use bevy_test::ai::external::ExternalBot;
+use bevy_test::ai::random::RandomBot;
+use bevy_test::cards::*;
+use bevy_test::rules::*;
+use bevy_test::sim::{play_hand, play_match};
+use harness::shuffled_game;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+const TIMEOUT: Duration = Duration::from_millis(200);
+
+// Writes `script` out so the bot can be started as `sh <path>`
+fn script(name: &str, script: &str) -> String {
+    let path = std::env::temp_dir().join(format!("millebornes_{}_{}.sh", name, std::process::id()));
+    std::fs::write(&path, script).unwrap();
+    format!("sh {}", path.display())
+}
+
+fn opening_view(seed: u64) -> bevy_test::view::PlayerView {
+    let game = shuffled_game(&mut StdRng::seed_from_u64(seed));
+    game.view(game.turn.unwrap())
+}
+
+#[test]
+fn the_external_bot_plays_what_the_process_answers() {
+    // Always discards the last card offered
+    let command = script("discarder", r#"sed -u -n 's/^{"type":"turn","id":\([0-9]*\).*\({"Discard":{"card":[0-9]*}}\)\]}$/{"id":\1,"action":\2}/p'"#);
+    let mut bot = ExternalBot::new(&command, Duration::from_secs(5));
+    let view = opening_view(1);
+
+    assert_eq!(bot.choose(&view), view.legal_actions().last().copied());
+    assert_eq!(bot.choose(&view), view.legal_actions().last().copied());
+}
+
+#[test]
+fn a_silent_bot_is_moved_for_after_the_timeout() {
+    let command = script("silent", "cat > /dev/null");
+    let mut bot = ExternalBot::new(&command, TIMEOUT);
+    let view = opening_view(2);
+
+    let started = Instant::now();
+    let action = bot.choose(&view).unwrap();
+
+    assert!(view.legal_actions().contains(&action));
+    assert!(started.elapsed() >= TIMEOUT);
+}
+
+#[test]
+fn illegal_answers_are_replaced_with_legal_moves() {
+    // Plays a card it doesn't hold
+    let command = script("cheat", r#"sed -u -n 's/^{"type":"turn","id":\([0-9]*\).*$/{"id":\1,"action":{"Discard":{"card":1}}}/p'"#);
+    let mut bot = ExternalBot::new(&command, Duration::from_secs(5));
+    let view = opening_view(3);
+
+    let action = bot.choose(&view).unwrap();
+
+    assert!(view.legal_actions().contains(&action));
+    assert_ne!(action, Action::Discard { card: bevy::prelude::Entity::from_bits(1) });
+}
+
+#[test]
+fn a_missing_program_still_gets_a_game() {
+    let mut bot = ExternalBot::new("no-such-millebornes-bot", TIMEOUT);
+    let mut game = shuffled_game(&mut StdRng::seed_from_u64(4));
+
+    while let Some(seat) = game.turn {
+        let action = bot.choose(&game.view(seat)).unwrap();
+        game.apply(seat, action).unwrap();
+        game.next_turn();
+    }
+
+    assert!(game.hand_points(Seat::Player) + game.hand_points(Seat::Opponent) > 0);
+}
+
+#[test]
+fn the_external_bot_never_sees_the_other_seats_draws() {
+    // Keeps a copy of everything it is sent and always discards
+    let log = std::env::temp_dir().join(format!("millebornes_sent_{}.log", std::process::id()));
+    let command = script("recorder", &format!(
+        r#"tee {} | sed -u -n 's/^{{"type":"turn","id":\([0-9]*\).*\({{"Discard":{{"card":[0-9]*}}}}\)\]}}$/{{"id":\1,"action":\2}}/p'"#,
+        log.display()));
+    let mut bot = ExternalBot::new(&command, Duration::from_secs(5));
+    let mut opponent = RandomBot::seeded(5);
+
+    play_hand(&GameRules::default(), &mut bot, &mut opponent, &mut StdRng::seed_from_u64(5), None).unwrap();
+    drop(bot);
+
+    let hidden = serde_json::to_value(Entity::PLACEHOLDER).unwrap();
+    let draws: Vec<serde_json::Value> = std::fs::read_to_string(&log).unwrap().lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .filter_map(|sent| sent["event"].get("CardDrawn").cloned())
+        .collect();
+    let (ours, theirs): (Vec<_>, Vec<_>) = draws.iter().partition(|drawn| drawn["seat"] == "Player");
+
+    assert!(!theirs.is_empty());
+    assert!(theirs.iter().all(|drawn| drawn["card"] == hidden));
+    assert!(ours.iter().all(|drawn| drawn["card"] != hidden));
+}
+
+#[test]
+fn matches_stay_repeatable_once_the_bot_is_moved_for() {
+    let play = || play_match(&GameRules::default(), "external:no-such-millebornes-bot", "easy", 6).unwrap();
+    let (first, second) = (play(), play());
+
+    assert_eq!(first.player_score, second.player_score);
+    assert_eq!(first.opponent_score, second.opponent_score);
+    assert_eq!(first.turns(), second.turns());
+}
@@ -0,0 +1,268 @@
+// Drives the rules plugin headlessly so whole turns can be scripted from tests
+#![allow(dead_code)]
+
+use std::time::{Duration, Instant};
+use bevy::prelude::*;
+use bevy_test::cards::*;
+use bevy_test::constants::*;
+use bevy_test::events::*;
+use bevy_test::invariants::card_violations;
+use bevy_test::millebornes::*;
+use bevy_test::rules::*;
+use bevy_test::sim::shuffled_deck;
+use rand::rngs::StdRng;
+
+// Guards against a state machine that never settles
+const MAX_FRAMES: usize = 16;
+const BOT_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Everything of one event type the game has sent since the harness was built
+#[derive(Resource)]
+pub struct EventLog<E: Event>(pub Vec<E>);
+
+fn record<E: Event + Clone>(mut reader: EventReader<E>, mut log: ResMut<EventLog<E>>) {
+    log.0.extend(reader.iter().cloned());
+}
+
+fn add_recorder<E: Event + Clone>(app: &mut App) {
+    app.insert_resource(EventLog::<E>(Vec::new()))
+        .add_systems(Last, record::<E>);
+}
+
+// `deck` dealt out on the pure rules core, with the player to move
+fn dealt(deck: Vec<PileCard>) -> Game {
+    let mut game = Game::new(deck, &GameRules::default());
+    game.deal(6);
+    game.next_turn();
+    game
+}
+
+// A whole shuffled deck dealt out, as `sim` deals it
+pub fn shuffled_game(rng: &mut StdRng) -> Game {
+    dealt(shuffled_deck(rng))
+}
+
+// A dealt hand with the player to move, the deck only holds the cards needed
+pub fn game_with(player: &[SubType], opponent: &[SubType], draws: &[SubType]) -> Game {
+    let mut order = Vec::new();
+    for i in 0..6 {
+        order.push(player[i]);
+        order.push(opponent[i]);
+    }
+    order.extend_from_slice(draws);
+
+    let mut deck: Vec<PileCard> = order.iter().enumerate()
+        .map(|(index, sub_type)| PileCard { entity: Entity::from_raw(index as u32), sub_type: *sub_type })
+        .collect();
+    deck.reverse();
+    dealt(deck)
+}
+
+pub struct Harness {
+    pub app: App
+}
+
+impl Harness {
+    pub fn new() -> Self {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, MilleBornesRules));
+
+        add_recorder::<CardDrawn>(&mut app);
+        add_recorder::<CardPlayed>(&mut app);
+        add_recorder::<HazardApplied>(&mut app);
+        add_recorder::<SafetyPlayed>(&mut app);
+        add_recorder::<CoupFourre>(&mut app);
+        add_recorder::<CardDiscarded>(&mut app);
+        add_recorder::<HandEnded>(&mut app);
+        add_recorder::<MatchEnded>(&mut app);
+
+        // Run Startup so the cards exist and we settle in the menu
+        app.update();
+
+        Self { app }
+    }
+
+    // Start a hand with a random deck and run until the first turn
+    pub fn start(&mut self) {
+        self.setup();
+        self.settle();
+    }
+
+    // Start a hand with `player` and `opponent` as the dealt hands, followed by `draws` in draw order
+    pub fn start_with(&mut self, player: &[SubType], opponent: &[SubType], draws: &[SubType]) {
+        let hand_size = self.app.world.resource::<GameRules>().hand_size as usize;
+        assert!(player.len() <= hand_size && opponent.len() <= hand_size);
+
+        self.setup();
+
+        // The deal alternates player then opponent, padding hands from the rest of the deck
+        let mut order = Vec::new();
+        for i in 0..hand_size {
+            order.push(player.get(i).copied());
+            order.push(opponent.get(i).copied());
+        }
+        order.extend(draws.iter().map(|draw| Some(*draw)));
+        self.stack_deck(&order);
+
+        self.settle();
+    }
+
+    fn setup(&mut self) {
+        self.app.world.resource_mut::<NextState<GameState>>().set(GameState::SetupGame);
+        self.app.update();
+    }
+
+    // Reorder the draw pile so the given cards come off the top in order, `None` takes any other card
+    fn stack_deck(&mut self, order: &[Option<SubType>]) {
+        let mut remaining: Vec<PileCard> = self.game().deck.clone();
+        let mut top = Vec::new();
+
+        for wanted in order {
+            let index = match wanted {
+                Some(sub_type) => remaining.iter()
+                    .position(|card| card.sub_type == *sub_type)
+                    .unwrap_or_else(|| panic!("No {:?} left to stack", sub_type)),
+                None => remaining.iter()
+                    .position(|card| !order.contains(&Some(card.sub_type)))
+                    .unwrap_or(0),
+            };
+            top.push(remaining.remove(index));
+        }
+
+        // Cards are drawn from the end of the deck
+        top.reverse();
+        remaining.extend(top);
+
+        self.app.world.resource_mut::<Game>().deck = remaining;
+    }
+
+    // Update until the game is waiting on a seat or the hand is over
+    pub fn settle(&mut self) {
+        for _ in 0..MAX_FRAMES {
+            self.app.update();
+            self.assert_cards_conserved();
+            if self.state() == GameState::DuringTurn && self.turn().is_some() || self.state() == GameState::EndOfHand {
+                return;
+            }
+        }
+
+        panic!("Game did not settle, stuck in {:?}", self.state());
+    }
+
+    // Update until it is `seat`'s turn, for when bots are taking the turns before it.
+    // Bots think on another thread, so give them however many frames they need to play.
+    pub fn wait_for_turn(&mut self, seat: Seat) {
+        let started = Instant::now();
+        while started.elapsed() < BOT_TIMEOUT {
+            self.app.update();
+            self.assert_cards_conserved();
+            if self.state() == GameState::DuringTurn && self.turn() == Some(seat) {
+                return;
+            }
+        }
+
+        panic!("{:?} never got a turn, stuck in {:?}", seat, self.state());
+    }
+
+    // Update until the hand is over, for when bots are taking the turns
+    pub fn play_out(&mut self, max_frames: usize) {
+        for _ in 0..max_frames {
+            self.app.update();
+            self.assert_cards_conserved();
+            if self.state() == GameState::EndOfHand {
+                return;
+            }
+        }
+
+        panic!("Hand did not finish, stuck in {:?}", self.state());
+    }
+
+    pub fn assert_cards_conserved(&mut self) {
+        let violations = card_violations(&mut self.app.world);
+        assert!(violations.is_empty(), "Card conservation broken:\n{}",
+            violations.iter().map(|violation| violation.to_string()).collect::<Vec<_>>().join("\n"));
+    }
+
+    pub fn state(&self) -> GameState {
+        *self.app.world.resource::<State<GameState>>().get()
+    }
+
+    pub fn turn(&self) -> Option<Seat> {
+        match self.app.world.resource::<State<TurnState>>().get() {
+            TurnState::PlayerTurn => Some(Seat::Player),
+            TurnState::OpponentTurn => Some(Seat::Opponent),
+            TurnState::NoTurn => None,
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        self.app.world.resource::<Game>()
+    }
+
+    pub fn score(&self) -> &Score {
+        self.app.world.resource::<Score>()
+    }
+
+    pub fn tableau(&self, seat: Seat) -> &Tableau {
+        self.game().tableau(seat)
+    }
+
+    pub fn sub_type(&self, card: Entity) -> SubType {
+        *self.app.world.get::<SubType>(card).unwrap()
+    }
+
+    pub fn hand(&mut self, seat: Seat) -> Vec<Entity> {
+        let mut hand: Vec<Entity> = match seat {
+            Seat::Player => self.app.world.query_filtered::<Entity, With<PlayerHand>>().iter(&self.app.world).collect(),
+            Seat::Opponent => self.app.world.query_filtered::<Entity, With<OpponentHand>>().iter(&self.app.world).collect(),
+        };
+        hand.sort();
+        hand
+    }
+
+    pub fn hand_sub_types(&mut self, seat: Seat) -> Vec<SubType> {
+        self.hand(seat).iter().map(|card| self.sub_type(*card)).collect()
+    }
+
+    // Find a card of the given type in a seat's hand
+    pub fn card_in_hand(&mut self, seat: Seat, sub_type: SubType) -> Entity {
+        self.hand(seat).into_iter()
+            .find(|card| self.sub_type(*card) == sub_type)
+            .unwrap_or_else(|| panic!("{:?} has no {:?} in hand", seat, sub_type))
+    }
+
+    // Ask the rules to take an action and run until the next decision.
+    // Returns whether the rules accepted the request.
+    pub fn request(&mut self, seat: Seat, action: Action) -> bool {
+        self.app.world.send_event(PlayRequest { seat, action });
+        self.app.update();
+
+        let accepted = self.app.world.resource::<NextState<GameState>>().0 == Some(GameState::NextTurn);
+        if accepted {
+            self.settle();
+        }
+        accepted
+    }
+
+    // Play a card of the given type, using whichever play or response the rules offer for it
+    pub fn play(&mut self, seat: Seat, sub_type: SubType) -> bool {
+        let card = self.card_in_hand(seat, sub_type);
+        let action = self.game().legal_actions(seat).into_iter()
+            .find(|action| action.card() == card && !matches!(action, Action::Discard { .. }))
+            .unwrap_or_else(|| {
+                let (target, pile) = play_target(seat, sub_type);
+                Action::Play { card, target, pile }
+            });
+
+        self.request(seat, action)
+    }
+
+    pub fn discard(&mut self, seat: Seat, sub_type: SubType) -> bool {
+        let card = self.card_in_hand(seat, sub_type);
+        self.request(seat, Action::Discard { card })
+    }
+
+    pub fn events<E: Event>(&self) -> &[E] {
+        &self.app.world.resource::<EventLog<E>>().0
+    }
+}
@@ -0,0 +1,69 @@
+mod harness;
+
+use bevy_test::ai::belief::CardCounter;
+use bevy_test::ai::hint::hint;
+use bevy_test::cards::*;
+use bevy_test::rules::*;
+use harness::game_with;
+
+use SubType::*;
+
+const JUNK: [SubType; 6] = [TwentyFive, TwentyFive, Fifty, Fifty, SeventyFive, SeventyFive];
+
+fn hinted(game: &Game, seat: Seat) -> (SubType, Action, String) {
+    let view = game.view(seat);
+    let hint = hint(&view, &CardCounter::default()).unwrap();
+    let sub_type = view.hand.iter().find(|card| card.entity == hint.action.card()).unwrap().sub_type;
+    (sub_type, hint.action, hint.explanation)
+}
+
+#[test]
+fn the_hint_gets_a_stopped_car_rolling() {
+    let game = game_with(&[Roll, Fifty, Fifty, TwentyFive, Accident, Repairs], &JUNK, &[Fifty]);
+
+    let (sub_type, action, explanation) = hinted(&game, Seat::Player);
+
+    assert_eq!(sub_type, Roll);
+    assert!(matches!(action, Action::Play { target: Seat::Player, pile: Pile::Battle, .. }));
+    assert!(explanation.contains("Roll"), "{}", explanation);
+}
+
+fn play(game: &mut Game, seat: Seat, sub_type: SubType) {
+    let card = game.hand(seat).iter().find(|card| card.sub_type == sub_type).unwrap().entity;
+    let action = *game.legal_actions(seat).iter().find(|action| action.card() == card).unwrap();
+    assert!(!matches!(action, Action::Discard { .. }));
+    game.apply(seat, action).unwrap();
+    game.next_turn();
+}
+
+#[test]
+fn the_hint_points_out_a_coup_fourre() {
+    let mut game = game_with(
+        &[Roll, Accident, Fifty, Fifty, Fifty, Fifty],
+        &[Roll, DrivingAce, Fifty, Fifty, Fifty, Fifty],
+        &[Fifty, Fifty, Fifty, Fifty]);
+    play(&mut game, Seat::Player, Roll);
+    play(&mut game, Seat::Opponent, Roll);
+    play(&mut game, Seat::Player, Accident);
+
+    let (sub_type, action, explanation) = hinted(&game, Seat::Opponent);
+
+    assert_eq!(sub_type, DrivingAce);
+    assert!(matches!(action, Action::CoupFourre { .. }));
+    assert!(explanation.starts_with("Coup fourré"), "{}", explanation);
+}
+
+#[test]
+fn the_hint_throws_away_a_dead_card() {
+    let mut game = game_with(&[Stop, TwoHundred, TwoHundred, TwoHundred, Stop, Stop], &JUNK, &[Stop]);
+    // A third 200 can never be played
+    game.player.distance.push(game.player_hand[1]);
+    game.player.distance.push(game.player_hand[2]);
+    game.player_hand.drain(1..3);
+
+    let (sub_type, action, explanation) = hinted(&game, Seat::Player);
+
+    assert_eq!(sub_type, TwoHundred);
+    assert!(matches!(action, Action::Discard { .. }));
+    assert!(explanation.contains("can't help"), "{}", explanation);
+}
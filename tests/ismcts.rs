@@ -0,0 +1,112 @@
+mod harness;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use bevy_test::ai::belief::CardCounter;
+use bevy_test::ai::bot::Bot;
+use bevy_test::ai::heuristic::*;
+use bevy_test::ai::ismcts::*;
+use bevy_test::cards::*;
+use harness::shuffled_game;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+fn sorted(mut sub_types: Vec<SubType>) -> Vec<SubType> {
+    sub_types.sort_by_key(|sub_type| *sub_type as usize);
+    sub_types
+}
+
+#[test]
+fn determinizing_keeps_everything_the_seat_knows() {
+    let mut rng = StdRng::seed_from_u64(34);
+    let game = shuffled_game(&mut rng);
+    let view = game.view(Seat::Player);
+
+    let sampled = determinize(&view, &CardCounter::default(), &mut rng);
+    assert_eq!(sampled.player_hand, game.player_hand);
+    assert_eq!(sampled.opponent_hand.len(), game.opponent_hand.len());
+    assert_eq!(sampled.deck.len(), game.deck.len());
+    assert_eq!(sampled.turn, game.turn);
+
+    // The hidden cards are shuffled between the deck and the other hand, but never invented
+    let hidden = sampled.opponent_hand.iter().chain(&sampled.deck).map(|card| card.sub_type).collect();
+    let real = game.opponent_hand.iter().chain(&game.deck).map(|card| card.sub_type).collect();
+    assert_eq!(sorted(hidden), sorted(real));
+    assert!(sampled.opponent_hand.iter().all(|card| !game.opponent_hand.contains(card)));
+}
+
+#[test]
+fn the_search_picks_a_legal_action_within_its_iterations() {
+    let mut rng = StdRng::seed_from_u64(34);
+    let game = shuffled_game(&mut rng);
+    let view = game.view(Seat::Player);
+
+    let action = IsmctsBot::seeded(Budget::Iterations(100), 34).choose(&view).unwrap();
+    assert!(view.legal_actions().contains(&action));
+}
+
+#[test]
+fn the_search_stops_when_its_time_is_up() {
+    let mut rng = StdRng::seed_from_u64(34);
+    let game = shuffled_game(&mut rng);
+
+    let started = Instant::now();
+    IsmctsBot::seeded(Budget::Time(Duration::from_millis(50)), 34).choose(&game.view(Seat::Player)).unwrap();
+    assert!(started.elapsed() < Duration::from_millis(500));
+}
+
+#[test]
+fn the_search_stops_when_its_move_is_cancelled() {
+    let game = shuffled_game(&mut StdRng::seed_from_u64(34));
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let mut bot = IsmctsBot::seeded(Budget::Time(Duration::from_secs(60)), 34);
+    bot.cancel_with(cancelled.clone());
+
+    let cancelling = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        cancelled.store(true, Ordering::Relaxed);
+    });
+    let started = Instant::now();
+    bot.choose(&game.view(Seat::Player));
+    cancelling.join().unwrap();
+
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn a_search_with_no_budget_still_moves() {
+    let game = shuffled_game(&mut StdRng::seed_from_u64(35));
+    let view = game.view(Seat::Player);
+    let mut bot = IsmctsBot::seeded(Budget::Iterations(0), 35);
+
+    let action = bot.choose(&view).unwrap();
+
+    assert!(view.legal_actions().contains(&action));
+}
+
+#[test]
+fn the_search_beats_the_easy_bot() {
+    let mut rng = StdRng::seed_from_u64(34);
+    let mut search = IsmctsBot::seeded(Budget::Iterations(200), 34);
+    let mut easy = HeuristicBot::seeded(Difficulty::Easy, 34);
+    let (mut search_points, mut easy_points) = (0, 0);
+
+    for _ in 0..6 {
+        let mut game = shuffled_game(&mut rng);
+        while let Some(seat) = game.turn {
+            let view = game.view(seat);
+            let action = match seat {
+                Seat::Player => search.choose(&view),
+                Seat::Opponent => easy.choose(&view),
+            };
+            game.apply(seat, action.unwrap()).unwrap();
+            game.next_turn();
+        }
+
+        search_points += game.hand_points(Seat::Player);
+        easy_points += game.hand_points(Seat::Opponent);
+    }
+
+    assert!(search_points > easy_points, "{} vs {}", search_points, easy_points);
+}
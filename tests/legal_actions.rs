@@ -0,0 +1,189 @@
+mod harness;
+
+use bevy::prelude::*;
+use bevy_test::cards::*;
+use bevy_test::events::*;
+use bevy_test::rules::*;
+use harness::{game_with, shuffled_game};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use SubType::*;
+
+const JUNK: [SubType; 6] = [TwentyFive, TwentyFive, Fifty, Fifty, SeventyFive, SeventyFive];
+
+fn card(game: &Game, seat: Seat, sub_type: SubType) -> Entity {
+    game.hand(seat).iter().find(|card| card.sub_type == sub_type).unwrap().entity
+}
+
+fn play(game: &mut Game, seat: Seat, sub_type: SubType) -> Vec<GameEvent> {
+    let card = card(game, seat, sub_type);
+    let action = *game.legal_actions(seat).iter()
+        .find(|action| action.card() == card && !matches!(action, Action::Discard { .. }))
+        .unwrap();
+
+    let events = game.apply(seat, action).unwrap();
+    game.next_turn();
+    events
+}
+
+fn discard(game: &mut Game, seat: Seat, sub_type: SubType) {
+    let card = card(game, seat, sub_type);
+    game.apply(seat, Action::Discard { card }).unwrap();
+    game.next_turn();
+}
+
+fn playable(game: &Game, seat: Seat) -> Vec<SubType> {
+    let mut playable: Vec<SubType> = game.legal_actions(seat).iter()
+        .filter(|action| !matches!(action, Action::Discard { .. }))
+        .map(|action| game.hand(seat).iter().find(|card| card.entity == action.card()).unwrap().sub_type)
+        .collect();
+    playable.dedup();
+    playable
+}
+
+#[test]
+fn an_opening_hand_can_only_roll_or_play_safeties() {
+    let game = game_with(&[Roll, OneHundred, Accident, DrivingAce, SpeedLimit, Repairs], &JUNK, &[Stop]);
+
+    assert_eq!(playable(&game, Seat::Player), vec![Roll, DrivingAce, SpeedLimit]);
+
+    // Every card can always be discarded
+    let discards = game.legal_actions(Seat::Player).iter()
+        .filter(|action| matches!(action, Action::Discard { .. }))
+        .count();
+    assert_eq!(discards, 7);
+}
+
+#[test]
+fn only_the_seat_on_turn_has_actions() {
+    let game = game_with(&JUNK, &JUNK, &[Roll]);
+
+    assert!(!game.legal_actions(Seat::Player).is_empty());
+    assert!(game.legal_actions(Seat::Opponent).is_empty());
+}
+
+#[test]
+fn plays_name_their_target_pile() {
+    let mut game = game_with(&[Roll, Stop, SpeedLimit, Fifty, Fifty, Fifty], &[Roll, Fifty, Fifty, Fifty, Fifty, Fifty], &[Fifty, Fifty, Fifty]);
+    play(&mut game, Seat::Player, Roll);
+    play(&mut game, Seat::Opponent, Roll);
+
+    let actions = game.legal_actions(Seat::Player);
+    let stop = card(&game, Seat::Player, Stop);
+    let limit = card(&game, Seat::Player, SpeedLimit);
+    let fifty = card(&game, Seat::Player, Fifty);
+
+    assert!(actions.contains(&Action::Play { card: stop, target: Seat::Opponent, pile: Pile::Battle }));
+    assert!(actions.contains(&Action::Play { card: limit, target: Seat::Opponent, pile: Pile::Speed }));
+    assert!(actions.contains(&Action::Play { card: fifty, target: Seat::Player, pile: Pile::Distance }));
+}
+
+#[test]
+fn the_matching_safety_is_offered_as_a_coup_fourre() {
+    let mut game = game_with(&[Roll, Accident, Fifty, Fifty, Fifty, Fifty], &[Roll, DrivingAce, Fifty, Fifty, Fifty, Fifty], &[Fifty, Fifty, Fifty]);
+    play(&mut game, Seat::Player, Roll);
+    play(&mut game, Seat::Opponent, Roll);
+    play(&mut game, Seat::Player, Accident);
+
+    let ace = card(&game, Seat::Opponent, DrivingAce);
+    let actions = game.legal_actions(Seat::Opponent);
+    assert!(actions.contains(&Action::CoupFourre { card: ace }));
+    assert!(!actions.iter().any(|action| matches!(action, Action::Play { card, .. } if *card == ace)));
+
+    let events = game.apply(Seat::Opponent, Action::CoupFourre { card: ace }).unwrap();
+    assert!(events.iter().any(|event| matches!(event, GameEvent::CoupFourre(CoupFourre { hazard: Accident, .. }))));
+    assert_eq!(game.opponent.battle_top(), Roll);
+}
+
+#[test]
+fn a_coup_fourre_is_only_offered_straight_away() {
+    let mut game = game_with(&[Roll, Accident, Fifty, Fifty, Fifty, Fifty], &[Roll, DrivingAce, Fifty, Fifty, Fifty, Fifty], &[Fifty, Fifty, Fifty, Fifty]);
+    play(&mut game, Seat::Player, Roll);
+    play(&mut game, Seat::Opponent, Roll);
+    play(&mut game, Seat::Player, Accident);
+    discard(&mut game, Seat::Opponent, Fifty);
+    discard(&mut game, Seat::Player, Fifty);
+
+    let ace = card(&game, Seat::Opponent, DrivingAce);
+    let actions = game.legal_actions(Seat::Opponent);
+    assert!(!actions.contains(&Action::CoupFourre { card: ace }));
+    assert!(actions.contains(&Action::Play { card: ace, target: Seat::Opponent, pile: Pile::Safety }));
+}
+
+#[test]
+fn apply_refuses_anything_not_legal() {
+    let mut game = game_with(&[OneHundred, Fifty, Fifty, Fifty, Fifty, Fifty], &JUNK, &[Fifty]);
+
+    let hundred = card(&game, Seat::Player, OneHundred);
+    let theirs = game.hand(Seat::Opponent)[0].entity;
+
+    assert_eq!(game.apply(Seat::Opponent, Action::Discard { card: theirs }).err(), Some(RuleError::NotYourTurn));
+    assert_eq!(game.apply(Seat::Player, Action::Discard { card: theirs }).err(), Some(RuleError::NotInHand));
+    assert_eq!(game.apply(Seat::Player, Action::Play { card: hundred, target: Seat::Player, pile: Pile::Distance }).err(),
+               Some(RuleError::Illegal));
+    assert_eq!(game.hand(Seat::Player).len(), 7);
+}
+
+#[test]
+fn distance_respects_limits() {
+    let mut game = game_with(&[Roll, TwoHundred, TwoHundred, TwoHundred, SeventyFive, Fifty], &[Roll, SpeedLimit, Fifty, Fifty, Fifty, Fifty],
+                             &[TwentyFive, TwentyFive, TwentyFive, TwentyFive, TwentyFive, TwentyFive, TwentyFive]);
+    play(&mut game, Seat::Player, Roll);
+    discard(&mut game, Seat::Opponent, Fifty);
+    play(&mut game, Seat::Player, TwoHundred);
+    discard(&mut game, Seat::Opponent, Fifty);
+    play(&mut game, Seat::Player, TwoHundred);
+    discard(&mut game, Seat::Opponent, Fifty);
+
+    // Two 200s already, and another would be 600 which is fine but not allowed
+    assert_eq!(playable(&game, Seat::Player), vec![SeventyFive, Fifty, TwentyFive]);
+    discard(&mut game, Seat::Player, TwoHundred);
+
+    play(&mut game, Seat::Opponent, SpeedLimit);
+    assert_eq!(playable(&game, Seat::Player), vec![Fifty, TwentyFive]);
+}
+
+#[test]
+fn the_trip_must_be_exact() {
+    let mut game = game_with(&[Roll, TwoHundred, TwoHundred, OneHundred, OneHundred, SeventyFive], &JUNK,
+                             &[OneHundred, TwentyFive, TwentyFive, TwentyFive, TwentyFive, TwentyFive, TwentyFive,
+                               TwentyFive, TwentyFive, TwentyFive, TwentyFive, TwentyFive, TwentyFive, TwentyFive]);
+    for sub_type in [Roll, TwoHundred, TwoHundred, OneHundred, OneHundred, SeventyFive] {
+        play(&mut game, Seat::Player, sub_type);
+        let junk = game.hand(Seat::Opponent)[0].sub_type;
+        discard(&mut game, Seat::Opponent, junk);
+    }
+
+    // 100 would overshoot, only 25 lands exactly on 700
+    assert_eq!(game.player.miles(), 675);
+    assert_eq!(playable(&game, Seat::Player), vec![TwentyFive]);
+
+    play(&mut game, Seat::Player, TwentyFive);
+    assert!(game.is_hand_over());
+    assert_eq!(game.turn, None);
+}
+
+#[test]
+fn random_legal_play_always_finishes_the_hand() {
+    let mut rng = StdRng::seed_from_u64(26);
+
+    for _ in 0..20 {
+        let mut game = shuffled_game(&mut rng);
+
+        let mut turns = 0;
+        while let Some(seat) = game.turn {
+            let actions = game.legal_actions(seat);
+            let action = *actions.choose(&mut rng).unwrap();
+            game.apply(seat, action).unwrap();
+            game.next_turn();
+
+            assert_eq!(game.zones().len(), 106);
+            turns += 1;
+            assert!(turns < 500);
+        }
+
+        assert!(game.is_hand_over());
+    }
+}
@@ -0,0 +1,819 @@
+use std::io::{BufReader, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use bevy::prelude::*;
+use bevy_test::ai::bot::{Bot, ComputerPlayers, Seats};
+use bevy_test::ai::heuristic::{Difficulty, HeuristicBot};
+use bevy_test::cards::*;
+use bevy_test::constants::*;
+use bevy_test::events::*;
+use bevy_test::invariants::card_violations;
+use bevy_test::millebornes::{MilleBornesRules, Score};
+use bevy_test::net::client::{Connection, JoinServer, LobbyInfo, NetworkClient};
+use bevy_test::net::protocol::*;
+use bevy_test::net::server::{Server, ServerConfig};
+use bevy_test::rules::*;
+use bevy_test::view::{PlayerView, Viewpoint};
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+// Short matches so the tests don't take long
+fn config(bot: Option<&str>) -> ServerConfig {
+    ServerConfig {
+        game_rules: GameRules { winning_score: 1000, ..default() },
+        bot: bot.map(str::to_string),
+        bot_delay: Duration::ZERO,
+        grace: Duration::from_millis(200),
+        coaching: false,
+        max_connections: 8
+    }
+}
+
+fn host(config: ServerConfig) -> (SocketAddr, JoinHandle<Result<MatchEnded, String>>) {
+    let server = Server::bind("127.0.0.1:0", config).unwrap();
+    let address = server.local_addr().unwrap();
+    (address, thread::spawn(move || server.run()))
+}
+
+// A client speaking the protocol directly, with no app
+struct RawClient {
+    id: usize,
+    session: String,
+    writer: TcpStream,
+    reader: BufReader<TcpStream>
+}
+
+impl RawClient {
+    // Connected, with nothing said yet
+    fn raw(address: SocketAddr) -> Self {
+        let writer = TcpStream::connect(address).unwrap();
+        writer.set_read_timeout(Some(TIMEOUT)).unwrap();
+        let reader = BufReader::new(writer.try_clone().unwrap());
+        Self { id: 0, session: String::new(), writer, reader }
+    }
+
+    // Agrees a version and sends `first` without waiting to hear back
+    fn open(address: SocketAddr, first: ClientMessage) -> Self {
+        let mut client = Self::raw(address);
+        client.send(hello());
+        match client.receive() {
+            Some(ServerMessage::Hello { version }) => assert_eq!(version, PROTOCOL_VERSION),
+            other => panic!("expected a hello, got {:?}", other),
+        }
+        client.send(first);
+        client
+    }
+
+    fn connect(address: SocketAddr, name: &str) -> Self {
+        Self::open(address, ClientMessage::Join { name: name.into() })
+    }
+
+    fn rejoin(address: SocketAddr, session: &str) -> Self {
+        Self::open(address, ClientMessage::Rejoin { session: session.into() })
+    }
+
+    fn watch(address: SocketAddr, name: &str, coach: Option<Seat>) -> Self {
+        Self::open(address, ClientMessage::Watch { name: name.into(), coach })
+    }
+
+    // Everything sent to somebody watching once the match starts, until the server closes the game
+    fn look_on(&mut self) -> (Option<Seat>, Vec<ServerMessage>) {
+        let coach = loop {
+            match self.receive() {
+                Some(ServerMessage::Lobby { .. }) => continue,
+                Some(ServerMessage::Watching { coach, .. }) => break coach,
+                other => panic!("expected to start watching, got {:?}", other),
+            }
+        };
+
+        let mut messages = Vec::new();
+        loop {
+            match self.receive() {
+                Some(ServerMessage::Closed { .. }) | None => return (coach, messages),
+                Some(message) => messages.push(message),
+            }
+        }
+    }
+
+    // Joins the lobby, where the server sits us down if there's room
+    fn join(address: SocketAddr, name: &str) -> Self {
+        let mut client = Self::connect(address, name);
+        match client.receive() {
+            Some(ServerMessage::Joined { id, session }) => {
+                client.id = id;
+                client.session = session;
+            }
+            other => panic!("expected to join, got {:?}", other),
+        }
+        client
+    }
+
+    fn send(&mut self, message: ClientMessage) {
+        send(&mut self.writer, &message).unwrap();
+    }
+
+    fn receive(&mut self) -> Option<ServerMessage> {
+        receive(&mut self.reader).unwrap()
+    }
+
+    // The next error, skipping any lobbies sent meanwhile
+    fn error(&mut self) -> ProtocolError {
+        loop {
+            match self.receive() {
+                Some(ServerMessage::Error { error }) => return error,
+                Some(ServerMessage::Lobby { .. }) => continue,
+                other => panic!("expected an error, got {:?}", other),
+            }
+        }
+    }
+
+    // The next lobby the server describes
+    fn lobby(&mut self) -> Lobby {
+        match self.receive() {
+            Some(ServerMessage::Lobby { lobby }) => lobby,
+            other => panic!("expected the lobby, got {:?}", other),
+        }
+    }
+
+    // Says we're ready whenever the lobby has us sitting down and not ready, until the match
+    // starts, and then that we're ready for the first deal
+    fn start(&mut self) -> Seat {
+        loop {
+            match self.receive() {
+                Some(ServerMessage::Lobby { lobby }) => {
+                    let me = lobby.player(self.id).unwrap();
+                    if me.seat.is_some() && !me.ready {
+                        self.send(ClientMessage::Ready);
+                    }
+                }
+                Some(ServerMessage::Started { seat, .. }) => {
+                    self.send(ClientMessage::Ready);
+                    return seat;
+                }
+                other => panic!("expected the match to start, got {:?}", other),
+            }
+        }
+    }
+
+    // Plays with `bot` until the server closes the game, returning every update it was sent
+    fn play_out(&mut self, bot: &mut dyn Bot) -> (Vec<(PlayerView, Vec<GameEvent>)>, String) {
+        let mut updates = Vec::new();
+
+        loop {
+            match self.receive() {
+                Some(ServerMessage::Update { view, events } | ServerMessage::Resumed { view, events, .. }) => {
+                    if view.is_my_turn() {
+                        let action = bot.choose(&view).unwrap();
+                        self.send(ClientMessage::Play { action });
+                    }
+                    // The server may already have closed the game if that was the last hand
+                    if view.turn.is_none() {
+                        let _ = send(&mut self.writer, &ClientMessage::Ready);
+                    }
+                    updates.push((*view, events));
+                }
+                Some(ServerMessage::Closed { reason }) => return (updates, reason),
+                // `start` can answer a lobby that was already out of date, so a spare ready arrives after the deal
+                Some(ServerMessage::Error { error: ProtocolError::NotNow }) => continue,
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+    }
+
+    fn closed(&mut self) -> String {
+        loop {
+            match self.receive() {
+                Some(ServerMessage::Closed { reason }) => return reason,
+                Some(_) => continue,
+                None => panic!("the connection ended without a reason"),
+            }
+        }
+    }
+}
+
+// Nothing sent to `seat` names a card it shouldn't see yet
+fn assert_nothing_hidden_sent(seat: Seat, updates: &[(PlayerView, Vec<GameEvent>)]) {
+    for (_, events) in updates {
+        for event in events {
+            if let GameEvent::CardDrawn(drawn) = event {
+                assert_eq!(drawn.seat == seat, drawn.card != Entity::PLACEHOLDER, "{:?}", drawn);
+            }
+        }
+    }
+}
+
+#[test]
+fn a_client_plays_a_match_against_the_servers_bot() {
+    let (address, server) = host(config(Some("medium")));
+
+    let mut client = RawClient::join(address, "Ann");
+    let seat = client.start();
+    let (updates, reason) = client.play_out(&mut HeuristicBot::seeded(Difficulty::Medium, 1));
+
+    let ended = server.join().unwrap().unwrap();
+    assert_eq!(seat, Seat::Player);
+    assert_eq!(reason, "The match is over");
+    assert!(ended.player_score.max(ended.opponent_score) >= 1000);
+    assert!(updates.iter().all(|(view, _)| view.seat == seat));
+    assert_nothing_hidden_sent(seat, &updates);
+
+    // The final score comes before the game is closed
+    let (_, last) = updates.last().unwrap();
+    assert!(matches!(last.last(), Some(GameEvent::MatchEnded(sent)) if sent.winner == ended.winner && sent.player_score == ended.player_score));
+}
+
+#[test]
+fn the_server_keeps_listening_while_its_bot_waits_to_move() {
+    let (address, _server) = host(ServerConfig { bot_delay: Duration::from_secs(20), ..config(Some("medium")) });
+
+    let mut client = RawClient::join(address, "Ann");
+    client.start();
+    let view = loop {
+        match client.receive() {
+            Some(ServerMessage::Update { view, .. }) if view.is_my_turn() => break view,
+            Some(_) => continue,
+            None => panic!("the game closed before our turn"),
+        }
+    };
+    let action = *view.legal_actions().iter().find(|action| matches!(action, Action::Discard { .. })).unwrap();
+    client.send(ClientMessage::Play { action });
+
+    // Out of turn now, and told so straight away rather than once the bot has moved
+    let started = Instant::now();
+    client.send(ClientMessage::Play { action });
+    let error = loop {
+        match client.receive() {
+            Some(ServerMessage::Error { error }) => break error,
+            Some(ServerMessage::Update { view, .. }) => assert!(!view.is_my_turn()),
+            other => panic!("expected an error, got {:?}", other),
+        }
+    };
+    assert!(matches!(error, ProtocolError::Rule { .. }), "{:?}", error);
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn two_clients_play_each_other_and_only_see_their_own_hands() {
+    let (address, server) = host(config(None));
+
+    let clients = [RawClient::join(address, "Ann"), RawClient::join(address, "Bob")];
+    let players: Vec<_> = clients.into_iter().enumerate().map(|(index, mut client)| {
+        thread::spawn(move || {
+            let seat = client.start();
+            let (updates, _) = client.play_out(&mut HeuristicBot::seeded(Difficulty::Medium, index as u64));
+            (seat, updates)
+        })
+    }).collect();
+    let players: Vec<_> = players.into_iter().map(|player| player.join().unwrap()).collect();
+
+    assert!(server.join().unwrap().is_ok());
+    assert_eq!(players[0].0, Seat::Player);
+    assert_eq!(players[1].0, Seat::Opponent);
+    for (seat, updates) in &players {
+        assert_nothing_hidden_sent(*seat, updates);
+    }
+
+    // Both were told about the same game
+    let last = |index: usize| players[index].1.last().unwrap().0.clone();
+    assert_eq!(last(0).player.miles(), last(1).player.miles());
+    assert_eq!(last(0).opponent.miles(), last(1).opponent.miles());
+}
+
+#[test]
+fn the_lobby_shows_who_is_sitting_where() {
+    let (address, _server) = host(config(Some("random")));
+
+    let mut ann = RawClient::join(address, "Ann");
+    let lobby = ann.lobby();
+    assert_eq!(lobby.sitter(Seat::Player).as_deref(), Some("Ann"));
+    assert_eq!(lobby.sitter(Seat::Opponent).as_deref(), Some("Bot (random)"));
+
+    // With both seats taken a newcomer waits standing up
+    let mut bob = RawClient::join(address, "Bob");
+    let lobby = bob.lobby();
+    assert_eq!(lobby.players.len(), 2);
+    assert_eq!(lobby.player(bob.id).unwrap().seat, None);
+    assert_eq!(lobby.host().unwrap().name, "Ann");
+}
+
+#[test]
+fn only_the_host_can_change_the_bots_and_rules() {
+    let (address, _server) = host(config(None));
+
+    let mut ann = RawClient::join(address, "Ann");
+    ann.lobby();
+    let mut bob = RawClient::join(address, "Bob");
+    bob.lobby();
+    ann.lobby();
+
+    let short = GameRules { winning_score: 2500, ..default() };
+    bob.send(ClientMessage::SetRules { game_rules: short.clone() });
+    assert_eq!(bob.error(), ProtocolError::NotAllowed);
+    bob.send(ClientMessage::TakeSeat { seat: None });
+    let lobby = ann.lobby();
+    assert_eq!(lobby.game_rules, config(None).game_rules);
+    assert_eq!(lobby.player(bob.id).unwrap().seat, None);
+
+    ann.send(ClientMessage::SetRules { game_rules: short.clone() });
+    assert_eq!(ann.lobby().game_rules, short);
+    ann.send(ClientMessage::SetBot { seat: Seat::Opponent, bot: Some("easy".into()) });
+    assert_eq!(ann.lobby().bots, [None, Some("easy".to_string())]);
+
+    // A bot can't take a seat somebody is sitting in
+    ann.send(ClientMessage::SetBot { seat: Seat::Player, bot: Some("easy".into()) });
+    assert_eq!(ann.error(), ProtocolError::SeatTaken);
+    ann.send(ClientMessage::Ready);
+    assert_eq!(ann.lobby().bots, [None, Some("easy".to_string())]);
+}
+
+#[test]
+fn the_host_can_only_pick_the_built_in_bots() {
+    let (address, _server) = host(config(None));
+
+    let mut ann = RawClient::join(address, "Ann");
+    ann.lobby();
+    for bot in ["external:touch millebornes_was_run", "policy:/etc/passwd", "ismcts:100000000", "expert:100000000", "hard:nobody"] {
+        ann.send(ClientMessage::SetBot { seat: Seat::Opponent, bot: Some(bot.into()) });
+        assert_eq!(ann.error(), ProtocolError::UnknownBot, "{}", bot);
+    }
+
+    ann.send(ClientMessage::SetBot { seat: Seat::Opponent, bot: Some("hard:bully".into()) });
+    assert_eq!(ann.lobby().bots, [None, Some("hard:bully".to_string())]);
+}
+
+#[test]
+fn changing_the_lobby_means_everyone_has_to_be_ready_again() {
+    let (address, _server) = host(config(None));
+
+    let mut ann = RawClient::join(address, "Ann");
+    ann.lobby();
+    ann.send(ClientMessage::Ready);
+    assert!(ann.lobby().player(ann.id).unwrap().ready);
+
+    ann.send(ClientMessage::SetRules { game_rules: GameRules { miles: 1000, ..default() } });
+    assert!(!ann.lobby().player(ann.id).unwrap().ready);
+}
+
+#[test]
+fn anybody_standing_when_the_match_starts_is_turned_away() {
+    let (address, _server) = host(config(Some("random")));
+
+    let mut ann = RawClient::join(address, "Ann");
+    let mut bob = RawClient::join(address, "Bob");
+    ann.start();
+
+    assert_eq!(bob.closed(), "The game started without you");
+    assert_eq!(RawClient::connect(address, "Cat").closed(), "The game has already started");
+}
+
+#[test]
+fn leaving_the_lobby_frees_the_seat() {
+    let (address, _server) = host(config(None));
+
+    let mut ann = RawClient::join(address, "Ann");
+    ann.lobby();
+    let bob = RawClient::join(address, "Bob");
+    assert_eq!(ann.lobby().sitter(Seat::Opponent).as_deref(), Some("Bob"));
+    drop(bob);
+
+    let lobby = ann.lobby();
+    assert_eq!(lobby.players.len(), 1);
+    assert_eq!(lobby.sitter(Seat::Opponent), None);
+}
+
+#[test]
+fn a_dropped_player_gets_their_seat_back_with_what_they_missed() {
+    let (address, server) = host(ServerConfig { grace: TIMEOUT, ..config(Some("medium")) });
+
+    let mut ann = RawClient::join(address, "Ann");
+    let seat = ann.start();
+    let dealt = match ann.receive() {
+        Some(ServerMessage::Update { view, .. }) => view,
+        other => panic!("expected the deal, got {:?}", other),
+    };
+    let session = ann.session.clone();
+    drop(ann);
+
+    // The bot plays up to our turn and then the game waits for us
+    thread::sleep(Duration::from_millis(300));
+    let mut ann = RawClient::rejoin(address, &session);
+    let (resumed, missed) = match ann.receive() {
+        Some(ServerMessage::Resumed { seat: resumed_seat, score, view, events, .. }) => {
+            assert_eq!(resumed_seat, seat);
+            assert_eq!(score, Score::default());
+            (view, events)
+        }
+        other => panic!("expected to resume, got {:?}", other),
+    };
+    assert!(resumed.is_my_turn());
+    assert_eq!(missed.is_empty(), dealt.is_my_turn());
+    assert_nothing_hidden_sent(seat, &[(*resumed.clone(), missed)]);
+
+    // Carry on where we left off
+    let action = HeuristicBot::seeded(Difficulty::Medium, 1).choose(&resumed).unwrap();
+    ann.send(ClientMessage::Play { action });
+    let (updates, reason) = ann.play_out(&mut HeuristicBot::seeded(Difficulty::Medium, 1));
+    assert_eq!(reason, "The match is over");
+    assert_nothing_hidden_sent(seat, &updates);
+    assert!(server.join().unwrap().is_ok());
+}
+
+#[test]
+fn rejoining_needs_a_seat_to_go_back_to() {
+    let (address, _server) = host(config(Some("random")));
+
+    let mut ann = RawClient::join(address, "Ann");
+    ann.lobby();
+    let mut bob = RawClient::join(address, "Bob");
+    bob.lobby();
+    assert_eq!(RawClient::rejoin(address, "nonsense").closed(), "Your seat is gone");
+
+    // In the lobby a dropped connection gives up the seat straight away
+    let session = ann.session.clone();
+    drop(ann);
+    assert_eq!(bob.lobby().sitter(Seat::Player), None);
+    assert_eq!(RawClient::rejoin(address, &session).closed(), "Your seat is gone");
+}
+
+#[test]
+fn only_a_new_connection_can_rejoin() {
+    let (address, _server) = host(config(Some("random")));
+
+    let mut ann = RawClient::join(address, "Ann");
+    ann.lobby();
+    let session = ann.session.clone();
+    ann.send(ClientMessage::Rejoin { session: session.clone() });
+    assert_eq!(ann.error(), ProtocolError::NotAllowed);
+
+    // Nor can somebody watching take the seat over
+    let mut cal = RawClient::watch(address, "Cal", None);
+    cal.send(ClientMessage::Rejoin { session });
+    assert_eq!(cal.error(), ProtocolError::NotAllowed);
+
+    // Ann is still connected and keeps the seat
+    assert_eq!(ann.lobby().sitter(Seat::Player).as_deref(), Some("Ann"));
+}
+
+#[test]
+fn staying_away_from_a_match_ends_it_for_everyone() {
+    let (address, server) = host(config(None));
+
+    let mut ann = RawClient::join(address, "Ann");
+    let mut bob = RawClient::join(address, "Bob");
+    let starting = thread::spawn(move || {
+        bob.start();
+        bob
+    });
+    ann.start();
+    drop(starting.join().unwrap());
+
+    assert!(ann.closed().contains("left"));
+    assert!(server.join().unwrap().is_err());
+}
+
+#[test]
+fn the_port_is_free_once_the_match_is_over() {
+    let (address, server) = host(config(None));
+
+    drop(RawClient::join(address, "Ann"));
+
+    assert_eq!(server.join().unwrap().unwrap_err(), "Everybody left the lobby");
+    assert!(Server::bind(address, config(None)).is_ok());
+}
+
+#[test]
+fn somebody_watching_only_sees_the_table() {
+    let (address, server) = host(config(Some("medium")));
+
+    let mut ann = RawClient::join(address, "Ann");
+    ann.lobby();
+    let mut cat = RawClient::watch(address, "Cat", Some(Seat::Player));
+    let lobby = ann.lobby();
+    assert_eq!(lobby.watchers, vec![LobbyWatcher { name: "Cat".into(), coach: None }]);
+    assert_eq!(lobby.players.len(), 1);
+
+    // Nothing somebody watching asks for is done
+    cat.send(ClientMessage::SetRules { game_rules: GameRules { miles: 1000, ..default() } });
+    cat.send(ClientMessage::TakeSeat { seat: Some(Seat::Player) });
+    cat.send(ClientMessage::Ready);
+    for _ in 0..3 {
+        assert_eq!(cat.error(), ProtocolError::NotAllowed);
+    }
+    ann.send(ClientMessage::Ready);
+    let ready = ann.lobby();
+    assert_eq!(ready.game_rules, lobby.game_rules);
+    assert_eq!(ready.players.len(), 1);
+    assert!(ready.player(ann.id).unwrap().ready);
+
+    let watching = thread::spawn(move || cat.look_on());
+    ann.start();
+    ann.play_out(&mut HeuristicBot::seeded(Difficulty::Medium, 1));
+    let (coach, messages) = watching.join().unwrap();
+
+    assert!(server.join().unwrap().is_ok());
+    assert_eq!(coach, None);
+    assert!(!messages.is_empty());
+    for message in messages {
+        let ServerMessage::Watched { table, events } = message else { panic!("expected the table, got {:?}", message) };
+        assert!(table.player_hand_size <= 7 && table.opponent_hand_size <= 7);
+        for event in events {
+            if let GameEvent::CardDrawn(drawn) = event {
+                assert_eq!(drawn.card, Entity::PLACEHOLDER);
+            }
+        }
+    }
+}
+
+#[test]
+fn a_coach_is_sent_one_seats_updates() {
+    let (address, server) = host(ServerConfig { coaching: true, ..config(Some("medium")) });
+
+    let mut ann = RawClient::join(address, "Ann");
+    let mut cat = RawClient::watch(address, "Cat", Some(Seat::Opponent));
+    let watching = thread::spawn(move || cat.look_on());
+    ann.start();
+    ann.play_out(&mut HeuristicBot::seeded(Difficulty::Medium, 1));
+    let (coach, messages) = watching.join().unwrap();
+
+    assert!(server.join().unwrap().is_ok());
+    assert_eq!(coach, Some(Seat::Opponent));
+    let updates: Vec<(PlayerView, Vec<GameEvent>)> = messages.into_iter()
+        .map(|message| match message {
+            ServerMessage::Update { view, events } => (*view, events),
+            other => panic!("expected the seat's updates, got {:?}", other),
+        })
+        .collect();
+    // A seat can play out its whole hand once the deck runs dry, so only the deal has to show cards
+    assert!(updates.iter().all(|(view, _)| view.seat == Seat::Opponent));
+    assert!(!updates[0].0.hand.is_empty());
+    assert_nothing_hidden_sent(Seat::Opponent, &updates);
+}
+
+#[test]
+fn somebody_can_start_watching_during_the_match() {
+    let (address, _server) = host(ServerConfig { bot_delay: Duration::from_millis(50), ..config(Some("medium")) });
+
+    let mut ann = RawClient::join(address, "Ann");
+    ann.start();
+    assert!(matches!(ann.receive(), Some(ServerMessage::Update { .. })));
+
+    let mut cat = RawClient::watch(address, "Cat", None);
+    assert!(matches!(cat.receive(), Some(ServerMessage::Watching { coach: None, .. })));
+    match cat.receive() {
+        Some(ServerMessage::Watched { table, events }) => {
+            assert!(events.is_empty());
+            assert!(table.deck_size > 0);
+            assert_eq!(table.player_hand_size + table.opponent_hand_size, 13);
+        }
+        other => panic!("expected the table, got {:?}", other),
+    }
+}
+
+#[test]
+fn connections_over_the_limit_are_told_the_server_is_full() {
+    let (address, _server) = host(ServerConfig { max_connections: 2, ..config(Some("random")) });
+
+    let mut ann = RawClient::join(address, "Ann");
+    ann.lobby();
+    let cal = RawClient::watch(address, "Cal", None);
+    let mut turned_away = RawClient::raw(address);
+    assert!(matches!(turned_away.receive(), Some(ServerMessage::Error { error: ProtocolError::Full })));
+    assert!(turned_away.receive().is_none());
+
+    // Somebody leaving makes room again
+    drop(cal);
+    let started = Instant::now();
+    loop {
+        let mut dan = RawClient::raw(address);
+        dan.send(hello());
+        match dan.receive() {
+            Some(ServerMessage::Hello { .. }) => break,
+            Some(ServerMessage::Error { error: ProtocolError::Full }) if started.elapsed() < TIMEOUT => thread::sleep(Duration::from_millis(10)),
+            other => panic!("expected a hello, got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn a_client_that_stops_reading_is_hung_up_on() {
+    let (address, _server) = host(config(None));
+
+    let mut sam = RawClient::join(address, "Sam");
+    sam.lobby();
+    let mut ann = RawClient::join(address, "Ann");
+    ann.lobby();
+
+    // Every change to the rules sends everybody the lobby again, none of which Sam reads
+    let mut writer = sam.writer.try_clone().unwrap();
+    thread::spawn(move || {
+        let mut line = serde_json::to_string(&ClientMessage::SetRules { game_rules: GameRules::default() }).unwrap();
+        line.push('\n');
+        let spam = line.repeat(1000);
+        for _ in 0..100 {
+            if writer.write_all(spam.as_bytes()).is_err() {
+                return;
+            }
+        }
+    });
+
+    // Ann keeps hearing from the table, until it hangs up on Sam
+    while ann.lobby().players.len() > 1 {}
+    let mut rest = Vec::new();
+    let _ = sam.reader.read_to_end(&mut rest);
+}
+
+#[test]
+fn versions_are_agreed_on_the_newest_both_ends_speak() {
+    assert_eq!(negotiate(OLDEST_PROTOCOL_VERSION, PROTOCOL_VERSION + 3), Some(PROTOCOL_VERSION));
+    assert_eq!(negotiate(0, PROTOCOL_VERSION), Some(PROTOCOL_VERSION));
+    assert_eq!(negotiate(PROTOCOL_VERSION + 1, PROTOCOL_VERSION + 3), None);
+    assert_eq!(negotiate(0, OLDEST_PROTOCOL_VERSION - 1), None);
+}
+
+#[test]
+fn a_client_speaking_another_version_is_turned_away() {
+    let (address, _server) = host(config(Some("random")));
+
+    let mut client = RawClient::raw(address);
+    client.send(ClientMessage::Hello { oldest: PROTOCOL_VERSION + 1, newest: PROTOCOL_VERSION + 2 });
+
+    assert_eq!(client.error(), ProtocolError::Incompatible { oldest: OLDEST_PROTOCOL_VERSION, newest: PROTOCOL_VERSION });
+    assert_eq!(client.closed(), "Incompatible protocol version");
+}
+
+#[test]
+fn the_handshake_comes_first() {
+    let (address, _server) = host(config(Some("random")));
+
+    let mut client = RawClient::raw(address);
+    client.send(ClientMessage::Join { name: "Ann".into() });
+
+    assert_eq!(client.error(), ProtocolError::NoHandshake);
+    assert_eq!(client.closed(), "Expected a hello first");
+}
+
+#[test]
+fn malformed_lines_are_answered_and_oversized_ones_hung_up_on() {
+    let (address, _server) = host(config(Some("random")));
+
+    let mut client = RawClient::open(address, ClientMessage::Ready);
+    assert_eq!(client.error(), ProtocolError::NotAllowed);
+
+    writeln!(client.writer, "{{\"type\":\"join\",").unwrap();
+    assert!(matches!(client.error(), ProtocolError::Malformed { .. }));
+    writeln!(client.writer, "{{\"type\":\"teleport\"}}").unwrap();
+    assert!(matches!(client.error(), ProtocolError::Malformed { .. }));
+
+    // The connection carries on after a bad line
+    client.send(ClientMessage::Join { name: "".into() });
+    assert_eq!(client.error(), ProtocolError::BadName);
+    client.send(ClientMessage::Join { name: "A".repeat(MAX_NAME_LENGTH + 1) });
+    assert_eq!(client.error(), ProtocolError::BadName);
+    client.send(ClientMessage::Join { name: "Ann".into() });
+    assert!(matches!(client.receive(), Some(ServerMessage::Joined { .. })));
+
+    writeln!(client.writer, "{}", " ".repeat(MAX_MESSAGE_SIZE + 1)).unwrap();
+    assert_eq!(client.error(), ProtocolError::TooLarge);
+    assert_eq!(client.closed(), "Message too large");
+}
+
+#[test]
+fn plays_out_of_turn_or_with_cards_not_in_hand_are_refused() {
+    let (address, server) = host(config(None));
+
+    let mut ann = RawClient::join(address, "Ann");
+    let mut bob = RawClient::join(address, "Bob");
+    let some_card = Action::Discard { card: Entity::from_raw(0) };
+    ann.send(ClientMessage::Play { action: some_card });
+    assert_eq!(ann.error(), ProtocolError::NotNow);
+
+    let starting = thread::spawn(move || {
+        bob.start();
+        bob
+    });
+    ann.start();
+    let mut bob = starting.join().unwrap();
+    let first_view = |client: &mut RawClient| match client.receive() {
+        Some(ServerMessage::Update { view, .. }) => *view,
+        other => panic!("expected the deal, got {:?}", other),
+    };
+    let (ann_view, bob_view) = (first_view(&mut ann), first_view(&mut bob));
+    let (mut mover, view, mut waiter, waiting) = if ann_view.is_my_turn() {
+        (ann, ann_view, bob, bob_view)
+    }
+    else {
+        (bob, bob_view, ann, ann_view)
+    };
+
+    waiter.send(ClientMessage::Play { action: Action::Discard { card: waiting.hand[0].entity } });
+    assert_eq!(waiter.error(), ProtocolError::Rule { error: RuleError::NotYourTurn });
+    mover.send(ClientMessage::Play { action: Action::Discard { card: Entity::from_raw(999) } });
+    assert_eq!(mover.error(), ProtocolError::Rule { error: RuleError::NotInHand });
+    mover.send(ClientMessage::Ready);
+    assert_eq!(mover.error(), ProtocolError::NotNow);
+    mover.send(ClientMessage::SetRules { game_rules: GameRules::default() });
+    assert!(matches!(mover.error(), ProtocolError::NotNow | ProtocolError::NotAllowed));
+
+    // None of that touched the game, which carries on from the same place
+    let discard = view.legal_actions().into_iter().find(|action| matches!(action, Action::Discard { .. })).unwrap();
+    mover.send(ClientMessage::Play { action: discard });
+    let after = first_view(&mut mover);
+    assert_eq!(after.hand.len(), view.hand.len() - 1);
+    assert_eq!(after.deck_size, view.deck_size - 1);
+    drop((mover, waiter));
+    assert!(server.join().unwrap().is_err());
+}
+
+#[test]
+fn the_app_plays_a_whole_match_as_a_client() {
+    let (address, server) = host(config(Some("medium")));
+
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, MilleBornesRules, ComputerPlayers, NetworkClient))
+        .init_resource::<Viewpoint>()
+        .insert_resource(JoinServer { address: address.to_string(), name: "Ann".into(), watch: None });
+
+    let started = Instant::now();
+    while !app.world.contains_resource::<LobbyInfo>() {
+        assert!(started.elapsed() < TIMEOUT, "never reached the lobby");
+        app.update();
+    }
+    assert_eq!(*app.world.resource::<State<GameState>>().get(), GameState::Lobby);
+    assert!(app.world.resource::<LobbyInfo>().is_host());
+    app.world.resource_mut::<Connection>().send(&ClientMessage::Ready).unwrap();
+
+    while app.world.resource::<Score>().match_winner.is_none() {
+        assert!(started.elapsed() < TIMEOUT, "the match didn't finish");
+        app.update();
+
+        let state = *app.world.resource::<State<GameState>>().get();
+        let game = app.world.resource::<Game>().clone();
+        match state {
+            GameState::DuringTurn => {
+                let seat = app.world.resource::<Viewpoint>().0;
+                assert!(app.world.resource::<Seats>().is_human(seat));
+                assert!(app.world.resource::<Seats>().is_remote(seat.other()));
+                if let Some(action) = game.legal_actions(seat).first() {
+                    app.world.send_event(PlayRequest { seat, action: *action });
+                }
+            }
+            GameState::EndOfHand if app.world.resource::<Score>().match_winner.is_none() => {
+                assert!(card_violations(&mut app.world).is_empty(), "{:?}", card_violations(&mut app.world));
+                app.world.resource_mut::<NextState<GameState>>().set(GameState::SetupGame);
+            }
+            _ => thread::sleep(Duration::from_millis(1)),
+        }
+    }
+
+    let ended = server.join().unwrap().unwrap();
+    let score = app.world.resource::<Score>();
+    assert_eq!((score.player_score, score.opponent_score), (ended.player_score, ended.opponent_score));
+}
+
+#[test]
+fn the_app_watches_a_match_without_playing_in_it() {
+    let (address, server) = host(config(Some("medium")));
+
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, MilleBornesRules, ComputerPlayers, NetworkClient))
+        .init_resource::<Viewpoint>()
+        .add_event::<PlayRequest>()
+        .insert_resource(JoinServer { address: address.to_string(), name: "Cat".into(), watch: Some(None) });
+
+    let started = Instant::now();
+    while !app.world.contains_resource::<LobbyInfo>() {
+        assert!(started.elapsed() < TIMEOUT, "never reached the lobby");
+        app.update();
+    }
+    assert_eq!(app.world.resource::<LobbyInfo>().me, None);
+
+    let playing = thread::spawn(move || {
+        let mut ann = RawClient::join(address, "Ann");
+        ann.start();
+        ann.play_out(&mut HeuristicBot::seeded(Difficulty::Medium, 1));
+    });
+
+    while app.world.resource::<Score>().match_winner.is_none() {
+        assert!(started.elapsed() < TIMEOUT, "the match didn't finish");
+        app.update();
+
+        match *app.world.resource::<State<GameState>>().get() {
+            GameState::DuringTurn => {
+                assert!(app.world.resource::<Seats>().is_remote(Seat::Player));
+                assert!(app.world.resource::<Seats>().is_remote(Seat::Opponent));
+                assert!(app.world.resource::<Events<PlayRequest>>().is_empty());
+            }
+            GameState::EndOfHand if app.world.resource::<Score>().match_winner.is_none() => {
+                assert!(card_violations(&mut app.world).is_empty(), "{:?}", card_violations(&mut app.world));
+                app.world.resource_mut::<NextState<GameState>>().set(GameState::SetupGame);
+            }
+            _ => thread::sleep(Duration::from_millis(1)),
+        }
+    }
+
+    playing.join().unwrap();
+    let ended = server.join().unwrap().unwrap();
+    let score = app.world.resource::<Score>();
+    assert_eq!((score.player_score, score.opponent_score), (ended.player_score, ended.opponent_score));
+}
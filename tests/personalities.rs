@@ -0,0 +1,107 @@
+mod harness;
+
+use bevy_test::ai::heuristic::{Difficulty, HeuristicBot};
+use bevy_test::ai::personality::*;
+use bevy_test::cards::*;
+use bevy_test::rules::*;
+use bevy_test::sim::bot_named;
+use harness::game_with;
+
+use SubType::*;
+
+const DRAWS: [SubType; 10] = [TwentyFive; 10];
+
+fn play(game: &mut Game, seat: Seat, sub_type: SubType) {
+    let card = game.hand(seat).iter().find(|card| card.sub_type == sub_type).unwrap().entity;
+    let action = *game.legal_actions(seat).iter().find(|action| action.card() == card).unwrap();
+    game.apply(seat, action).unwrap();
+    game.next_turn();
+}
+
+// Both cars rolling, with the player to move holding `hand` after the Roll
+fn rolling_with(hand: [SubType; 5]) -> Game {
+    let mut player = vec![Roll];
+    player.extend(hand);
+    let mut game = game_with(&player, &[Roll, TwentyFive, TwentyFive, TwentyFive, TwentyFive, TwentyFive], &DRAWS);
+    play(&mut game, Seat::Player, Roll);
+    play(&mut game, Seat::Opponent, Roll);
+    game
+}
+
+fn chosen(game: &Game, difficulty: Difficulty, personality: &Personality) -> SubType {
+    let view = game.view(Seat::Player);
+    let action = HeuristicBot::seeded(difficulty, 0).with_personality(personality.clone()).best(&view).unwrap();
+    view.hand.iter().find(|card| card.entity == action.card()).unwrap().sub_type
+}
+
+fn shipped(name: &str) -> Personality {
+    Personalities::load(PERSONALITIES_FILE).unwrap().named(name).unwrap().clone()
+}
+
+#[test]
+fn the_shipped_personalities_load_after_the_standard_one() {
+    let personalities = Personalities::load(PERSONALITIES_FILE).unwrap();
+
+    assert_eq!(personalities.all[0], Personality::default());
+    assert!(personalities.all.len() > 1);
+    assert!(personalities.named("bully").is_some());
+    assert_eq!(personalities.chosen().name, "Standard");
+}
+
+#[test]
+fn a_missing_file_leaves_only_the_standard_personality() {
+    let personalities = Personalities::load_or_default("no/such/personalities.ron");
+
+    assert_eq!(personalities.all, vec![Personality::default()]);
+}
+
+#[test]
+fn weights_left_out_of_the_file_keep_their_standard_value() {
+    let personalities = Personalities::parse("[(name: \"Brute\", aggression: 2.0)]").unwrap();
+    let brute = personalities.named("Brute").unwrap();
+
+    assert_eq!(brute.aggression, 2.);
+    assert_eq!(brute.hoarding, Personality::default().hoarding);
+}
+
+#[test]
+fn an_aggressive_bot_slows_the_other_car_instead_of_driving() {
+    let game = rolling_with([OneHundred, SpeedLimit, TwentyFive, TwentyFive, TwentyFive]);
+
+    assert_eq!(chosen(&game, Difficulty::Medium, &Personality::default()), OneHundred);
+    assert_eq!(chosen(&game, Difficulty::Medium, &shipped("Bully")), SpeedLimit);
+}
+
+#[test]
+fn a_cautious_bot_keeps_the_safe_trip_open() {
+    let game = rolling_with([TwoHundred, OneHundred, TwentyFive, TwentyFive, TwentyFive]);
+
+    assert_eq!(chosen(&game, Difficulty::Medium, &Personality::default()), TwoHundred);
+    assert_eq!(chosen(&game, Difficulty::Medium, &shipped("Cautious")), OneHundred);
+}
+
+#[test]
+fn a_bot_that_does_not_hoard_plays_its_safeties_early() {
+    let game = rolling_with([PunctureProof, TwentyFive, TwentyFive, TwentyFive, TwentyFive]);
+
+    assert_eq!(chosen(&game, Difficulty::Hard, &Personality::default()), TwentyFive);
+    assert_eq!(chosen(&game, Difficulty::Hard, &shipped("Speedster")), PunctureProof);
+}
+
+#[test]
+fn a_keen_hoarder_holds_its_safeties_even_at_the_easier_levels() {
+    let game = rolling_with([PunctureProof, TwentyFive, TwentyFive, TwentyFive, TwentyFive]);
+
+    assert_eq!(chosen(&game, Difficulty::Medium, &Personality::default()), PunctureProof);
+    assert_eq!(chosen(&game, Difficulty::Medium, &shipped("Collector")), TwentyFive);
+}
+
+#[test]
+fn simulated_bots_can_be_given_a_personality() {
+    assert!(bot_named("hard:bully", 0).is_some());
+    assert!(bot_named("medium:Cautious", 0).is_some());
+    assert!(bot_named("expert:collector", 0).is_some());
+    assert!(bot_named("expert:50", 0).is_some());
+    assert!(bot_named("hard:nobody", 0).is_none());
+    assert!(bot_named("expert:nobody", 0).is_none());
+}
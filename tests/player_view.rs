@@ -0,0 +1,59 @@
+mod harness;
+
+use bevy_test::cards::*;
+use harness::Harness;
+
+use SubType::*;
+
+const JUNK: [SubType; 6] = [TwentyFive, TwentyFive, Fifty, Fifty, SeventyFive, SeventyFive];
+
+#[test]
+fn a_view_only_shows_its_own_hand() {
+    let mut harness = Harness::new();
+    harness.start();
+
+    let view = harness.game().view(Seat::Player);
+    assert_eq!(view.hand, harness.game().player_hand);
+    assert_eq!(view.hand_size(Seat::Player), 7);
+    assert_eq!(view.hand_size(Seat::Opponent), 6);
+    assert_eq!(view.deck_size, 106 - 13);
+    assert_eq!(view.discard_size(), 0);
+
+    for card in harness.game().opponent_hand.iter().chain(&harness.game().deck) {
+        assert!(!view.can_see(card.entity));
+    }
+}
+
+#[test]
+fn played_and_discarded_cards_are_public() {
+    let mut harness = Harness::new();
+    harness.start_with(&[Roll, Fifty, Fifty, Fifty, Fifty, Fifty], &JUNK, &[Fifty, Fifty]);
+
+    assert!(harness.play(Seat::Player, Roll));
+    assert!(harness.discard(Seat::Opponent, TwentyFive));
+
+    let view = harness.game().view(Seat::Player);
+    assert_eq!(view.tableau(Seat::Player).battle_top(), Roll);
+    assert_eq!(view.discard.len(), 1);
+    assert!(view.can_see(view.discard[0].entity));
+    assert!(view.can_see(view.tableau(Seat::Player).battle[0].entity));
+}
+
+#[test]
+fn a_view_offers_the_same_actions_as_the_game() {
+    let mut harness = Harness::new();
+    harness.start_with(
+        &[Roll, Accident, TwentyFive, TwentyFive, Fifty, Fifty],
+        &[Roll, DrivingAce, Fifty, Fifty, SeventyFive, SeventyFive],
+        &[Fifty, Fifty, Fifty, Fifty]);
+
+    assert!(harness.play(Seat::Player, Roll));
+    assert!(harness.play(Seat::Opponent, Roll));
+    assert!(harness.play(Seat::Player, Accident));
+
+    for seat in [Seat::Player, Seat::Opponent] {
+        let view = harness.game().view(seat);
+        assert_eq!(view.legal_actions(), harness.game().legal_actions(seat));
+    }
+    assert!(harness.game().view(Seat::Opponent).is_my_turn());
+}
@@ -0,0 +1,257 @@
+mod harness;
+
+use bevy_test::cards::*;
+use bevy_test::constants::*;
+use bevy_test::events::*;
+use bevy_test::invariants::*;
+use bevy_test::rules::*;
+use harness::Harness;
+
+use SubType::*;
+
+const JUNK: [SubType; 6] = [TwentyFive, TwentyFive, Fifty, Fifty, SeventyFive, SeventyFive];
+
+#[test]
+fn deal_gives_both_seats_a_hand_and_the_player_draws_first() {
+    let mut harness = Harness::new();
+    harness.start();
+
+    assert_eq!(harness.state(), GameState::DuringTurn);
+    assert_eq!(harness.turn(), Some(Seat::Player));
+    assert_eq!(harness.hand(Seat::Player).len(), 7);
+    assert_eq!(harness.hand(Seat::Opponent).len(), 6);
+    assert_eq!(harness.game().deck.len(), 106 - 13);
+    assert_eq!(harness.events::<CardDrawn>().len(), 13);
+}
+
+#[test]
+fn deck_positions_follow_the_draw_order() {
+    let mut harness = Harness::new();
+    harness.start();
+
+    let deck = harness.game().deck.clone();
+    for (index, card) in deck.iter().enumerate() {
+        assert_eq!(harness.app.world.get::<DeckPosition>(card.entity), Some(&DeckPosition(index)));
+    }
+
+    let dealt = harness.hand(Seat::Player)[0];
+    assert!(harness.app.world.get::<DeckPosition>(dealt).is_none());
+}
+
+#[test]
+fn distance_needs_a_roll() {
+    let mut harness = Harness::new();
+    harness.start_with(&[OneHundred, Roll, TwentyFive, TwentyFive, Fifty, Fifty], &JUNK, &[Fifty]);
+
+    assert!(!harness.play(Seat::Player, OneHundred));
+    assert_eq!(harness.turn(), Some(Seat::Player));
+
+    assert!(harness.play(Seat::Player, Roll));
+    assert_eq!(harness.tableau(Seat::Player).battle_top(), Roll);
+    assert_eq!(harness.turn(), Some(Seat::Opponent));
+
+    assert!(harness.discard(Seat::Opponent, TwentyFive));
+    assert!(harness.play(Seat::Player, OneHundred));
+    assert_eq!(harness.tableau(Seat::Player).miles(), 100);
+    assert_eq!(harness.events::<CardPlayed>().len(), 2);
+}
+
+#[test]
+fn hazards_land_on_the_opponent() {
+    let mut harness = Harness::new();
+    harness.start_with(
+        &[Roll, Accident, TwentyFive, TwentyFive, Fifty, Fifty],
+        &[Roll, Repairs, Roll, OneHundred, Fifty, Fifty],
+        &[Fifty, Fifty, Fifty, Fifty]);
+
+    assert!(harness.play(Seat::Player, Roll));
+    assert!(harness.play(Seat::Opponent, Roll));
+    assert!(harness.play(Seat::Player, Accident));
+
+    let opponent = harness.tableau(Seat::Opponent);
+    assert_eq!(opponent.battle_top(), Accident);
+    assert!(!opponent.is_rolling());
+    assert_eq!(harness.tableau(Seat::Player).battle_top(), Roll);
+
+    let applied = harness.events::<HazardApplied>();
+    assert_eq!(applied.len(), 1);
+    assert_eq!(applied[0].seat, Seat::Player);
+    assert_eq!(applied[0].target, Seat::Opponent);
+
+    assert!(!harness.play(Seat::Opponent, OneHundred));
+    assert!(!harness.play(Seat::Opponent, Roll));
+    assert!(harness.play(Seat::Opponent, Repairs));
+    assert!(harness.discard(Seat::Player, TwentyFive));
+    assert!(harness.play(Seat::Opponent, Roll));
+    assert!(harness.tableau(Seat::Opponent).is_rolling());
+}
+
+#[test]
+fn safeties_give_another_turn() {
+    let mut harness = Harness::new();
+    harness.start_with(&[DrivingAce, Roll, TwentyFive, TwentyFive, Fifty, Fifty], &JUNK, &[Fifty, Fifty]);
+
+    assert!(harness.play(Seat::Player, DrivingAce));
+    assert_eq!(harness.turn(), Some(Seat::Player));
+    assert_eq!(harness.hand(Seat::Player).len(), 7);
+    assert_eq!(harness.events::<SafetyPlayed>().len(), 1);
+    assert!(harness.tableau(Seat::Player).has_safety(DrivingAce));
+}
+
+#[test]
+fn a_safety_answering_the_last_hazard_is_a_coup_fourre() {
+    let mut harness = Harness::new();
+    harness.start_with(
+        &[Roll, Accident, TwentyFive, TwentyFive, Fifty, Fifty],
+        &[Roll, DrivingAce, Fifty, Fifty, SeventyFive, SeventyFive],
+        &[Fifty, Fifty, Fifty, Fifty]);
+
+    assert!(harness.play(Seat::Player, Roll));
+    assert!(harness.play(Seat::Opponent, Roll));
+    assert!(harness.play(Seat::Player, Accident));
+    assert!(harness.play(Seat::Opponent, DrivingAce));
+
+    let opponent = harness.tableau(Seat::Opponent);
+    assert_eq!(opponent.battle_top(), Roll);
+    assert_eq!(opponent.coup_fourres, 1);
+    assert_eq!(harness.events::<CoupFourre>().len(), 1);
+    assert!(harness.events::<SafetyPlayed>().is_empty());
+
+    // The accident was thrown away and the opponent goes again
+    assert_eq!(harness.game().discard.len(), 1);
+    assert_eq!(harness.turn(), Some(Seat::Opponent));
+}
+
+#[test]
+fn a_coup_fourre_is_not_replaced_from_the_deck() {
+    let mut harness = Harness::new();
+    harness.start_with(
+        &[Roll, Accident, TwentyFive, TwentyFive, Fifty, Fifty],
+        &[Roll, DrivingAce, Fifty, Fifty, SeventyFive, SeventyFive],
+        &[Fifty, Fifty, Fifty, Fifty]);
+
+    assert!(harness.play(Seat::Player, Roll));
+    assert!(harness.play(Seat::Opponent, Roll));
+    assert!(harness.play(Seat::Player, Accident));
+    let deck = harness.game().deck.len();
+    assert_eq!(harness.hand(Seat::Opponent).len(), 7);
+    assert!(harness.play(Seat::Opponent, DrivingAce));
+
+    // The coup fourré is the seat's play for the turn it has already drawn for,
+    // so the extra turn's draw is the only card it gets
+    assert_eq!(harness.game().deck.len(), deck - 1);
+    assert_eq!(harness.hand(Seat::Opponent).len(), 7);
+}
+
+#[test]
+fn discards_are_stacked_in_order() {
+    let mut harness = Harness::new();
+    harness.start_with(&JUNK, &JUNK, &[OneHundred]);
+
+    assert!(harness.discard(Seat::Player, TwentyFive));
+    assert!(harness.discard(Seat::Opponent, Fifty));
+
+    let discard = harness.game().discard.clone();
+    assert_eq!(discard.len(), 2);
+    assert_eq!(harness.app.world.get::<DiscardPosition>(discard[0].entity), Some(&DiscardPosition(0)));
+    assert_eq!(harness.app.world.get::<DiscardPosition>(discard[1].entity), Some(&DiscardPosition(1)));
+    assert!(harness.app.world.get::<DiscardPile>(discard[1].entity).is_some());
+    assert_eq!(harness.events::<CardDiscarded>().len(), 2);
+}
+
+#[test]
+fn completing_the_trip_ends_and_scores_the_hand() {
+    let mut harness = Harness::new();
+    harness.app.world.resource_mut::<GameRules>().miles = 200;
+    harness.start_with(&[Roll, TwoHundred, Fifty, Fifty, Fifty, Fifty], &JUNK, &[Fifty, Fifty, Fifty]);
+
+    assert!(harness.play(Seat::Player, Roll));
+    assert!(harness.discard(Seat::Opponent, TwentyFive));
+    assert!(harness.play(Seat::Player, TwoHundred));
+
+    assert_eq!(harness.state(), GameState::EndOfHand);
+    assert_eq!(harness.turn(), None);
+
+    let ended = harness.events::<HandEnded>();
+    assert_eq!(ended.len(), 1);
+    assert_eq!(ended[0].winner, Some(Seat::Player));
+    // Miles, trip completed and shutout, but not a safe trip
+    assert_eq!(ended[0].player_points, 200 + 400 + 500);
+    assert_eq!(ended[0].opponent_points, 0);
+    assert_eq!(harness.score().player_score, 1100);
+    assert!(harness.events::<MatchEnded>().is_empty());
+}
+
+#[test]
+fn reaching_the_winning_score_ends_the_match() {
+    let mut harness = Harness::new();
+    {
+        let mut rules = harness.app.world.resource_mut::<GameRules>();
+        rules.miles = 200;
+        rules.winning_score = 1000;
+    }
+    harness.start_with(&[Roll, OneHundred, OneHundred, Fifty, Fifty, Fifty], &JUNK, &[Fifty, Fifty, Fifty, Fifty]);
+
+    assert!(harness.play(Seat::Player, Roll));
+    assert!(harness.discard(Seat::Opponent, TwentyFive));
+    assert!(harness.play(Seat::Player, OneHundred));
+    assert!(harness.discard(Seat::Opponent, TwentyFive));
+    assert!(harness.play(Seat::Player, OneHundred));
+
+    let ended = harness.events::<MatchEnded>();
+    assert_eq!(ended.len(), 1);
+    assert_eq!(ended[0].winner, Seat::Player);
+    assert_eq!(harness.score().match_winner, Some(Seat::Player));
+}
+
+#[test]
+fn the_next_hand_starts_from_a_clean_table() {
+    let mut harness = Harness::new();
+    harness.app.world.resource_mut::<GameRules>().miles = 200;
+    harness.start_with(&[Roll, TwoHundred, Fifty, Fifty, Fifty, Fifty], &JUNK, &[Fifty, Fifty, Fifty]);
+
+    assert!(harness.play(Seat::Player, Roll));
+    assert!(harness.discard(Seat::Opponent, TwentyFive));
+    assert!(harness.play(Seat::Player, TwoHundred));
+    assert_eq!(harness.state(), GameState::EndOfHand);
+
+    harness.start();
+    assert_eq!(harness.turn(), Some(Seat::Player));
+    assert_eq!(harness.hand(Seat::Player).len(), 7);
+    assert_eq!(harness.tableau(Seat::Player).miles(), 0);
+    assert!(harness.game().discard.is_empty());
+    assert_eq!(harness.score().player_score, 1100);
+}
+
+#[test]
+fn conservation_reports_cards_outside_a_single_zone() {
+    let mut harness = Harness::new();
+    harness.start();
+    harness.assert_cards_conserved();
+
+    let card = harness.hand(Seat::Player)[0];
+    harness.app.world.entity_mut(card).insert(DiscardPile);
+    let other = harness.hand(Seat::Opponent)[0];
+    harness.app.world.entity_mut(other).remove::<OpponentHand>();
+
+    let violations = card_violations(&mut harness.app.world);
+    assert_eq!(violations.len(), 2);
+    assert!(violations.iter().any(|violation| matches!(violation,
+        CardViolation::ManyZones { card: found, .. } if *found == card)));
+    assert!(violations.iter().any(|violation| matches!(violation,
+        CardViolation::NoZone { card: found, .. } if *found == other)));
+}
+
+#[test]
+fn conservation_reports_missing_cards() {
+    let mut harness = Harness::new();
+    harness.start();
+
+    let card = harness.game().deck[0];
+    let sub_type = card.sub_type;
+    harness.app.world.despawn(card.entity);
+
+    let expected = DECK_COMPOSITION.iter().find(|(counted, _)| *counted == sub_type).unwrap().1;
+    let violations = card_violations(&mut harness.app.world);
+    assert_eq!(violations, vec![CardViolation::WrongCount { sub_type, expected, found: expected - 1 }]);
+}
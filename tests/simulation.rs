@@ -0,0 +1,54 @@
+use bevy::prelude::Entity;
+use bevy_test::rules::GameRules;
+use bevy_test::sim::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+#[test]
+fn the_same_seed_plays_the_same_match() {
+    let rules = GameRules::default();
+    let first = play_match(&rules, "medium", "easy", 7).unwrap();
+    let second = play_match(&rules, "medium", "easy", 7).unwrap();
+
+    assert_eq!(matches_csv(std::slice::from_ref(&first)), matches_csv(&[second]));
+    assert!(first.winner.is_some());
+    assert!(first.player_score.max(first.opponent_score) >= rules.winning_score);
+}
+
+#[test]
+fn card_ids_only_follow_the_shuffled_order() {
+    let deck = shuffled_deck(&mut StdRng::seed_from_u64(38));
+
+    // Numbered by where they ended up, so the id of a card in a hand gives nothing away
+    assert!(deck.iter().enumerate().all(|(index, card)| card.entity == Entity::from_raw(index as u32)));
+}
+
+#[test]
+fn the_summary_adds_up() {
+    let rules = GameRules::default();
+    let records: Vec<MatchRecord> = (0..6)
+        .map(|seed| if seed % 2 == 0 { play_match(&rules, "hard", "random", seed) } else { play_match(&rules, "random", "hard", seed) })
+        .map(Option::unwrap)
+        .collect();
+    let summary = summarise(&records);
+
+    assert_eq!(summary.matches, 6);
+    assert_eq!(summary.unfinished, 0);
+    assert_eq!(summary.hands, records.iter().map(|record| record.hands.len()).sum::<usize>());
+    assert_eq!(summary.bots.len(), 2);
+    assert_eq!(summary.bots.iter().map(|bot| bot.wins).sum::<usize>(), 6);
+    assert!(summary.bots.iter().all(|bot| bot.matches == 6));
+    assert!(summary.average_hand_turns > 0.);
+    assert_eq!(matches_csv(&records).lines().count(), 7);
+}
+
+#[test]
+fn bots_are_built_from_their_names() {
+    for name in ["random", "easy", "medium", "hard", "expert", "ismcts:10", "external:my-bot --fast"] {
+        assert!(bot_named(name, 0).is_some(), "{}", name);
+    }
+
+    for name in ["", "grandmaster", "ismcts:lots", "external:"] {
+        assert!(bot_named(name, 0).is_none(), "{}", name);
+    }
+}
@@ -0,0 +1,78 @@
+use bevy_test::rules::GameRules;
+use bevy_test::tournament::*;
+
+fn entrants(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn elo_moves_ratings_towards_the_result() {
+    assert_eq!(expected_score(1500., 1500.), 0.5);
+    assert_eq!(elo_update(1500., 1500., 1.), 1516.);
+    assert_eq!(elo_update(1500., 1500., 0.), 1484.);
+
+    // Beating a much weaker bot is worth very little
+    assert!(elo_update(1800., 1200., 1.) - 1800. < 1.);
+}
+
+#[test]
+fn a_round_robin_plays_every_pairing_from_both_seats() {
+    let tournament = Tournament { entrants: entrants(&["random", "easy", "hard"]), format: Format::RoundRobin, games: 2, seed: 5 };
+    let result = tournament.run(&GameRules::default());
+
+    assert_eq!(result.matches.len(), 12);
+    assert!(result.leaderboard.iter().all(|standing| standing.played == 8));
+    for (a, b) in [("random", "easy"), ("random", "hard"), ("easy", "hard")] {
+        let as_player = result.matches.iter().filter(|record| record.player == a && record.opponent == b).count();
+        let as_opponent = result.matches.iter().filter(|record| record.player == b && record.opponent == a).count();
+        assert_eq!((as_player, as_opponent), (2, 2));
+    }
+
+    assert_eq!(result.leaderboard[0].name, "hard");
+    assert_eq!(result.leaderboard[2].name, "random");
+}
+
+#[test]
+fn every_deal_is_played_from_both_seats() {
+    let tournament = Tournament { entrants: entrants(&["random", "easy"]), format: Format::RoundRobin, games: 3, seed: 2 };
+    let result = tournament.run(&GameRules::default());
+
+    assert_eq!(result.matches.len(), 6);
+    let mut seeds: Vec<u64> = result.matches.iter().map(|record| record.seed).collect();
+    for pair in result.matches.chunks(2) {
+        assert_eq!(pair[0].seed, pair[1].seed);
+        assert_eq!((&pair[0].player, &pair[0].opponent), (&pair[1].opponent, &pair[1].player));
+    }
+    seeds.dedup();
+    assert_eq!(seeds.len(), 3);
+}
+
+#[test]
+fn swiss_rounds_avoid_rematches() {
+    let tournament = Tournament {
+        entrants: entrants(&["random", "easy", "medium", "hard"]),
+        format: Format::Swiss { rounds: 3 },
+        games: 1,
+        seed: 9
+    };
+    let result = tournament.run(&GameRules::default());
+
+    // Three rounds of four bots is exactly one meeting per pairing
+    let mut pairings: Vec<(String, String)> = result.matches.iter()
+        .map(|record| if record.player < record.opponent { (record.player.clone(), record.opponent.clone()) } else { (record.opponent.clone(), record.player.clone()) })
+        .collect();
+    pairings.sort();
+    pairings.dedup();
+    assert_eq!(pairings.len(), 6);
+    assert_eq!(result.matches.len(), 12);
+}
+
+#[test]
+fn the_leaderboard_is_sorted_and_repeatable() {
+    let tournament = Tournament { entrants: entrants(&["medium", "random", "easy"]), format: Format::RoundRobin, games: 1, seed: 1 };
+    let first = tournament.run(&GameRules::default());
+    let second = tournament.run(&GameRules::default());
+
+    assert!(first.leaderboard.windows(2).all(|pair| pair[0].rating >= pair[1].rating));
+    assert_eq!(leaderboard_csv(&first.leaderboard), leaderboard_csv(&second.leaderboard));
+}
@@ -0,0 +1,111 @@
+use bevy_test::ai::policy::LinearPolicy;
+use bevy_test::cards::Seat;
+use bevy_test::env::*;
+use bevy_test::rules::GameRules;
+use bevy_test::sim::*;
+use bevy_test::training::*;
+
+const BOTH: [Seat; 2] = [Seat::Player, Seat::Opponent];
+
+fn hard_samples(matches: u64) -> Vec<Sample> {
+    (0..matches)
+        .flat_map(|seed| play_logged_match(&GameRules::default(), "hard", "hard", seed).unwrap().1)
+        .flat_map(|log| samples_from_log(&log, &BOTH))
+        .collect()
+}
+
+fn temp_file(name: &str) -> String {
+    let path = std::env::temp_dir().join(format!("{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_file(&path);
+    path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn every_logged_move_becomes_a_sample() {
+    let (record, logs) = play_logged_match(&GameRules::default(), "hard", "medium", 4).unwrap();
+    let log = &logs[0];
+    let samples = samples_from_log(log, &BOTH);
+    let hand = &record.hands[0];
+
+    assert_eq!(logs.len(), record.hands.len());
+    assert_eq!(samples.len(), hand.turns);
+    for sample in &samples {
+        assert_eq!(sample.observation.len(), OBSERVATION_SIZE);
+        assert!(sample.legal_mask[sample.action]);
+        let (mine, theirs) = match sample.seat {
+            Seat::Player => (hand.player_points, hand.opponent_points),
+            Seat::Opponent => (hand.opponent_points, hand.player_points),
+        };
+        assert_eq!(sample.margin, (mine - theirs) as f32 / REWARD_SCALE);
+        assert_eq!(sample.result, match hand.winner {
+            Some(winner) if winner == sample.seat => 1,
+            Some(_) => -1,
+            None => 0,
+        });
+    }
+
+    let player_only = samples_from_log(log, &[Seat::Player]);
+    assert!(player_only.iter().all(|sample| sample.seat == Seat::Player));
+    assert!(player_only.len() < samples.len());
+}
+
+#[test]
+fn samples_survive_the_file_format() {
+    let samples = hard_samples(1);
+    let mut bytes = Vec::new();
+    write_header(&mut bytes).unwrap();
+    for sample in &samples {
+        write_sample(&mut bytes, sample).unwrap();
+    }
+
+    assert_eq!(read_samples(&mut bytes.as_slice()).unwrap(), samples);
+    assert!(read_samples(&mut &b"not training data"[..]).is_err());
+}
+
+#[test]
+fn appending_writes_the_header_once() {
+    let path = temp_file("append.mbtd");
+    let samples = hard_samples(1);
+    let (first, second) = samples.split_at(samples.len() / 2);
+
+    append_samples(&path, first).unwrap();
+    append_samples(&path, second).unwrap();
+
+    assert_eq!(load_samples(&path).unwrap(), samples);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn nothing_is_appended_to_a_file_in_another_format() {
+    let samples = hard_samples(1);
+    let other = temp_file("other.txt");
+    std::fs::write(&other, "some notes").unwrap();
+    let older = temp_file("older.mbtd");
+    let mut header = b"MBTD".to_vec();
+    for value in [0u32, OBSERVATION_SIZE as u32, ACTION_COUNT as u32] {
+        header.extend(value.to_le_bytes());
+    }
+    std::fs::write(&older, &header).unwrap();
+
+    assert!(append_samples(&other, &samples).is_err());
+    assert!(append_samples(&older, &samples).is_err());
+    assert_eq!(std::fs::read(&other).unwrap(), b"some notes");
+    assert_eq!(std::fs::read(&older).unwrap(), header);
+    let _ = std::fs::remove_file(&other);
+    let _ = std::fs::remove_file(&older);
+}
+
+#[test]
+fn a_policy_trained_on_the_samples_can_be_played() {
+    let samples = hard_samples(3);
+    let policy = LinearPolicy::fit(&samples, 3, 0.01);
+    let path = temp_file("policy.json");
+    policy.save(&path).unwrap();
+
+    assert!(policy.accuracy(&samples) > 0.5, "{}", policy.accuracy(&samples));
+    assert_eq!(LinearPolicy::load(&path).unwrap(), policy);
+
+    let record = play_match(&GameRules::default(), &format!("policy:{}", path), "random", 1).unwrap();
+    assert_eq!(record.winner, Some(Seat::Player));
+    let _ = std::fs::remove_file(&path);
+}